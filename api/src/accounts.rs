@@ -0,0 +1,323 @@
+use chrono::Duration;
+use common::models::{EncryptedAccount, Exchange};
+use common::{Error, Result};
+use connectors::{
+    binance_account::BinanceAccountConnector, coinbase_account::CoinbaseAccountConnector,
+    http::HttpClient, signing::Credentials, AccountConnector,
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+// Version prefix of encrypted credentials, so the scheme can change later
+const CIPHER_VERSION: &str = "v1";
+const NONCE_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+
+/// Settings for connecting exchange accounts
+#[derive(Debug, Clone)]
+pub struct AccountConfig {
+    /// Key encrypting stored API credentials; accounts are disabled if unset
+    pub encryption_key: Option<[u8; 32]>,
+    /// Quote currencies fills are imported in, paired with each asset
+    pub quotes: Vec<String>,
+    /// Base URL of the Binance spot API, if not the public one
+    pub binance_url: Option<String>,
+    /// Base URL of the Coinbase Exchange API, if not the public one
+    pub coinbase_url: Option<String>,
+}
+
+impl Default for AccountConfig {
+    fn default() -> Self {
+        Self {
+            encryption_key: None,
+            quotes: ["USD", "USDT", "USDC", "EUR"].map(String::from).to_vec(),
+            binance_url: None,
+            coinbase_url: None,
+        }
+    }
+}
+
+impl AccountConfig {
+    pub fn from_env() -> Result<Self> {
+        let default = Self::default();
+
+        let encryption_key = match std::env::var("ACCOUNT_ENCRYPTION_KEY") {
+            Ok(key) if !key.is_empty() => {
+                let key = hex::decode(key.trim())
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .ok_or_else(|| {
+                        Error::ConfigError(
+                            "ACCOUNT_ENCRYPTION_KEY must be 64 hex characters (32 bytes)"
+                                .to_string(),
+                        )
+                    })?;
+                Some(key)
+            }
+            _ => None,
+        };
+        let quotes = std::env::var("ACCOUNT_IMPORT_QUOTES")
+            .map(|v| {
+                v.split(',')
+                    .map(|quote| quote.trim().to_uppercase())
+                    .filter(|quote| !quote.is_empty())
+                    .collect()
+            })
+            .unwrap_or(default.quotes);
+
+        Ok(Self {
+            encryption_key,
+            quotes,
+            ..default
+        })
+    }
+}
+
+/// Authenticated encryption of stored credentials, built from HMAC-SHA256.
+///
+/// The plaintext is XORed with an HMAC-SHA256 keystream in counter mode
+/// under a random nonce, then the nonce and ciphertext are authenticated
+/// with HMAC-SHA256 (encrypt-then-MAC). Both keys are derived from the
+/// configured key.
+pub struct CredentialCipher {
+    encryption_key: [u8; 32],
+    mac_key: [u8; 32],
+}
+
+fn hmac_sha256(key: &[u8], parts: &[&[u8]]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+impl CredentialCipher {
+    pub fn new(key: &[u8; 32]) -> Self {
+        let derive = |label: &[u8]| -> [u8; 32] {
+            hmac_sha256(key, &[label]).finalize().into_bytes().into()
+        };
+
+        Self {
+            encryption_key: derive(b"coinlizard account credentials encryption"),
+            mac_key: derive(b"coinlizard account credentials authentication"),
+        }
+    }
+
+    // XOR data with the keystream of a nonce
+    fn apply_keystream(&self, nonce: &[u8], data: &mut [u8]) {
+        for (counter, chunk) in data.chunks_mut(32).enumerate() {
+            let block = hmac_sha256(
+                &self.encryption_key,
+                &[nonce, &(counter as u64).to_be_bytes()],
+            )
+            .finalize()
+            .into_bytes();
+            for (byte, key) in chunk.iter_mut().zip(block) {
+                *byte ^= key;
+            }
+        }
+    }
+
+    fn tag(&self, nonce: &[u8], ciphertext: &[u8]) -> Hmac<Sha256> {
+        hmac_sha256(
+            &self.mac_key,
+            &[CIPHER_VERSION.as_bytes(), nonce, ciphertext],
+        )
+    }
+
+    /// Encrypt credentials as "v1:<hex of nonce, ciphertext and tag>"
+    pub fn encrypt(&self, credentials: &Credentials) -> Result<String> {
+        let mut data = serde_json::to_vec(credentials)
+            .map_err(|e| Error::InternalError(format!("Failed to encode credentials: {}", e)))?;
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom::getrandom(&mut nonce)
+            .map_err(|e| Error::InternalError(format!("Failed to generate nonce: {}", e)))?;
+
+        self.apply_keystream(&nonce, &mut data);
+        let tag = self.tag(&nonce, &data).finalize().into_bytes();
+
+        let sealed = [&nonce[..], &data, &tag].concat();
+        Ok(format!("{}:{}", CIPHER_VERSION, hex::encode(sealed)))
+    }
+
+    /// Decrypt credentials, failing if they were encrypted with another key
+    /// or tampered with
+    pub fn decrypt(&self, encrypted: &str) -> Result<Credentials> {
+        let invalid = || Error::ConfigError("Invalid encrypted credentials".to_string());
+
+        let sealed = encrypted
+            .strip_prefix(CIPHER_VERSION)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .and_then(|rest| hex::decode(rest).ok())
+            .filter(|sealed| sealed.len() >= NONCE_LEN + TAG_LEN)
+            .ok_or_else(invalid)?;
+        let (nonce, rest) = sealed.split_at(NONCE_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - TAG_LEN);
+
+        self.tag(nonce, ciphertext).verify_slice(tag).map_err(|_| {
+            Error::ConfigError(
+                "Failed to decrypt credentials: wrong ACCOUNT_ENCRYPTION_KEY or corrupted data"
+                    .to_string(),
+            )
+        })?;
+
+        let mut data = ciphertext.to_vec();
+        self.apply_keystream(nonce, &mut data);
+        serde_json::from_slice(&data).map_err(|_| invalid())
+    }
+}
+
+/// Connected exchange accounts and the means to reach them
+pub struct AccountBook {
+    config: AccountConfig,
+    cipher: Option<CredentialCipher>,
+    http: HttpClient,
+    accounts: RwLock<HashMap<String, EncryptedAccount>>,
+}
+
+impl AccountBook {
+    pub fn new(config: AccountConfig, http: HttpClient) -> Self {
+        Self {
+            cipher: config.encryption_key.as_ref().map(CredentialCipher::new),
+            config,
+            http,
+            accounts: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &AccountConfig {
+        &self.config
+    }
+
+    /// Cipher of stored credentials; accounts cannot be used without one
+    pub fn cipher(&self) -> Result<&CredentialCipher> {
+        self.cipher.as_ref().ok_or_else(|| {
+            Error::ConfigError(
+                "Exchange accounts are disabled: ACCOUNT_ENCRYPTION_KEY is not set".to_string(),
+            )
+        })
+    }
+
+    /// Authenticated connector of an account, stamping requests with the
+    /// exchange's clock
+    pub fn connector(
+        &self,
+        exchange: Exchange,
+        credentials: Credentials,
+        clock_offset: Duration,
+    ) -> Result<Arc<dyn AccountConnector>> {
+        let http = self.http.clone();
+
+        match exchange {
+            Exchange::Binance => {
+                let connector = match &self.config.binance_url {
+                    Some(url) => BinanceAccountConnector::with_base_url(url, credentials),
+                    None => BinanceAccountConnector::new(credentials),
+                };
+                Ok(Arc::new(
+                    connector.with_http(http).with_clock_offset(clock_offset),
+                ))
+            }
+            Exchange::Coinbase => {
+                let connector = match &self.config.coinbase_url {
+                    Some(url) => CoinbaseAccountConnector::with_base_url(url, credentials),
+                    None => CoinbaseAccountConnector::new(credentials),
+                };
+                Ok(Arc::new(
+                    connector.with_http(http).with_clock_offset(clock_offset),
+                ))
+            }
+            other => Err(Error::ParseError(format!(
+                "Exchange accounts are not supported on {}",
+                other
+            ))),
+        }
+    }
+
+    /// All accounts, oldest first
+    pub fn list(&self) -> Vec<EncryptedAccount> {
+        let accounts = self.accounts.read().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<EncryptedAccount> = accounts.values().cloned().collect();
+        list.sort_by_key(|stored| stored.account.created_at);
+        list
+    }
+
+    /// Look up an account by ID
+    pub fn get(&self, id: &str) -> Option<EncryptedAccount> {
+        let accounts = self.accounts.read().unwrap_or_else(|e| e.into_inner());
+        accounts.get(id).cloned()
+    }
+
+    /// Add or replace an account
+    pub fn upsert(&self, stored: EncryptedAccount) {
+        let mut accounts = self.accounts.write().unwrap_or_else(|e| e.into_inner());
+        accounts.insert(stored.account.id.clone(), stored);
+    }
+
+    /// Remove an account, returning it if it existed
+    pub fn remove(&self, id: &str) -> Option<EncryptedAccount> {
+        let mut accounts = self.accounts.write().unwrap_or_else(|e| e.into_inner());
+        accounts.remove(id)
+    }
+
+    /// Replace all accounts, e.g. after loading them from the store
+    pub fn replace(&self, new_accounts: Vec<EncryptedAccount>) {
+        let mut accounts = self.accounts.write().unwrap_or_else(|e| e.into_inner());
+        *accounts = new_accounts
+            .into_iter()
+            .map(|stored| (stored.account.id.clone(), stored))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials() -> Credentials {
+        Credentials {
+            api_key: "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A".to_string(),
+            // Longer than one keystream block
+            api_secret: "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j"
+                .to_string(),
+            passphrase: Some("correct horse".to_string()),
+        }
+    }
+
+    #[test]
+    fn credentials_round_trip() {
+        let cipher = CredentialCipher::new(&[7; 32]);
+        let encrypted = cipher.encrypt(&credentials()).unwrap();
+
+        assert!(encrypted.starts_with("v1:"));
+        assert!(!encrypted.contains(&credentials().api_secret));
+
+        let decrypted = cipher.decrypt(&encrypted).unwrap();
+        assert_eq!(decrypted.api_key, credentials().api_key);
+        assert_eq!(decrypted.api_secret, credentials().api_secret);
+        assert_eq!(decrypted.passphrase, credentials().passphrase);
+
+        // Every encryption uses a fresh nonce
+        assert_ne!(cipher.encrypt(&credentials()).unwrap(), encrypted);
+    }
+
+    #[test]
+    fn decrypt_rejects_other_keys_and_tampering() {
+        let cipher = CredentialCipher::new(&[7; 32]);
+        let encrypted = cipher.encrypt(&credentials()).unwrap();
+
+        assert!(CredentialCipher::new(&[8; 32]).decrypt(&encrypted).is_err());
+
+        // Flip a bit of the ciphertext
+        let mut sealed = hex::decode(&encrypted[3..]).unwrap();
+        sealed[NONCE_LEN] ^= 1;
+        let tampered = format!("v1:{}", hex::encode(sealed));
+        assert!(cipher.decrypt(&tampered).is_err());
+
+        assert!(cipher.decrypt("v2:00").is_err());
+        assert!(cipher.decrypt(&encrypted[..20]).is_err());
+    }
+}
@@ -0,0 +1,340 @@
+use crate::decimal::{checked_sum, to_f64};
+use common::{
+    models::{
+        AggregatedPrice, AggregationMethod, CurrentPrice, ExcludedSource, ExclusionReason,
+        PriceSource, TradingPair,
+    },
+    Error, Result,
+};
+use rust_decimal::Decimal;
+
+/// Settings controlling how exchange prices are aggregated
+#[derive(Debug, Clone, Copy)]
+pub struct AggregationConfig {
+    /// Method used to combine the prices
+    pub method: AggregationMethod,
+    /// Maximum deviation from the cross-exchange median, in percent, before a
+    /// price is rejected as an outlier. `None` disables outlier rejection.
+    pub max_deviation_percent: Option<f64>,
+    /// Minimum number of contributing exchanges required for an aggregate
+    pub min_sources: usize,
+}
+
+impl Default for AggregationConfig {
+    fn default() -> Self {
+        Self {
+            method: AggregationMethod::Median,
+            max_deviation_percent: Some(5.0),
+            min_sources: 1,
+        }
+    }
+}
+
+impl AggregationConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        let method = std::env::var("AGGREGATION_METHOD")
+            .ok()
+            .and_then(|m| m.parse().ok())
+            .unwrap_or(default.method);
+        let max_deviation_percent = match std::env::var("AGGREGATION_MAX_DEVIATION_PERCENT") {
+            // A non-positive threshold disables outlier rejection
+            Ok(value) => value.parse::<f64>().ok().filter(|v| *v > 0.0),
+            Err(_) => default.max_deviation_percent,
+        };
+        let min_sources = std::env::var("AGGREGATION_MIN_SOURCES")
+            .ok()
+            .and_then(|n| n.parse().ok())
+            .unwrap_or(default.min_sources);
+
+        Self {
+            method,
+            max_deviation_percent,
+            min_sources,
+        }
+    }
+}
+
+/// 24h volume of a price in quote currency, derived from base volume if needed
+pub fn quote_volume(price: &CurrentPrice) -> Option<Decimal> {
+    price
+        .volume_24h
+        .or_else(|| price.base_volume_24h?.checked_mul(price.price))
+}
+
+/// Unweighted median of a list of values
+pub fn median(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    // Both indices point at the middle element when the length is odd
+    let lower = sorted[(sorted.len() - 1) / 2];
+    let upper = sorted[sorted.len() / 2];
+    Some((lower + upper) / 2.0)
+}
+
+/// Fewest quotes a deviating price can be told apart in. With two, their
+/// median lies halfway between them, so both deviate from it equally.
+pub const MIN_CONSENSUS_SOURCES: usize = 3;
+
+/// Cross-exchange median of a set of prices for the same pair, if enough
+/// exchanges quote it to single out a deviating price
+pub fn consensus(prices: &[CurrentPrice]) -> Option<f64> {
+    if prices.len() < MIN_CONSENSUS_SOURCES {
+        return None;
+    }
+    let values: Vec<f64> = prices.iter().map(|p| to_f64(p.price)).collect();
+    median(&values)
+}
+
+// Weighted mean of (value, weight) pairs whose weights sum to `total`
+fn weighted_mean(values: &[(Decimal, Decimal)], total: Decimal) -> Option<Decimal> {
+    let products = values
+        .iter()
+        .map(|(value, weight)| value.checked_mul(*weight))
+        .collect::<Option<Vec<_>>>()?;
+    checked_sum(products)?.checked_div(total)
+}
+
+// Weighted median of (value, weight) pairs whose weights sum to `total`
+fn weighted_median(values: &mut [(Decimal, Decimal)], total: Decimal) -> Option<Decimal> {
+    values.sort_by_key(|(value, _)| *value);
+
+    let mut cumulative = Decimal::ZERO;
+    for (i, (value, weight)) in values.iter().enumerate() {
+        cumulative += *weight;
+        let remaining = total - cumulative;
+        if cumulative == remaining {
+            // Exactly half the weight lies on either side
+            return match values.get(i + 1) {
+                Some((next, _)) => value.checked_add(*next).map(|sum| sum / Decimal::TWO),
+                None => Some(*value),
+            };
+        }
+        if cumulative > remaining {
+            return Some(*value);
+        }
+    }
+
+    values.last().map(|(value, _)| *value)
+}
+
+/// Aggregate per-exchange prices for a pair into a single price
+pub fn aggregate(
+    pair: &TradingPair,
+    prices: &[CurrentPrice],
+    config: &AggregationConfig,
+) -> Result<AggregatedPrice> {
+    let mut excluded = Vec::new();
+
+    // Reject stale prices and those that deviate too far from the cross-exchange median
+    let consensus = consensus(prices);
+
+    let mut candidates = Vec::new();
+    for price in prices {
+        if price.flags.iter().any(|flag| flag.is_stale()) {
+            excluded.push(ExcludedSource {
+                exchange: price.exchange,
+                price: price.price,
+                reason: ExclusionReason::Stale,
+            });
+            continue;
+        }
+
+        if let (Some(max_deviation), Some(consensus)) = (config.max_deviation_percent, consensus) {
+            if consensus > 0.0 {
+                let deviation_percent = (to_f64(price.price) - consensus).abs() / consensus * 100.0;
+                if deviation_percent > max_deviation {
+                    excluded.push(ExcludedSource {
+                        exchange: price.exchange,
+                        price: price.price,
+                        reason: ExclusionReason::Outlier { deviation_percent },
+                    });
+                    continue;
+                }
+            }
+        }
+        candidates.push(price);
+    }
+
+    // Weight by quote volume; fall back to equal weights if no exchange reports volume
+    let any_volume = candidates.iter().any(|p| quote_volume(p).is_some());
+    let mut weighted = Vec::new();
+    for price in candidates {
+        match quote_volume(price) {
+            Some(volume) => weighted.push((price, volume)),
+            None if !any_volume => weighted.push((price, Decimal::ONE)),
+            None => excluded.push(ExcludedSource {
+                exchange: price.exchange,
+                price: price.price,
+                reason: ExclusionReason::NoVolume,
+            }),
+        }
+    }
+
+    let min_sources = config.min_sources.max(1);
+    if weighted.len() < min_sources {
+        return Err(Error::ExchangeError(format!(
+            "Not enough prices to aggregate {}/{}: {} usable, {} required",
+            pair.base,
+            pair.quote,
+            weighted.len(),
+            min_sources
+        )));
+    }
+
+    let out_of_range = || {
+        Error::ExchangeError(format!(
+            "Cannot aggregate {}/{}: prices or volumes out of range",
+            pair.base, pair.quote
+        ))
+    };
+
+    let total_volume =
+        checked_sum(weighted.iter().map(|(_, weight)| *weight)).ok_or_else(out_of_range)?;
+    let mut total_weight = total_volume;
+    if total_weight.is_zero() {
+        // Equal weights if none of the usable prices has any volume
+        for (_, weight) in &mut weighted {
+            *weight = Decimal::ONE;
+        }
+        total_weight = Decimal::from(weighted.len());
+    }
+
+    let sources: Vec<PriceSource> = weighted
+        .iter()
+        .map(|(price, weight)| PriceSource {
+            exchange: price.exchange,
+            price: price.price,
+            volume_24h: quote_volume(price),
+            weight: to_f64(weight / total_weight),
+        })
+        .collect();
+
+    let mut values: Vec<(Decimal, Decimal)> = weighted
+        .iter()
+        .map(|(price, weight)| (price.price, *weight))
+        .collect();
+    let aggregate_price = match config.method {
+        AggregationMethod::Mean => weighted_mean(&values, total_weight),
+        AggregationMethod::Median => weighted_median(&mut values, total_weight),
+    }
+    .ok_or_else(out_of_range)?;
+
+    let volume_24h = any_volume.then_some(total_volume);
+    let timestamp = weighted
+        .iter()
+        .map(|(price, _)| price.timestamp)
+        .max()
+        .unwrap_or_else(chrono::Utc::now);
+
+    Ok(AggregatedPrice {
+        pair: pair.clone(),
+        price: aggregate_price,
+        method: config.method,
+        volume_24h,
+        sources,
+        excluded,
+        timestamp,
+        route: None,
+        conversion: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::Exchange;
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn pair() -> TradingPair {
+        TradingPair {
+            base: "BTC".to_string(),
+            quote: "USD".to_string(),
+        }
+    }
+
+    fn price(exchange: Exchange, price: &str, volume: Option<&str>) -> CurrentPrice {
+        CurrentPrice {
+            exchange,
+            pair: pair(),
+            price: decimal(price),
+            bid: None,
+            ask: None,
+            open_24h: None,
+            high_24h: None,
+            low_24h: None,
+            change_24h: None,
+            change_percent_24h: None,
+            base_volume_24h: None,
+            volume_24h: volume.map(decimal),
+            timestamp: chrono::Utc::now(),
+            flags: Vec::new(),
+        }
+    }
+
+    fn config(method: AggregationMethod) -> AggregationConfig {
+        AggregationConfig {
+            method,
+            ..AggregationConfig::default()
+        }
+    }
+
+    #[test]
+    fn aggregates_are_exact_decimals() {
+        let prices = [
+            price(Exchange::Coinbase, "0.1", Some("1")),
+            price(Exchange::Binance, "0.1001", Some("3")),
+        ];
+
+        let mean = aggregate(&pair(), &prices, &config(AggregationMethod::Mean)).unwrap();
+        assert_eq!(mean.price, decimal("0.100075"));
+        assert_eq!(mean.volume_24h, Some(decimal("4")));
+        assert_eq!(mean.sources[1].weight, 0.75);
+
+        // Exactly half the weight on either side averages the middle prices
+        let prices = [
+            price(Exchange::Coinbase, "0.1", Some("2")),
+            price(Exchange::Binance, "0.1001", Some("2")),
+        ];
+        let median = aggregate(&pair(), &prices, &config(AggregationMethod::Median)).unwrap();
+        assert_eq!(median.price, decimal("0.10005"));
+    }
+
+    #[test]
+    fn outliers_need_three_quotes() {
+        let outlier = AggregationConfig {
+            max_deviation_percent: Some(5.0),
+            ..config(AggregationMethod::Mean)
+        };
+
+        // Two quotes deviate equally from their median, so neither is rejected
+        let prices = [
+            price(Exchange::Coinbase, "100", Some("1")),
+            price(Exchange::Binance, "120", Some("1")),
+        ];
+        let aggregated = aggregate(&pair(), &prices, &outlier).unwrap();
+        assert!(aggregated.excluded.is_empty());
+        assert_eq!(aggregated.sources.len(), 2);
+        assert_eq!(aggregated.price, decimal("110"));
+
+        // A third quote singles out the deviating one
+        let prices = [
+            price(Exchange::Coinbase, "100", Some("1")),
+            price(Exchange::Binance, "120", Some("1")),
+            price(Exchange::Custom("kraken"), "101", Some("1")),
+        ];
+        let aggregated = aggregate(&pair(), &prices, &outlier).unwrap();
+        assert_eq!(aggregated.excluded.len(), 1);
+        assert_eq!(aggregated.excluded[0].exchange, Exchange::Binance);
+        assert_eq!(aggregated.price, decimal("100.5"));
+    }
+}
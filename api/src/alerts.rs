@@ -0,0 +1,659 @@
+use chrono::{DateTime, Duration, Utc};
+use common::models::{
+    AlertCondition, AlertEvent, AlertRule, CurrentPrice, Exchange, PriceInterval, TradingPair,
+};
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use store::{PriceQuery, PriceStore};
+use tracing::{debug, error, info, warn};
+
+use crate::decimal::to_f64;
+
+/// Header carrying the hex-encoded HMAC-SHA256 signature of a notification
+pub const SIGNATURE_HEADER: &str = "X-CoinLizard-Signature";
+/// Header carrying the Unix timestamp included in the signature
+pub const TIMESTAMP_HEADER: &str = "X-CoinLizard-Timestamp";
+/// Header carrying the event ID, identical across retries of one notification
+pub const DELIVERY_HEADER: &str = "X-CoinLizard-Delivery";
+
+/// Settings for alert evaluation and webhook delivery
+#[derive(Debug, Clone)]
+pub struct AlertConfig {
+    /// Secret used to sign webhook payloads; payloads are unsigned if unset
+    pub webhook_secret: Option<String>,
+    /// Delivery attempts per notification, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each further retry
+    pub retry_backoff: Duration,
+    /// Timeout of a single delivery attempt
+    pub webhook_timeout: Duration,
+    /// Minimum time between notifications of a rule that sets no cooldown
+    pub default_cooldown: Duration,
+    /// How often the prices watched by alert rules are fetched and evaluated
+    pub evaluate_secs: u64,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            webhook_secret: None,
+            max_attempts: 3,
+            retry_backoff: Duration::seconds(2),
+            webhook_timeout: Duration::seconds(10),
+            default_cooldown: Duration::minutes(15),
+            evaluate_secs: 60,
+        }
+    }
+}
+
+impl AlertConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_secs = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .map(Duration::seconds)
+        };
+
+        Self {
+            webhook_secret: std::env::var("ALERT_WEBHOOK_SECRET")
+                .ok()
+                .filter(|s| !s.is_empty()),
+            max_attempts: std::env::var("ALERT_WEBHOOK_MAX_ATTEMPTS")
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .filter(|n| *n > 0)
+                .unwrap_or(default.max_attempts),
+            retry_backoff: env_secs("ALERT_WEBHOOK_RETRY_BACKOFF_SECS")
+                .unwrap_or(default.retry_backoff),
+            webhook_timeout: env_secs("ALERT_WEBHOOK_TIMEOUT_SECS")
+                .unwrap_or(default.webhook_timeout),
+            default_cooldown: env_secs("ALERT_DEFAULT_COOLDOWN_SECS")
+                .unwrap_or(default.default_cooldown),
+            evaluate_secs: std::env::var("ALERT_EVALUATE_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|secs| *secs > 0)
+                .unwrap_or(default.evaluate_secs),
+        }
+    }
+}
+
+// Trigger state of a rule between evaluations
+#[derive(Debug, Clone, Copy, Default)]
+struct RuleState {
+    /// Whether the condition held at the last evaluation
+    active: bool,
+    /// When the rule last sent a notification
+    last_fired: Option<DateTime<Utc>>,
+}
+
+// A triggered condition before it becomes an event
+struct Trigger {
+    exchange: Exchange,
+    price: Decimal,
+    reference_price: Option<Decimal>,
+    message: String,
+}
+
+/// Evaluates alert rules against incoming prices and delivers notifications
+pub struct AlertEngine {
+    config: AlertConfig,
+    client: reqwest::Client,
+    rules: RwLock<HashMap<String, AlertRule>>,
+    state: Mutex<HashMap<String, RuleState>>,
+    // Latest price per coin, quote currency and exchange, for spread conditions
+    latest: Mutex<HashMap<(String, String, Exchange), Decimal>>,
+}
+
+impl AlertEngine {
+    pub fn new(config: AlertConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.webhook_timeout.to_std().unwrap_or_default())
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            client,
+            rules: RwLock::new(HashMap::new()),
+            state: Mutex::new(HashMap::new()),
+            latest: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// All rules, oldest first
+    pub fn list(&self) -> Vec<AlertRule> {
+        let rules = self.rules.read().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<AlertRule> = rules.values().cloned().collect();
+        list.sort_by_key(|rule| rule.created_at);
+        list
+    }
+
+    /// Look up a rule by ID
+    pub fn get(&self, id: &str) -> Option<AlertRule> {
+        let rules = self.rules.read().unwrap_or_else(|e| e.into_inner());
+        rules.get(id).cloned()
+    }
+
+    /// Add or replace a rule; a changed rule starts untriggered
+    pub fn upsert(&self, rule: AlertRule) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.remove(&rule.id);
+
+        let mut rules = self.rules.write().unwrap_or_else(|e| e.into_inner());
+        rules.insert(rule.id.clone(), rule);
+    }
+
+    /// Remove a rule, returning it if it existed
+    pub fn remove(&self, id: &str) -> Option<AlertRule> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.remove(id);
+
+        let mut rules = self.rules.write().unwrap_or_else(|e| e.into_inner());
+        rules.remove(id)
+    }
+
+    /// Replace all rules, e.g. after loading them from the store
+    pub fn replace(&self, new_rules: Vec<AlertRule>) {
+        let mut rules = self.rules.write().unwrap_or_else(|e| e.into_inner());
+        *rules = new_rules
+            .into_iter()
+            .map(|rule| (rule.id.clone(), rule))
+            .collect();
+    }
+
+    /// Evaluate the rules of a coin against newly ingested prices.
+    ///
+    /// Rules notify when their condition starts to hold, and not again until the
+    /// condition has cleared and the rule's cooldown has passed. Notifications
+    /// are delivered in the background.
+    pub async fn evaluate(&self, coin_id: &str, prices: &[CurrentPrice], store: &PriceStore) {
+        let Some(quote) = prices.first().map(|p| p.pair.quote.to_uppercase()) else {
+            return;
+        };
+
+        {
+            let mut latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+            for price in prices {
+                latest.insert(
+                    (coin_id.to_string(), quote.clone(), price.exchange),
+                    price.price,
+                );
+            }
+        }
+
+        let rules: Vec<AlertRule> = {
+            let rules = self.rules.read().unwrap_or_else(|e| e.into_inner());
+            rules
+                .values()
+                .filter(|rule| {
+                    rule.spec.enabled
+                        && rule.spec.coin_id == coin_id
+                        && rule.spec.currency.eq_ignore_ascii_case(&quote)
+                })
+                .cloned()
+                .collect()
+        };
+
+        let now = Utc::now();
+
+        for rule in rules {
+            // Percent changes are measured against the stored price at the
+            // start of the window
+            let mut references = HashMap::new();
+            if let AlertCondition::PercentChange { window_secs, .. } = rule.spec.condition {
+                let window = Duration::seconds(window_secs as i64);
+                for price in watched(&rule, prices) {
+                    if let Some(reference) =
+                        window_start_price(store, &price.pair, price.exchange, window, now).await
+                    {
+                        references.insert(price.exchange, reference);
+                    }
+                }
+            }
+
+            let trigger = self.check(&rule, coin_id, &quote, prices, &references);
+            let fire = self.fire(&rule, trigger.is_some(), now);
+
+            let Some(trigger) = trigger.filter(|_| fire) else {
+                continue;
+            };
+
+            let event = AlertEvent {
+                id: uuid::Uuid::new_v4().to_string(),
+                rule_id: rule.id.clone(),
+                coin_id: coin_id.to_string(),
+                currency: quote.clone(),
+                condition: rule.spec.condition.clone(),
+                exchange: trigger.exchange,
+                price: trigger.price,
+                reference_price: trigger.reference_price,
+                message: trigger.message,
+                triggered_at: now,
+            };
+
+            info!("Alert {} triggered: {}", rule.id, event.message);
+
+            let client = self.client.clone();
+            let config = self.config.clone();
+            let url = rule.spec.webhook_url.clone();
+            tokio::spawn(async move { deliver(&client, &config, &url, &event).await });
+        }
+    }
+
+    // Update a rule's trigger state with whether its condition holds now,
+    // returning whether it should notify
+    fn fire(&self, rule: &AlertRule, triggered: bool, now: DateTime<Utc>) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = state.entry(rule.id.clone()).or_default();
+        let was_active = entry.active;
+        entry.active = triggered;
+
+        let cooldown = rule
+            .spec
+            .cooldown_secs
+            .map(|secs| Duration::seconds(secs as i64))
+            .unwrap_or(self.config.default_cooldown);
+        let cooled_down = entry.last_fired.is_none_or(|last| now - last >= cooldown);
+
+        let fire = triggered && !was_active && cooled_down;
+        if fire {
+            entry.last_fired = Some(now);
+        }
+        fire
+    }
+
+    // Check a rule's condition, returning what triggered it. Percent changes
+    // are measured against the reference price of each exchange.
+    fn check(
+        &self,
+        rule: &AlertRule,
+        coin_id: &str,
+        quote: &str,
+        prices: &[CurrentPrice],
+        references: &HashMap<Exchange, Decimal>,
+    ) -> Option<Trigger> {
+        let watched = watched(rule, prices);
+
+        match rule.spec.condition {
+            AlertCondition::PriceAbove { threshold } => watched
+                .filter(|p| to_f64(p.price) > threshold)
+                .map(|p| Trigger {
+                    exchange: p.exchange,
+                    price: p.price,
+                    reference_price: None,
+                    message: format!(
+                        "{}/{} is {} on {}, above {}",
+                        coin_id, quote, p.price, p.exchange, threshold
+                    ),
+                })
+                .next(),
+            AlertCondition::PriceBelow { threshold } => watched
+                .filter(|p| to_f64(p.price) < threshold)
+                .map(|p| Trigger {
+                    exchange: p.exchange,
+                    price: p.price,
+                    reference_price: None,
+                    message: format!(
+                        "{}/{} is {} on {}, below {}",
+                        coin_id, quote, p.price, p.exchange, threshold
+                    ),
+                })
+                .next(),
+            AlertCondition::PercentChange {
+                percent,
+                window_secs,
+            } => {
+                for price in watched {
+                    let Some(&reference) = references.get(&price.exchange) else {
+                        continue;
+                    };
+                    let change = to_f64((price.price - reference) / reference) * 100.0;
+                    let triggered = if percent >= 0.0 {
+                        change >= percent
+                    } else {
+                        change <= percent
+                    };
+                    if triggered {
+                        return Some(Trigger {
+                            exchange: price.exchange,
+                            price: price.price,
+                            reference_price: Some(reference),
+                            message: format!(
+                                "{}/{} moved {:.2}% on {} in the last {}s ({} -> {})",
+                                coin_id,
+                                quote,
+                                change,
+                                price.exchange,
+                                window_secs,
+                                reference,
+                                price.price
+                            ),
+                        });
+                    }
+                }
+                None
+            }
+            AlertCondition::Spread {
+                exchanges: [a, b],
+                threshold_percent,
+            } => {
+                let latest = self.latest.lock().unwrap_or_else(|e| e.into_inner());
+                let key = |ex| (coin_id.to_string(), quote.to_string(), ex);
+                let (Some(&price_a), Some(&price_b)) = (latest.get(&key(a)), latest.get(&key(b)))
+                else {
+                    return None;
+                };
+
+                let mid = (price_a + price_b) / Decimal::TWO;
+                if mid <= Decimal::ZERO {
+                    return None;
+                }
+                let spread = to_f64((price_a - price_b).abs() / mid) * 100.0;
+
+                (spread > threshold_percent).then(|| Trigger {
+                    exchange: a,
+                    price: price_a,
+                    reference_price: Some(price_b),
+                    message: format!(
+                        "{}/{} spread between {} ({}) and {} ({}) is {:.2}%, over {}%",
+                        coin_id, quote, a, price_a, b, price_b, spread, threshold_percent
+                    ),
+                })
+            }
+        }
+    }
+}
+
+// Prices of the exchanges a rule watches
+fn watched<'a>(
+    rule: &'a AlertRule,
+    prices: &'a [CurrentPrice],
+) -> impl Iterator<Item = &'a CurrentPrice> {
+    prices
+        .iter()
+        .filter(|p| rule.spec.exchange.is_none_or(|ex| ex == p.exchange))
+}
+
+// Earliest stored price of a pair within the window
+async fn window_start_price(
+    store: &PriceStore,
+    pair: &TradingPair,
+    exchange: Exchange,
+    window: Duration,
+    now: DateTime<Utc>,
+) -> Option<Decimal> {
+    let query = PriceQuery {
+        pair: pair.clone(),
+        exchange: Some(exchange),
+        interval: PriceInterval::OneMinute,
+        start_time: Some(now - window),
+        end_time: Some(now),
+        limit: None,
+    };
+
+    match store.get_price_history(&query).await {
+        // History is newest first
+        Ok(history) => history
+            .data
+            .last()
+            .map(|p| p.price)
+            .filter(|p| *p > Decimal::ZERO),
+        Err(e) => {
+            debug!(
+                "No history to evaluate {} price change against: {}",
+                exchange, e
+            );
+            None
+        }
+    }
+}
+
+/// Sign a webhook payload as `hex(HMAC-SHA256(secret, "{timestamp}.{body}"))`
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// POST an event to a webhook, retrying with exponential backoff on network
+// errors, rate limiting and server errors
+async fn deliver(client: &reqwest::Client, config: &AlertConfig, url: &str, event: &AlertEvent) {
+    let body = match serde_json::to_string(event) {
+        Ok(body) => body,
+        Err(e) => {
+            error!("Failed to serialize alert event {}: {}", event.id, e);
+            return;
+        }
+    };
+
+    let mut backoff = config.retry_backoff.to_std().unwrap_or_default();
+
+    for attempt in 1..=config.max_attempts {
+        let timestamp = Utc::now().timestamp();
+        let mut request = client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(DELIVERY_HEADER, &event.id)
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .body(body.clone());
+        if let Some(secret) = &config.webhook_secret {
+            request = request.header(
+                SIGNATURE_HEADER,
+                format!("sha256={}", sign(secret, timestamp, &body)),
+            );
+        }
+
+        let retryable = match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                debug!("Delivered alert event {} to {}", event.id, url);
+                return;
+            }
+            Ok(response) => {
+                let status = response.status();
+                warn!(
+                    "Webhook {} rejected alert event {} with status {} (attempt {}/{})",
+                    url, event.id, status, attempt, config.max_attempts
+                );
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to deliver alert event {} to {}: {} (attempt {}/{})",
+                    event.id, url, e, attempt, config.max_attempts
+                );
+                true
+            }
+        };
+
+        if !retryable || attempt == config.max_attempts {
+            break;
+        }
+
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+
+    error!(
+        "Giving up on delivering alert event {} to {}",
+        event.id, url
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::AlertRuleSpec;
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn rule(condition: AlertCondition, cooldown_secs: Option<u64>) -> AlertRule {
+        let now = Utc::now();
+        AlertRule {
+            id: "rule".to_string(),
+            spec: AlertRuleSpec {
+                coin_id: "bitcoin".to_string(),
+                currency: "USD".to_string(),
+                exchange: None,
+                condition,
+                webhook_url: "http://127.0.0.1:1/hook".to_string(),
+                cooldown_secs,
+                enabled: true,
+            },
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn price(exchange: Exchange, price: &str) -> CurrentPrice {
+        CurrentPrice {
+            exchange,
+            pair: TradingPair {
+                base: "BTC".to_string(),
+                quote: "USD".to_string(),
+            },
+            price: decimal(price),
+            bid: None,
+            ask: None,
+            open_24h: None,
+            high_24h: None,
+            low_24h: None,
+            change_24h: None,
+            change_percent_24h: None,
+            base_volume_24h: None,
+            volume_24h: None,
+            timestamp: Utc::now(),
+            flags: Vec::new(),
+        }
+    }
+
+    fn check(
+        engine: &AlertEngine,
+        rule: &AlertRule,
+        prices: &[CurrentPrice],
+        references: &HashMap<Exchange, Decimal>,
+    ) -> Option<Trigger> {
+        engine.check(rule, "bitcoin", "USD", prices, references)
+    }
+
+    #[test]
+    fn price_thresholds() {
+        let engine = AlertEngine::new(AlertConfig::default());
+        let prices = [
+            price(Exchange::Coinbase, "99"),
+            price(Exchange::Binance, "101"),
+        ];
+        let none = HashMap::new();
+
+        let above = rule(AlertCondition::PriceAbove { threshold: 100.0 }, None);
+        let trigger = check(&engine, &above, &prices, &none).unwrap();
+        assert_eq!(trigger.exchange, Exchange::Binance);
+        assert_eq!(trigger.price, decimal("101"));
+
+        let below = rule(AlertCondition::PriceBelow { threshold: 100.0 }, None);
+        let trigger = check(&engine, &below, &prices, &none).unwrap();
+        assert_eq!(trigger.exchange, Exchange::Coinbase);
+
+        // Rules restricted to an exchange ignore the others
+        let mut restricted = above.clone();
+        restricted.spec.exchange = Some(Exchange::Coinbase);
+        assert!(check(&engine, &restricted, &prices, &none).is_none());
+
+        let far_above = rule(AlertCondition::PriceAbove { threshold: 200.0 }, None);
+        assert!(check(&engine, &far_above, &prices, &none).is_none());
+    }
+
+    #[test]
+    fn percent_change_against_window_start() {
+        let engine = AlertEngine::new(AlertConfig::default());
+        let prices = [price(Exchange::Coinbase, "110")];
+        let references = HashMap::from([(Exchange::Coinbase, decimal("100"))]);
+
+        let rise = |percent| {
+            rule(
+                AlertCondition::PercentChange {
+                    percent,
+                    window_secs: 3600,
+                },
+                None,
+            )
+        };
+        let trigger = check(&engine, &rise(10.0), &prices, &references).unwrap();
+        assert_eq!(trigger.reference_price, Some(decimal("100")));
+        assert!(check(&engine, &rise(10.5), &prices, &references).is_none());
+
+        // Negative percentages watch for drops
+        assert!(check(&engine, &rise(-5.0), &prices, &references).is_none());
+        let fallen = [price(Exchange::Coinbase, "94")];
+        assert!(check(&engine, &rise(-5.0), &fallen, &references).is_some());
+
+        // Without a reference price there is nothing to compare against
+        assert!(check(&engine, &rise(10.0), &prices, &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn spread_between_exchanges() {
+        let engine = AlertEngine::new(AlertConfig::default());
+        let spread = rule(
+            AlertCondition::Spread {
+                exchanges: [Exchange::Coinbase, Exchange::Binance],
+                threshold_percent: 1.0,
+            },
+            None,
+        );
+        let latest = |coinbase: &str, binance: &str| {
+            let mut latest = engine.latest.lock().unwrap();
+            for (exchange, price) in [(Exchange::Coinbase, coinbase), (Exchange::Binance, binance)]
+            {
+                latest.insert(
+                    ("bitcoin".to_string(), "USD".to_string(), exchange),
+                    decimal(price),
+                );
+            }
+        };
+
+        latest("100", "100.5");
+        assert!(check(&engine, &spread, &[], &HashMap::new()).is_none());
+
+        latest("100", "102");
+        let trigger = check(&engine, &spread, &[], &HashMap::new()).unwrap();
+        assert_eq!(trigger.price, decimal("100"));
+        assert_eq!(trigger.reference_price, Some(decimal("102")));
+    }
+
+    #[test]
+    fn fires_once_and_rearms_after_clearing_and_cooldown() {
+        let engine = AlertEngine::new(AlertConfig::default());
+        let rule = rule(AlertCondition::PriceAbove { threshold: 100.0 }, Some(60));
+        let start = Utc::now();
+        let at = |secs| start + Duration::seconds(secs);
+
+        assert!(engine.fire(&rule, true, at(0)));
+        // Holding condition does not notify again
+        assert!(!engine.fire(&rule, true, at(120)));
+
+        // Clearing re-arms the rule, but only once the cooldown has passed
+        assert!(!engine.fire(&rule, false, at(130)));
+        assert!(engine.fire(&rule, true, at(140)));
+        assert!(!engine.fire(&rule, false, at(150)));
+        assert!(!engine.fire(&rule, true, at(160)));
+        assert!(!engine.fire(&rule, false, at(170)));
+        assert!(engine.fire(&rule, true, at(200)));
+    }
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        assert_eq!(
+            sign("whsec_test", 1_700_000_000, r#"{"rule_id":"r1"}"#),
+            "8abc3f3f220b448a59ee09eae830177d0f6f2519aa215fee98e1c7319a7b79c2"
+        );
+    }
+}
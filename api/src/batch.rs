@@ -0,0 +1,51 @@
+/// Limits on batch price lookups
+#[derive(Debug, Clone, Copy)]
+pub struct BatchConfig {
+    /// Maximum number of coin and currency combinations per request
+    pub max_cells: usize,
+    /// Maximum number of combinations looked up at the same time
+    pub concurrency: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_cells: 200,
+            concurrency: 16,
+        }
+    }
+}
+
+impl BatchConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_usize = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|v| *v > 0)
+        };
+
+        Self {
+            max_cells: env_usize("BATCH_MAX_CELLS").unwrap_or(default.max_cells),
+            concurrency: env_usize("BATCH_CONCURRENCY").unwrap_or(default.concurrency),
+        }
+    }
+}
+
+/// Split a comma-separated list, dropping empty entries and duplicates while
+/// keeping the first occurrence of each entry
+pub fn parse_list(list: &str, normalize: impl Fn(&str) -> String) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    for item in list
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let item = normalize(item);
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    items
+}
@@ -0,0 +1,246 @@
+use common::{
+    models::{Coin, CoinMetadata, Exchange},
+    Error, Result,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Settings for loading and refreshing the coin catalog
+#[derive(Debug, Clone)]
+pub struct CatalogConfig {
+    /// TOML or JSON file listing the coins to serve
+    pub path: Option<PathBuf>,
+    /// TOML or JSON file with descriptive and supply metadata per coin
+    pub metadata_path: Option<PathBuf>,
+    /// Whether coins listed on the exchanges are added to the catalog
+    pub discover: bool,
+    /// How often the catalog is rebuilt, in seconds
+    pub refresh_secs: u64,
+}
+
+impl Default for CatalogConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            metadata_path: None,
+            discover: false,
+            refresh_secs: 3600,
+        }
+    }
+}
+
+impl CatalogConfig {
+    pub fn from_env() -> Self {
+        let path = std::env::var("COIN_CATALOG_PATH").ok().map(PathBuf::from);
+        let metadata_path = std::env::var("COIN_METADATA_PATH").ok().map(PathBuf::from);
+        let discover = std::env::var("COIN_CATALOG_DISCOVER")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let refresh_secs = std::env::var("COIN_CATALOG_REFRESH_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3600);
+
+        Self {
+            path,
+            metadata_path,
+            discover,
+            refresh_secs,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CatalogFile {
+    coins: Vec<Coin>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MetadataFile {
+    metadata: HashMap<String, CoinMetadata>,
+}
+
+// Read a TOML or JSON file, chosen by file extension
+fn parse_file<T: DeserializeOwned>(path: &Path, what: &str) -> Result<T> {
+    let contents = std::fs::read_to_string(path).map_err(|e| {
+        Error::ConfigError(format!("Failed to read {} {}: {}", what, path.display(), e))
+    })?;
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&contents)
+            .map_err(|e| Error::ConfigError(format!("Invalid {} JSON: {}", what, e))),
+        _ => toml::from_str(&contents)
+            .map_err(|e| Error::ConfigError(format!("Invalid {} TOML: {}", what, e))),
+    }
+}
+
+/// Coins served when no catalog file is configured
+pub fn default_coins() -> Vec<Coin> {
+    [
+        ("bitcoin", "Bitcoin", "BTC"),
+        ("ethereum", "Ethereum", "ETH"),
+        ("ripple", "XRP", "XRP"),
+        ("cardano", "Cardano", "ADA"),
+        ("solana", "Solana", "SOL"),
+    ]
+    .into_iter()
+    .map(|(id, name, symbol)| Coin {
+        id: id.to_string(),
+        name: name.to_string(),
+        symbol: symbol.to_string(),
+        exchange_symbols: BTreeMap::new(),
+    })
+    .collect()
+}
+
+/// Load coins from a TOML or JSON catalog file
+pub fn load_file(path: &Path) -> Result<Vec<Coin>> {
+    let file: CatalogFile = parse_file(path, "coin catalog")?;
+    Ok(file.coins)
+}
+
+/// Load coin metadata, keyed by coin ID, from a TOML or JSON file
+pub fn load_metadata_file(path: &Path) -> Result<HashMap<String, CoinMetadata>> {
+    let file: MetadataFile = parse_file(path, "coin metadata")?;
+    Ok(file.metadata)
+}
+
+/// Check that coin ids are unique and that no two coins resolve to the same
+/// symbol on the same exchange
+pub fn validate(coins: &[Coin]) -> Result<()> {
+    let mut ids = HashSet::new();
+    let mut symbols: HashMap<(Exchange, &str), &str> = HashMap::new();
+    let exchanges = Exchange::all();
+
+    for coin in coins {
+        if !ids.insert(coin.id.as_str()) {
+            return Err(Error::ConfigError(format!(
+                "Duplicate coin id '{}' in catalog",
+                coin.id
+            )));
+        }
+
+        for &exchange in &exchanges {
+            let symbol = coin.symbol_on(exchange);
+            if let Some(other) = symbols.insert((exchange, symbol), &coin.id) {
+                return Err(Error::ConfigError(format!(
+                    "Coins '{}' and '{}' both use symbol {} on {}; add an exchange_symbols override",
+                    other, coin.id, symbol, exchange
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Add candidate coins whose id and exchange symbols are not yet taken.
+///
+/// Candidates that collide with a coin already in the list are skipped, so
+/// earlier sources (the catalog file) always win over later ones.
+pub fn merge(coins: &mut Vec<Coin>, candidates: Vec<Coin>) {
+    let exchanges = Exchange::all();
+    let symbols_of = |coin: &Coin| -> Vec<(Exchange, String)> {
+        exchanges
+            .iter()
+            .map(|&ex| (ex, coin.symbol_on(ex).to_string()))
+            .collect()
+    };
+    let mut ids: HashSet<String> = coins.iter().map(|c| c.id.clone()).collect();
+    let mut taken: HashSet<(Exchange, String)> = coins.iter().flat_map(symbols_of).collect();
+
+    for candidate in candidates {
+        let symbols = symbols_of(&candidate);
+        if ids.contains(&candidate.id) || symbols.iter().any(|s| taken.contains(s)) {
+            continue;
+        }
+
+        ids.insert(candidate.id.clone());
+        taken.extend(symbols);
+        coins.push(candidate);
+    }
+}
+
+/// Build catalog entries for symbols listed on the exchanges
+pub fn discovered_coins(symbols: impl IntoIterator<Item = String>) -> Vec<Coin> {
+    let symbols: BTreeSet<String> = symbols.into_iter().map(|s| s.to_uppercase()).collect();
+
+    symbols
+        .into_iter()
+        .map(|symbol| Coin {
+            id: symbol.to_lowercase(),
+            name: symbol.clone(),
+            symbol,
+            exchange_symbols: BTreeMap::new(),
+        })
+        .collect()
+}
+
+/// Thread-safe set of coins served by the API, with their metadata
+pub struct CoinCatalog {
+    coins: RwLock<HashMap<String, Coin>>,
+    metadata: RwLock<HashMap<String, CoinMetadata>>,
+}
+
+impl CoinCatalog {
+    pub fn new(coins: Vec<Coin>) -> Self {
+        Self {
+            coins: RwLock::new(coins.into_iter().map(|c| (c.id.clone(), c)).collect()),
+            metadata: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Metadata of a coin, empty if none is known
+    pub fn metadata(&self, id: &str) -> CoinMetadata {
+        self.metadata
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Set the metadata of a single coin
+    pub fn set_metadata(&self, id: &str, metadata: CoinMetadata) {
+        self.metadata
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.to_string(), metadata);
+    }
+
+    /// Replace the metadata of all coins
+    pub fn replace_metadata(&self, metadata: HashMap<String, CoinMetadata>) {
+        *self.metadata.write().unwrap_or_else(|e| e.into_inner()) = metadata;
+    }
+
+    /// Look up a coin by ID
+    pub fn get(&self, id: &str) -> Option<Coin> {
+        self.coins
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+            .cloned()
+    }
+
+    /// List all coins
+    pub fn list(&self) -> Vec<Coin> {
+        self.coins
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Atomically replace the catalog after validating the new coins
+    pub fn replace(&self, coins: Vec<Coin>) -> Result<()> {
+        validate(&coins)?;
+
+        let coins = coins.into_iter().map(|c| (c.id.clone(), c)).collect();
+        *self.coins.write().unwrap_or_else(|e| e.into_inner()) = coins;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+use chrono::{DateTime, Duration, Utc};
+use common::models::{ClockSkew, Exchange};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use tracing::{debug, warn};
+
+/// Settings for measuring the clock skew of each exchange
+#[derive(Debug, Clone, Copy)]
+pub struct ClockConfig {
+    /// How often each exchange's server time is sampled
+    pub sync_secs: u64,
+    /// Skew beyond which a warning is logged, in milliseconds
+    pub max_skew_ms: i64,
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            sync_secs: 300,
+            max_skew_ms: 1000,
+        }
+    }
+}
+
+impl ClockConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            sync_secs: std::env::var("CLOCK_SYNC_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default.sync_secs),
+            max_skew_ms: std::env::var("CLOCK_MAX_SKEW_MS")
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default.max_skew_ms),
+        }
+    }
+}
+
+/// Measure clock skew from a server time read during a request sent at
+/// `sent` and answered at `received`, both on the local clock
+pub fn measure(
+    exchange: Exchange,
+    sent: DateTime<Utc>,
+    server_time: DateTime<Utc>,
+    received: DateTime<Utc>,
+) -> ClockSkew {
+    let round_trip = received - sent;
+    let midpoint = sent + round_trip / 2;
+
+    ClockSkew {
+        exchange,
+        offset_ms: (server_time - midpoint).num_milliseconds(),
+        round_trip_ms: round_trip.num_milliseconds(),
+        server_time,
+        sampled_at: received,
+    }
+}
+
+/// Latest measured clock skew of each exchange
+pub struct ClockMonitor {
+    config: ClockConfig,
+    skews: RwLock<HashMap<Exchange, ClockSkew>>,
+}
+
+impl ClockMonitor {
+    pub fn new(config: ClockConfig) -> Self {
+        Self {
+            config,
+            skews: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Record a new sample, replacing the previous one of the exchange
+    pub fn record(&self, skew: ClockSkew) {
+        if skew.offset_ms.abs() > self.config.max_skew_ms {
+            warn!(
+                "{} clock is {} ms off the local clock (round trip {} ms)",
+                skew.exchange, skew.offset_ms, skew.round_trip_ms
+            );
+        } else {
+            debug!(
+                "{} clock is {} ms off the local clock (round trip {} ms)",
+                skew.exchange, skew.offset_ms, skew.round_trip_ms
+            );
+        }
+
+        let mut skews = self.skews.write().unwrap_or_else(|e| e.into_inner());
+        skews.insert(skew.exchange, skew);
+    }
+
+    /// Latest sample of every exchange, by exchange
+    pub fn list(&self) -> Vec<ClockSkew> {
+        let skews = self.skews.read().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<ClockSkew> = skews.values().cloned().collect();
+        list.sort_by_key(|skew| skew.exchange);
+        list
+    }
+
+    /// Exchange time minus local time; zero until the exchange is sampled
+    pub fn offset(&self, exchange: Exchange) -> Duration {
+        let skews = self.skews.read().unwrap_or_else(|e| e.into_inner());
+        skews.get(&exchange).map_or(Duration::zero(), |skew| {
+            Duration::milliseconds(skew.offset_ms)
+        })
+    }
+
+    /// Current time on an exchange's clock, for comparing with the
+    /// timestamps it reports
+    pub fn now(&self, exchange: Exchange) -> DateTime<Utc> {
+        Utc::now() + self.offset(exchange)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // 2024-03-01 12:00:00 plus `millis`
+    fn at(millis: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap() + Duration::milliseconds(millis)
+    }
+
+    #[test]
+    fn server_time_is_compared_with_the_round_trip_midpoint() {
+        let skew = measure(Exchange::Binance, at(0), at(1_100), at(200));
+
+        assert_eq!(skew.exchange, Exchange::Binance);
+        assert_eq!(skew.round_trip_ms, 200);
+        // The server read its clock halfway through, at 100 ms local time
+        assert_eq!(skew.offset_ms, 1_000);
+        assert_eq!(skew.server_time, at(1_100));
+        assert_eq!(skew.sampled_at, at(200));
+    }
+
+    #[test]
+    fn server_clock_behind_gives_negative_offset() {
+        let skew = measure(Exchange::Coinbase, at(0), at(-250), at(100));
+
+        assert_eq!(skew.round_trip_ms, 100);
+        assert_eq!(skew.offset_ms, -300);
+    }
+
+    #[test]
+    fn monitor_keeps_the_latest_sample_per_exchange() {
+        let monitor = ClockMonitor::new(ClockConfig::default());
+        assert_eq!(monitor.offset(Exchange::Binance), Duration::zero());
+
+        monitor.record(measure(Exchange::Binance, at(0), at(1_100), at(200)));
+        monitor.record(measure(Exchange::Coinbase, at(0), at(-250), at(100)));
+        assert_eq!(
+            monitor.offset(Exchange::Binance),
+            Duration::milliseconds(1_000)
+        );
+        assert_eq!(
+            monitor.offset(Exchange::Coinbase),
+            Duration::milliseconds(-300)
+        );
+
+        // A new sample replaces the previous one
+        monitor.record(measure(Exchange::Binance, at(0), at(5_000), at(0)));
+        assert_eq!(
+            monitor.offset(Exchange::Binance),
+            Duration::milliseconds(5_000)
+        );
+
+        let exchanges: Vec<Exchange> = monitor.list().iter().map(|s| s.exchange).collect();
+        assert_eq!(exchanges, [Exchange::Coinbase, Exchange::Binance]);
+    }
+
+    #[test]
+    fn now_is_shifted_by_the_offset() {
+        let monitor = ClockMonitor::new(ClockConfig::default());
+        monitor.record(measure(Exchange::Binance, at(0), at(60_000), at(0)));
+
+        let before = Utc::now();
+        let now = monitor.now(Exchange::Binance);
+        let after = Utc::now();
+
+        assert!(now >= before + Duration::seconds(60));
+        assert!(now <= after + Duration::seconds(60));
+        // Unsampled exchanges use the local clock
+        assert!(monitor.now(Exchange::Coinbase) <= Utc::now());
+    }
+}
@@ -0,0 +1,561 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use chrono::{Duration, Utc};
+use common::{
+    models::{PriceHistory, PriceHistoryPoint, PriceInterval, TimeUnit, Timeframe},
+    Error as CommonError,
+};
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use tracing::debug;
+
+use crate::aggregate::{self, median};
+use crate::decimal::to_f64;
+use crate::handler::{ApiError, SharedService};
+use crate::markets::MarketFilter;
+
+/// How far back `days=max` reaches
+const MAX_DAYS: i64 = 3650;
+
+/// Routes of the CoinGecko-compatible API, to be nested under `/api/v3`, so
+/// tools written against CoinGecko can switch base URLs without code changes
+pub fn router() -> Router<SharedService> {
+    Router::new()
+        .route("/ping", get(ping))
+        .route("/simple/price", get(simple_price))
+        .route(
+            "/simple/supported_vs_currencies",
+            get(supported_vs_currencies),
+        )
+        .route("/coins/list", get(coins_list))
+        .route("/coins/:id/market_chart", get(market_chart))
+        .route("/coins/:id/ohlc", get(ohlc))
+}
+
+// Parse CoinGecko's `precision` parameter: `full` or a number of decimals
+fn parse_precision(precision: Option<&str>) -> Result<Option<i32>, ApiError> {
+    match precision {
+        None | Some("full") => Ok(None),
+        Some(digits) => digits
+            .parse::<i32>()
+            .ok()
+            .filter(|d| (0..=18).contains(d))
+            .map(Some)
+            .ok_or_else(|| {
+                CommonError::ParseError(format!("Invalid precision: {}", digits)).into()
+            }),
+    }
+}
+
+fn round(value: f64, precision: Option<i32>) -> f64 {
+    match precision {
+        Some(digits) => {
+            let factor = 10f64.powi(digits);
+            (value * factor).round() / factor
+        }
+        None => value,
+    }
+}
+
+// Decimal value as a CoinGecko-style JSON number, rounded half away from zero
+fn round_decimal(value: Decimal, precision: Option<i32>) -> f64 {
+    let rounded = match precision {
+        Some(digits) => {
+            value.round_dp_with_strategy(digits as u32, RoundingStrategy::MidpointAwayFromZero)
+        }
+        None => value,
+    };
+    to_f64(rounded)
+}
+
+// Parse CoinGecko's `days` parameter: a number of days or `max`
+fn parse_days(days: &str) -> Result<Duration, ApiError> {
+    if days == "max" {
+        return Ok(Duration::days(MAX_DAYS));
+    }
+
+    days.parse::<f64>()
+        .ok()
+        .filter(|d| *d > 0.0)
+        .map(|d| Duration::seconds((d.min(MAX_DAYS as f64) * 86400.0) as i64))
+        .ok_or_else(|| CommonError::ParseError(format!("Invalid days: {}", days)).into())
+}
+
+// Granularity of a market chart covering `days`, unless one is requested.
+// Follows CoinGecko: 5-minutely for 1 day, hourly up to 90 days, daily beyond.
+fn chart_interval(days: Duration, interval: Option<&str>) -> Result<PriceInterval, ApiError> {
+    match interval {
+        Some("daily") => Ok(PriceInterval::OneDay),
+        Some("hourly") => Ok(PriceInterval::OneHour),
+        Some(other) => Err(CommonError::ParseError(format!("Invalid interval: {}", other)).into()),
+        None if days <= Duration::days(1) => Ok(PriceInterval::FiveMinutes),
+        None if days <= Duration::days(90) => Ok(PriceInterval::OneHour),
+        None => Ok(PriceInterval::OneDay),
+    }
+}
+
+// Candle size of OHLC data covering `days`. Follows CoinGecko: 30 minutes for
+// up to 2 days, 4 hours up to 30 days, 4 days beyond.
+fn ohlc_timeframe(days: Duration) -> Timeframe {
+    if days <= Duration::days(2) {
+        Timeframe {
+            count: 30,
+            unit: TimeUnit::Minute,
+        }
+    } else if days <= Duration::days(30) {
+        PriceInterval::FourHours.into()
+    } else {
+        Timeframe {
+            count: 4,
+            unit: TimeUnit::Day,
+        }
+    }
+}
+
+// Split a comma-separated list parameter
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|item| item.trim().to_lowercase())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[derive(Serialize)]
+struct Ping {
+    gecko_says: &'static str,
+}
+
+// Check API server status
+async fn ping() -> Json<Ping> {
+    Json(Ping {
+        gecko_says: "(V3) To the Moon!",
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct SimplePriceQuery {
+    ids: String,
+    vs_currencies: String,
+    #[serde(default)]
+    include_market_cap: bool,
+    #[serde(default)]
+    include_24hr_vol: bool,
+    #[serde(default)]
+    include_24hr_change: bool,
+    #[serde(default)]
+    include_last_updated_at: bool,
+    precision: Option<String>,
+}
+
+// Get aggregated prices of coins in any of the requested currencies. Coins and
+// currencies without a price are left out, as CoinGecko does.
+async fn simple_price(
+    State(service): State<SharedService>,
+    Query(query): Query<SimplePriceQuery>,
+) -> Result<Json<Map<String, Value>>, ApiError> {
+    let service = service.read().await;
+    let precision = parse_precision(query.precision.as_deref())?;
+
+    let mut result = Map::new();
+
+    for coin_id in split_list(&query.ids) {
+        let Ok(coin) = service.get_coin(&coin_id) else {
+            continue;
+        };
+        let metadata = service.get_coin_metadata(&coin_id)?;
+
+        let mut entry = Map::new();
+        for currency in split_list(&query.vs_currencies) {
+            let pair = coin.pair(&currency.to_uppercase());
+            let config = service.aggregation_config();
+
+            // Currencies no exchange quotes the coin in are priced through a
+            // route, which has no 24h change
+            let (aggregated, prices) =
+                match service.get_current_price(&coin_id, &currency, None).await {
+                    Ok(prices) => match aggregate::aggregate(&pair, &prices, config) {
+                        Ok(aggregated) => (aggregated, prices),
+                        Err(_) => continue,
+                    },
+                    Err(_) => match service
+                        .get_aggregated_price(&coin_id, &currency, config)
+                        .await
+                    {
+                        Ok(aggregated) => (aggregated, Vec::new()),
+                        Err(e) => {
+                            debug!("No {} price for {}: {}", currency, coin_id, e);
+                            continue;
+                        }
+                    },
+                };
+
+            entry.insert(
+                currency.clone(),
+                round_decimal(aggregated.price, precision).into(),
+            );
+
+            if query.include_market_cap {
+                let market_cap = metadata
+                    .circulating_supply
+                    .and_then(|supply| supply.checked_mul(aggregated.price))
+                    .map(|market_cap| round_decimal(market_cap, precision))
+                    .unwrap_or(0.0);
+                entry.insert(format!("{}_market_cap", currency), market_cap.into());
+            }
+            if query.include_24hr_vol {
+                let volume = round_decimal(aggregated.volume_24h.unwrap_or_default(), precision);
+                entry.insert(format!("{}_24h_vol", currency), volume.into());
+            }
+            if query.include_24hr_change {
+                // Median change of the exchanges that contributed to the price
+                let changes: Vec<f64> = prices
+                    .iter()
+                    .filter(|p| aggregated.sources.iter().any(|s| s.exchange == p.exchange))
+                    .filter_map(|p| p.change_percent_24h.map(to_f64))
+                    .collect();
+                let change = median(&changes).map(|c| round(c, precision));
+                entry.insert(format!("{}_24h_change", currency), change.into());
+            }
+            if query.include_last_updated_at {
+                entry.insert(
+                    "last_updated_at".to_string(),
+                    aggregated.timestamp.timestamp().into(),
+                );
+            }
+        }
+
+        if !entry.is_empty() {
+            result.insert(coin_id, Value::Object(entry));
+        }
+    }
+
+    Ok(Json(result))
+}
+
+// List quote currencies that coins in the catalog trade against
+async fn supported_vs_currencies(
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<String>>, ApiError> {
+    let service = service.read().await;
+    let coins = service.list_coins().await?;
+    let markets = service.list_markets(&MarketFilter::default()).await?;
+
+    let currencies: BTreeSet<String> = markets
+        .iter()
+        .filter(|market| {
+            coins
+                .iter()
+                .any(|coin| coin.symbol_on(market.exchange) == market.pair.base)
+        })
+        .map(|market| market.pair.quote.to_lowercase())
+        .collect();
+
+    Ok(Json(currencies.into_iter().collect()))
+}
+
+#[derive(Debug, Deserialize)]
+struct CoinsListQuery {
+    #[serde(default)]
+    include_platform: bool,
+}
+
+#[derive(Serialize)]
+struct CoinsListEntry {
+    id: String,
+    symbol: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    platforms: Option<BTreeMap<String, String>>,
+}
+
+// List all coins with their ids, symbols and names
+async fn coins_list(
+    State(service): State<SharedService>,
+    Query(query): Query<CoinsListQuery>,
+) -> Result<Json<Vec<CoinsListEntry>>, ApiError> {
+    let service = service.read().await;
+    let mut coins = service.list_coins().await?;
+    coins.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut entries = Vec::with_capacity(coins.len());
+    for coin in coins {
+        let platforms = if query.include_platform {
+            Some(service.get_coin_metadata(&coin.id)?.contracts)
+        } else {
+            None
+        };
+
+        entries.push(CoinsListEntry {
+            symbol: coin.symbol.to_lowercase(),
+            id: coin.id,
+            name: coin.name,
+            platforms,
+        });
+    }
+
+    Ok(Json(entries))
+}
+
+// Fetch price history covering the last `days`, oldest first
+async fn history_since(
+    service: &SharedService,
+    coin_id: &str,
+    vs_currency: &str,
+    timeframe: Timeframe,
+    days: Duration,
+) -> Result<Vec<PriceHistoryPoint>, ApiError> {
+    let service = service.read().await;
+    let end = Utc::now();
+    let start = end - days;
+    let bars = (days.num_seconds() / timeframe.max_duration().num_seconds().max(1)) as usize + 1;
+
+    let history: PriceHistory = service
+        .get_price_history(
+            coin_id,
+            vs_currency,
+            timeframe,
+            None,
+            Some(start),
+            Some(end),
+            Some(bars),
+        )
+        .await?;
+
+    let mut data = history.data;
+    data.retain(|point| point.timestamp >= start);
+    data.sort_by_key(|point| point.timestamp);
+    Ok(data)
+}
+
+#[derive(Debug, Deserialize)]
+struct MarketChartQuery {
+    vs_currency: String,
+    days: String,
+    interval: Option<String>,
+    precision: Option<String>,
+}
+
+#[derive(Serialize)]
+struct MarketChart {
+    prices: Vec<(i64, f64)>,
+    market_caps: Vec<(i64, f64)>,
+    total_volumes: Vec<(i64, f64)>,
+}
+
+// Get historical prices, market caps and volumes
+async fn market_chart(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<MarketChartQuery>,
+) -> Result<Json<MarketChart>, ApiError> {
+    let days = parse_days(&query.days)?;
+    let precision = parse_precision(query.precision.as_deref())?;
+
+    let interval = chart_interval(days, query.interval.as_deref())?;
+
+    let data = history_since(
+        &service,
+        &coin_id,
+        &query.vs_currency,
+        interval.into(),
+        days,
+    )
+    .await?;
+    let supply = service
+        .read()
+        .await
+        .get_coin_metadata(&coin_id)?
+        .circulating_supply;
+
+    let mut chart = MarketChart {
+        prices: Vec::with_capacity(data.len()),
+        market_caps: Vec::with_capacity(data.len()),
+        total_volumes: Vec::with_capacity(data.len()),
+    };
+
+    for point in &data {
+        let time = point.timestamp.timestamp_millis();
+        chart
+            .prices
+            .push((time, round_decimal(point.price, precision)));
+        if let Some(market_cap) = supply.and_then(|supply| supply.checked_mul(point.price)) {
+            chart
+                .market_caps
+                .push((time, round_decimal(market_cap, precision)));
+        }
+        // Bar volumes are in the base asset; CoinGecko reports them in the quote
+        let volume = point
+            .volume
+            .and_then(|volume| volume.checked_mul(point.price))
+            .unwrap_or_default();
+        chart
+            .total_volumes
+            .push((time, round_decimal(volume, precision)));
+    }
+
+    Ok(Json(chart))
+}
+
+#[derive(Debug, Deserialize)]
+struct OhlcQuery {
+    vs_currency: String,
+    days: String,
+    precision: Option<String>,
+}
+
+// Get OHLC candles
+async fn ohlc(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<OhlcQuery>,
+) -> Result<Json<Vec<(i64, f64, f64, f64, f64)>>, ApiError> {
+    let days = parse_days(&query.days)?;
+    let precision = parse_precision(query.precision.as_deref())?;
+
+    let data = history_since(
+        &service,
+        &coin_id,
+        &query.vs_currency,
+        ohlc_timeframe(days),
+        days,
+    )
+    .await?;
+
+    let candles = data
+        .iter()
+        .map(|point| {
+            (
+                point.timestamp.timestamp_millis(),
+                round_decimal(point.open.unwrap_or(point.price), precision),
+                round_decimal(point.high.unwrap_or(point.price), precision),
+                round_decimal(point.low.unwrap_or(point.price), precision),
+                round_decimal(point.price, precision),
+            )
+        })
+        .collect();
+
+    Ok(Json(candles))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality::QualityConfig;
+    use crate::service::CoinService;
+    use connectors::binance::BinanceConnector;
+    use connectors::coinbase::CoinbaseConnector;
+    use connectors::http::{HttpClient, HttpMode};
+    use std::sync::Arc;
+    use store::{PriceStore, StoreConfig};
+    use tokio::sync::RwLock;
+
+    fn days(days: &str) -> Duration {
+        parse_days(days).unwrap()
+    }
+
+    #[test]
+    fn days_are_numbers_fractions_or_max() {
+        assert_eq!(days("7"), Duration::days(7));
+        assert_eq!(days("0.5"), Duration::hours(12));
+        assert_eq!(days("max"), Duration::days(MAX_DAYS));
+        // Longer ranges are capped like `max`
+        assert_eq!(days("100000"), Duration::days(MAX_DAYS));
+
+        for invalid in ["0", "-1", "week", ""] {
+            assert!(parse_days(invalid).is_err(), "{} accepted", invalid);
+        }
+    }
+
+    #[test]
+    fn chart_granularity_follows_days() {
+        let interval = |d: &str| chart_interval(days(d), None).unwrap();
+
+        assert_eq!(interval("1"), PriceInterval::FiveMinutes);
+        assert_eq!(interval("1.5"), PriceInterval::OneHour);
+        assert_eq!(interval("90"), PriceInterval::OneHour);
+        assert_eq!(interval("91"), PriceInterval::OneDay);
+        assert_eq!(interval("max"), PriceInterval::OneDay);
+
+        // A requested interval wins
+        assert_eq!(
+            chart_interval(days("1"), Some("daily")).unwrap(),
+            PriceInterval::OneDay
+        );
+        assert_eq!(
+            chart_interval(days("365"), Some("hourly")).unwrap(),
+            PriceInterval::OneHour
+        );
+        assert!(chart_interval(days("1"), Some("minutely")).is_err());
+    }
+
+    #[test]
+    fn candle_size_follows_days() {
+        let timeframe = |d: &str| ohlc_timeframe(days(d)).to_string();
+
+        assert_eq!(timeframe("1"), "30m");
+        assert_eq!(timeframe("2"), "30m");
+        assert_eq!(timeframe("14"), "4h");
+        assert_eq!(timeframe("30"), "4h");
+        assert_eq!(timeframe("90"), "4d");
+    }
+
+    #[tokio::test]
+    async fn simple_price_maps_coins_to_currencies() {
+        let fixtures =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../connectors/tests/fixtures");
+        let replay = |name| HttpClient::new(HttpMode::Replay(fixtures.join(name)));
+        let coinbase =
+            CoinbaseConnector::with_base_url("http://coinbase.test/").with_http(replay("coinbase"));
+        let binance = BinanceConnector::with_base_url("http://binance.test/api/v3")
+            .with_http(replay("binance"));
+        // Store nothing listens on
+        let store = PriceStore::new(StoreConfig {
+            url: "http://127.0.0.1:1".to_string(),
+            token: "token".to_string(),
+            org: "org".to_string(),
+            bucket: "bucket".to_string(),
+            store_trades: false,
+            price_max_age_secs: 60,
+        })
+        .unwrap();
+        // The recorded prices are old, so they must not be flagged stale
+        let service = CoinService::new(Arc::new(coinbase), Arc::new(binance), Arc::new(store))
+            .with_quality(QualityConfig {
+                stale_after: Duration::days(365 * 100),
+                ..QualityConfig::default()
+            });
+
+        let query = SimplePriceQuery {
+            ids: "bitcoin,unknown".to_string(),
+            vs_currencies: "usd".to_string(),
+            include_market_cap: true,
+            include_24hr_vol: true,
+            include_24hr_change: true,
+            include_last_updated_at: true,
+            precision: Some("2".to_string()),
+        };
+        let Json(prices) = simple_price(State(Arc::new(RwLock::new(service))), Query(query))
+            .await
+            .unwrap();
+
+        // Unknown coins are left out, and a market cap without a known
+        // supply is zero
+        assert_eq!(
+            Value::Object(prices),
+            serde_json::json!({
+                "bitcoin": {
+                    "usd": 61000.5,
+                    "usd_market_cap": 0.0,
+                    "usd_24h_vol": 759192685.74,
+                    "usd_24h_change": 1.67,
+                    "last_updated_at": 1709294400,
+                }
+            })
+        );
+    }
+}
@@ -1,9 +1,42 @@
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tracing::warn;
+
+/// Which Coinbase API serves Coinbase market data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoinbaseApi {
+    /// The Exchange API (api.exchange.coinbase.com)
+    #[default]
+    Exchange,
+    /// The public market endpoints of the Advanced Trade API
+    Advanced,
+}
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ApiConfig {
     pub host: String,
     pub port: u16,
+    /// How often market metadata is reloaded from the exchanges, in seconds
+    pub market_refresh_secs: u64,
+    /// Bearer token required by admin endpoints; admin endpoints are disabled if unset
+    pub admin_token: Option<String>,
+    /// Base URL of the Binance spot API, if not the public one
+    pub binance_url: Option<String>,
+    /// Base URL of the Binance USDⓈ-M futures API, if not the public one
+    pub binance_futures_url: Option<String>,
+    /// Base URL of the Coinbase Exchange API, if not the public one
+    pub coinbase_url: Option<String>,
+    /// Coinbase API market data is read from
+    pub coinbase_api: CoinbaseApi,
+    /// Base URL of the Coinbase Advanced Trade API, if not the public one
+    pub coinbase_advanced_url: Option<String>,
+    /// Serve simulated markets instead of connecting to the exchanges
+    pub simulated_exchanges: bool,
+    /// Spec files of exchanges served by the declarative REST connector
+    pub rest_connector_specs: Vec<PathBuf>,
+    /// Spec files of decentralized exchanges priced from on-chain pools
+    pub dex_connector_specs: Vec<PathBuf>,
 }
 
 impl Default for ApiConfig {
@@ -11,6 +44,16 @@ impl Default for ApiConfig {
         Self {
             host: "0.0.0.0".to_string(),
             port: 3000,
+            market_refresh_secs: 3600,
+            admin_token: None,
+            binance_url: None,
+            binance_futures_url: None,
+            coinbase_url: None,
+            coinbase_api: CoinbaseApi::default(),
+            coinbase_advanced_url: None,
+            simulated_exchanges: false,
+            rest_connector_specs: Vec::new(),
+            dex_connector_specs: Vec::new(),
         }
     }
 }
@@ -22,7 +65,50 @@ impl ApiConfig {
             .ok()
             .and_then(|p| p.parse().ok())
             .unwrap_or(3000);
+        let market_refresh_secs = std::env::var("MARKET_REFRESH_SECS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(3600);
+        let admin_token = std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+        let url = |name: &str| std::env::var(name).ok().filter(|u| !u.is_empty());
+        let paths = |name: &str| -> Vec<PathBuf> {
+            std::env::var(name)
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|p| !p.is_empty())
+                        .map(PathBuf::from)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        let coinbase_api = match std::env::var("COINBASE_API").as_deref() {
+            Ok("advanced") => CoinbaseApi::Advanced,
+            Ok("exchange") | Ok("") | Err(_) => CoinbaseApi::Exchange,
+            Ok(other) => {
+                warn!(
+                    "Unknown COINBASE_API {}, expected exchange or advanced; using exchange",
+                    other
+                );
+                CoinbaseApi::Exchange
+            }
+        };
 
-        Self { host, port }
+        Self {
+            host,
+            port,
+            market_refresh_secs,
+            admin_token,
+            binance_url: url("BINANCE_API_URL"),
+            binance_futures_url: url("BINANCE_FUTURES_API_URL"),
+            coinbase_url: url("COINBASE_API_URL"),
+            coinbase_api,
+            coinbase_advanced_url: url("COINBASE_ADVANCED_API_URL"),
+            simulated_exchanges: std::env::var("SIMULATED_EXCHANGES")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+            rest_connector_specs: paths("REST_CONNECTOR_SPECS"),
+            dex_connector_specs: paths("DEX_CONNECTOR_SPECS"),
+        }
     }
 } 
\ No newline at end of file
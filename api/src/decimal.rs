@@ -0,0 +1,18 @@
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+// Prices, volumes, rates and values are carried as exact decimals; only
+// statistics derived from them (weights, deviations, percentages, indicators)
+// are computed in floating point.
+
+/// Float value of an exact decimal, for statistics
+pub fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or_default()
+}
+
+/// Sum of decimal values, or `None` if it is out of range
+pub fn checked_sum(values: impl IntoIterator<Item = Decimal>) -> Option<Decimal> {
+    values
+        .into_iter()
+        .try_fold(Decimal::ZERO, |sum, value| sum.checked_add(value))
+}
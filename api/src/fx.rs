@@ -0,0 +1,244 @@
+use chrono::{Duration, NaiveDate, NaiveTime};
+use common::models::{FxConversion, FxRates};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::RwLock;
+
+/// Settings for converting prices between fiat currencies
+#[derive(Debug, Clone)]
+pub struct FxConfig {
+    /// Base URL of the ECB-format rate files, or a `file://` directory holding
+    /// them; the ECB itself if unset
+    pub rates_url: Option<String>,
+    /// Currencies coins are priced in before converting, in order of preference
+    pub source_currencies: Vec<String>,
+    /// How many days of rates to load when none are stored yet
+    pub history_days: i64,
+    /// How old the latest rate may be before a conversion is refused
+    pub max_age_days: i64,
+    /// How often new rates are fetched
+    pub refresh_secs: u64,
+}
+
+impl Default for FxConfig {
+    fn default() -> Self {
+        Self {
+            rates_url: None,
+            source_currencies: vec!["USD".to_string(), "EUR".to_string()],
+            history_days: 365,
+            max_age_days: 7,
+            refresh_secs: 3600,
+        }
+    }
+}
+
+impl FxConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_days = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .filter(|v| *v > 0)
+        };
+
+        Self {
+            rates_url: std::env::var("FX_RATES_URL").ok().filter(|v| !v.is_empty()),
+            source_currencies: std::env::var("FX_SOURCE_CURRENCIES")
+                .ok()
+                .map(|v| {
+                    v.split(',')
+                        .map(|c| c.trim().to_uppercase())
+                        .filter(|c| !c.is_empty())
+                        .collect::<Vec<_>>()
+                })
+                .filter(|currencies| !currencies.is_empty())
+                .unwrap_or(default.source_currencies),
+            history_days: env_days("FX_HISTORY_DAYS").unwrap_or(default.history_days),
+            max_age_days: env_days("FX_MAX_AGE_DAYS").unwrap_or(default.max_age_days),
+            refresh_secs: std::env::var("FX_REFRESH_SECS")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+                .unwrap_or(default.refresh_secs),
+        }
+    }
+}
+
+// Units of `to` per unit of `from` on a day, if both currencies were quoted
+fn cross_rate(day: &FxRates, from: &str, to: &str) -> Option<Decimal> {
+    let per_base = |currency: &str| {
+        if currency == day.base {
+            Some(Decimal::ONE)
+        } else {
+            day.rates.get(currency).copied()
+        }
+    };
+
+    per_base(to)?.checked_div(per_base(from)?)
+}
+
+/// Daily fiat exchange rates, by day
+#[derive(Default)]
+pub struct FxTable {
+    days: RwLock<BTreeMap<NaiveDate, FxRates>>,
+}
+
+impl FxTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace the rates of some days
+    pub fn insert(&self, rates: Vec<FxRates>) {
+        let mut days = self.days.write().unwrap_or_else(|e| e.into_inner());
+        for day in rates {
+            days.insert(day.date, day);
+        }
+    }
+
+    /// Most recent day with rates
+    pub fn latest_date(&self) -> Option<NaiveDate> {
+        let days = self.days.read().unwrap_or_else(|e| e.into_inner());
+        days.keys().next_back().copied()
+    }
+
+    /// Currencies quoted on the most recent day, including the base currency
+    pub fn currencies(&self) -> BTreeSet<String> {
+        let days = self.days.read().unwrap_or_else(|e| e.into_inner());
+        days.values()
+            .next_back()
+            .map(|day| {
+                day.rates
+                    .keys()
+                    .cloned()
+                    .chain(std::iter::once(day.base.clone()))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether a currency is quoted on the most recent day
+    pub fn supports(&self, currency: &str) -> bool {
+        self.currencies().contains(&currency.to_uppercase())
+    }
+
+    /// Rates of the latest day at or before `date`, quoted against `base`
+    pub fn rates_on(&self, base: &str, date: NaiveDate) -> Option<FxRates> {
+        let base = base.to_uppercase();
+        let days = self.days.read().unwrap_or_else(|e| e.into_inner());
+        let (_, day) = days.range(..=date).next_back()?;
+
+        let rates = day
+            .rates
+            .keys()
+            .chain(std::iter::once(&day.base))
+            .filter(|currency| **currency != base)
+            .filter_map(|currency| Some((currency.clone(), cross_rate(day, &base, currency)?)))
+            .collect();
+
+        Some(FxRates {
+            source: day.source.clone(),
+            base,
+            date: day.date,
+            rates,
+        })
+    }
+
+    /// Rate converting `from` into `to` on the latest day at or before `date`
+    /// with both currencies quoted, no more than `max_age` days earlier
+    pub fn conversion(
+        &self,
+        from: &str,
+        to: &str,
+        date: NaiveDate,
+        max_age: i64,
+    ) -> Option<FxConversion> {
+        let (from, to) = (from.to_uppercase(), to.to_uppercase());
+        let days = self.days.read().unwrap_or_else(|e| e.into_inner());
+
+        days.range(date - Duration::days(max_age)..=date)
+            .rev()
+            .find_map(|(date, day)| {
+                let rate = cross_rate(day, &from, &to)?;
+                Some(FxConversion {
+                    from: from.clone(),
+                    to: to.clone(),
+                    rate,
+                    source: day.source.clone(),
+                    timestamp: date.and_time(NaiveTime::MIN).and_utc(),
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use connectors::{ecb::EcbConnector, FxRateConnector};
+
+    // Table of the sample ECB rates checked in with the connector tests:
+    // 2024-03-06 to 2024-03-08, a Friday
+    async fn table() -> FxTable {
+        let dir = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../connectors/tests/fixtures/ecb"
+        );
+        let days = EcbConnector::with_base_url(&format!("file://{}", dir))
+            .get_rate_history(date("2024-01-01"))
+            .await
+            .unwrap();
+
+        let table = FxTable::new();
+        table.insert(days);
+        table
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn cross_rates_are_derived_through_the_euro() {
+        let table = table().await;
+
+        let usd = table.rates_on("usd", date("2024-03-08")).unwrap();
+        assert_eq!(usd.base, "USD");
+        assert_eq!(usd.rates["EUR"], Decimal::ONE / decimal("1.0938"));
+        assert_eq!(usd.rates["GBP"], decimal("0.85098") / decimal("1.0938"));
+        assert_eq!(usd.rates["JPY"], decimal("160.52") / decimal("1.0938"));
+        assert!(!usd.rates.contains_key("USD"));
+
+        let conversion = table
+            .conversion("GBP", "JPY", date("2024-03-07"), 0)
+            .unwrap();
+        assert_eq!(conversion.rate, decimal("161.02") / decimal("0.85515"));
+    }
+
+    #[tokio::test]
+    async fn conversions_fall_back_to_earlier_days() {
+        let table = table().await;
+        assert_eq!(table.latest_date(), Some(date("2024-03-08")));
+
+        // Sunday uses Friday's rates, within the maximum age
+        let conversion = table
+            .conversion("EUR", "USD", date("2024-03-10"), 3)
+            .unwrap();
+        assert_eq!(conversion.timestamp.date_naive(), date("2024-03-08"));
+        assert_eq!(conversion.rate, decimal("1.0938"));
+        assert!(table
+            .conversion("EUR", "USD", date("2024-03-10"), 1)
+            .is_none());
+
+        // CHF was not quoted on 2024-03-06
+        let chf = table
+            .conversion("USD", "CHF", date("2024-03-06"), 7)
+            .map(|c| c.rate);
+        assert_eq!(chf, None);
+        assert!(table.supports("chf"));
+    }
+}
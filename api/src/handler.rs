@@ -1,24 +1,35 @@
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, Request, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
     Json,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use common::{
-    models::{Coin, CurrentPrice, Exchange, PriceHistory, PriceInterval},
+    models::{
+        AccountImport, AggregatedPrice, AggregationMethod, AlertRule, AlertRuleSpec, Basis,
+        BasisHistory, BatchPrices, ClockSkew, Coin, CoinMetadata, CoinProfile, ConvertedPrice,
+        ConvertedPriceHistory, CurrentPrice, Exchange, ExchangeAccount, ExchangeAccountSpec,
+        ExchangeStatus, FundingRateHistory, FxRates, IndicatorSeries, MarketInfo, PerpetualHistory,
+        PerpetualMarket, Portfolio, PortfolioSpec, PortfolioValuation, PortfolioValueHistory,
+        PriceHistory, PriceInterval, RoutedPrice, RoutedPriceHistory, Timeframe, Trade,
+    },
     Error as CommonError,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{debug, error};
 
+use crate::batch;
+use crate::indicators::{Indicator, IndicatorParams};
+use crate::markets::MarketFilter;
 use crate::service::CoinService;
 
-type SharedService = Arc<RwLock<CoinService>>;
+pub(crate) type SharedService = Arc<RwLock<CoinService>>;
 
 // Create a wrapper for our common::Error type
+#[derive(Debug)]
 pub struct ApiError(CommonError);
 
 // Implement From<CommonError> for ApiError
@@ -33,8 +44,11 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, message) = match self.0 {
             CommonError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
+            CommonError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
             CommonError::ExchangeError(msg) => (StatusCode::BAD_GATEWAY, msg),
+            err @ CommonError::ExchangeStatus { .. } => (StatusCode::BAD_GATEWAY, err.to_string()),
             CommonError::ParseError(msg) => (StatusCode::BAD_REQUEST, msg),
+            CommonError::InvalidRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             CommonError::DbError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             CommonError::HttpError(e) => (
                 StatusCode::BAD_GATEWAY,
@@ -53,6 +67,36 @@ impl IntoResponse for ApiError {
     }
 }
 
+/// Most bars or points returned by a history endpoint
+const MAX_HISTORY_LIMIT: usize = 10_000;
+
+// Parse the interval and limit parameters of a history query, defaulting to
+// daily bars
+fn parse_history_params(
+    interval: Option<&str>,
+    limit: Option<usize>,
+) -> Result<(Timeframe, Option<usize>), ApiError> {
+    let interval = match interval {
+        Some(interval) => interval.parse::<Timeframe>()?,
+        None => PriceInterval::OneDay.into(),
+    };
+
+    if let Some(limit) = limit.filter(|limit| *limit > MAX_HISTORY_LIMIT) {
+        return Err(CommonError::InvalidRequest(format!(
+            "Invalid limit: {}. Expected at most {}",
+            limit, MAX_HISTORY_LIMIT
+        ))
+        .into());
+    }
+
+    Ok((interval, limit))
+}
+
+// Parse an optional exchange query parameter
+fn parse_exchange(exchange: Option<&str>) -> Result<Option<Exchange>, ApiError> {
+    Ok(exchange.map(str::parse::<Exchange>).transpose()?)
+}
+
 // Return all supported coins
 pub async fn list_coins(State(service): State<SharedService>) -> Result<Json<Vec<Coin>>, ApiError> {
     let service = service.read().await;
@@ -60,6 +104,124 @@ pub async fn list_coins(State(service): State<SharedService>) -> Result<Json<Vec
     Ok(Json(coins))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ProfileQuery {
+    pub currency: Option<String>,
+}
+
+// Get the full profile of a coin
+pub async fn get_coin_profile(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<ProfileQuery>,
+) -> Result<Json<CoinProfile>, ApiError> {
+    let service = service.read().await;
+
+    // Default to USD if no currency specified
+    let currency = query.currency.unwrap_or_else(|| "USD".to_string());
+
+    let profile = service.get_coin_profile(&coin_id, &currency).await?;
+    Ok(Json(profile))
+}
+
+// Replace the metadata of a coin
+pub async fn update_coin_metadata(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Json(metadata): Json<CoinMetadata>,
+) -> Result<Json<CoinMetadata>, ApiError> {
+    let service = service.read().await;
+    let metadata = service.update_coin_metadata(&coin_id, metadata).await?;
+    Ok(Json(metadata))
+}
+
+// List connected exchange accounts
+pub async fn list_accounts(
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<ExchangeAccount>>, ApiError> {
+    let service = service.read().await;
+    Ok(Json(service.list_accounts()))
+}
+
+// Get an exchange account
+pub async fn get_account(
+    State(service): State<SharedService>,
+    Path(account_id): Path<String>,
+) -> Result<Json<ExchangeAccount>, ApiError> {
+    let service = service.read().await;
+    Ok(Json(service.get_account(&account_id)?))
+}
+
+// Connect an exchange account
+pub async fn create_account(
+    State(service): State<SharedService>,
+    Json(spec): Json<ExchangeAccountSpec>,
+) -> Result<(StatusCode, Json<ExchangeAccount>), ApiError> {
+    let service = service.read().await;
+    let account = service.create_account(spec).await?;
+    Ok((StatusCode::CREATED, Json(account)))
+}
+
+// Disconnect an exchange account
+pub async fn delete_account(
+    State(service): State<SharedService>,
+    Path(account_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let service = service.read().await;
+    service.delete_account(&account_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AccountImportQuery {
+    /// Comma-separated assets to import fills of, instead of those held
+    pub assets: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+}
+
+// Import the balances and fills of an exchange account
+pub async fn import_account(
+    State(service): State<SharedService>,
+    Path(account_id): Path<String>,
+    Query(query): Query<AccountImportQuery>,
+) -> Result<Json<AccountImport>, ApiError> {
+    let service = service.read().await;
+    let assets = query.assets.map(|assets| {
+        assets
+            .split(',')
+            .map(|asset| asset.trim().to_uppercase())
+            .filter(|asset| !asset.is_empty())
+            .collect()
+    });
+    let import = service
+        .import_account(&account_id, assets, query.since)
+        .await?;
+    Ok(Json(import))
+}
+
+// Reject admin requests without the configured bearer token
+pub async fn require_admin<B>(
+    State(admin_token): State<Option<String>>,
+    headers: HeaderMap,
+    request: Request<B>,
+    next: Next<B>,
+) -> Result<Response, ApiError> {
+    let Some(expected) = admin_token else {
+        return Err(CommonError::Unauthorized("Admin API is disabled".to_string()).into());
+    };
+
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    if provided != Some(expected.as_str()) {
+        return Err(CommonError::Unauthorized("Invalid admin token".to_string()).into());
+    }
+
+    Ok(next.run(request).await)
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PriceQuery {
     pub currency: Option<String>,
@@ -78,22 +240,142 @@ pub async fn get_current_price(
     let currency = query.currency.unwrap_or_else(|| "USD".to_string());
     
     // Parse exchange parameter if provided
-    let exchange = match query.exchange.as_deref() {
-        Some("coinbase") => Some(Exchange::Coinbase),
-        Some("binance") => Some(Exchange::Binance),
-        Some(unknown) => {
-            return Err(CommonError::ParseError(format!(
-                "Unknown exchange: {}. Supported exchanges: coinbase, binance",
-                unknown
-            )).into())
-        }
-        None => None,
-    };
+    let exchange = parse_exchange(query.exchange.as_deref())?;
 
     let prices = service.get_current_price(&coin_id, &currency, exchange).await?;
     Ok(Json(prices))
 }
 
+#[derive(Debug, Deserialize)]
+pub struct AggregatedPriceQuery {
+    pub currency: Option<String>,
+    pub method: Option<String>,
+    pub max_deviation: Option<f64>,
+    pub min_sources: Option<usize>,
+}
+
+// Get a single price for a coin aggregated across exchanges
+pub async fn get_aggregated_price(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<AggregatedPriceQuery>,
+) -> Result<Json<AggregatedPrice>, ApiError> {
+    let service = service.read().await;
+
+    // Default to USD if no currency specified
+    let currency = query.currency.unwrap_or_else(|| "USD".to_string());
+
+    // Override the configured aggregation settings with any query parameters
+    let mut config = *service.aggregation_config();
+    if let Some(method) = query.method.as_deref() {
+        config.method = method.parse::<AggregationMethod>()?;
+    }
+    if let Some(max_deviation) = query.max_deviation {
+        config.max_deviation_percent = Some(max_deviation).filter(|v| *v > 0.0);
+    }
+    if let Some(min_sources) = query.min_sources {
+        config.min_sources = min_sources;
+    }
+
+    let price = service
+        .get_aggregated_price(&coin_id, &currency, &config)
+        .await?;
+
+    Ok(Json(price))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoutedPriceQuery {
+    pub currency: Option<String>,
+}
+
+// Get a coin's price derived through intermediate markets
+pub async fn get_routed_price(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<RoutedPriceQuery>,
+) -> Result<Json<RoutedPrice>, ApiError> {
+    let service = service.read().await;
+
+    // Default to USD if no currency specified
+    let currency = query.currency.unwrap_or_else(|| "USD".to_string());
+
+    let price = service.get_routed_price(&coin_id, &currency).await?;
+    Ok(Json(price))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchPriceQuery {
+    pub ids: String,
+    pub currencies: Option<String>,
+}
+
+// Get aggregated prices of several coins in several currencies
+pub async fn get_batch_prices(
+    State(service): State<SharedService>,
+    Query(query): Query<BatchPriceQuery>,
+) -> Result<Json<BatchPrices>, ApiError> {
+    let service = service.read().await;
+
+    let ids = batch::parse_list(&query.ids, str::to_lowercase);
+    // Default to USD if no currency specified
+    let currencies = batch::parse_list(
+        query.currencies.as_deref().unwrap_or("USD"),
+        str::to_uppercase,
+    );
+
+    let prices = service.get_batch_prices(&ids, &currencies).await?;
+    Ok(Json(prices))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConvertedPriceQuery {
+    pub currency: Option<String>,
+    pub via: Option<String>,
+}
+
+// Get a coin's price converted from another fiat currency
+pub async fn get_converted_price(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<ConvertedPriceQuery>,
+) -> Result<Json<ConvertedPrice>, ApiError> {
+    let service = service.read().await;
+
+    // Default to USD if no currency specified
+    let currency = query.currency.unwrap_or_else(|| "USD".to_string());
+
+    let price = service
+        .get_converted_price(
+            &coin_id,
+            &currency,
+            query.via.as_deref(),
+            service.aggregation_config(),
+        )
+        .await?;
+    Ok(Json(price))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FxRatesQuery {
+    pub base: Option<String>,
+    pub date: Option<NaiveDate>,
+}
+
+// Get the fiat exchange rates of a day
+pub async fn get_fx_rates(
+    State(service): State<SharedService>,
+    Query(query): Query<FxRatesQuery>,
+) -> Result<Json<FxRates>, ApiError> {
+    let service = service.read().await;
+
+    // Default to EUR, the currency reference rates are published against
+    let base = query.base.unwrap_or_else(|| "EUR".to_string());
+
+    let rates = service.get_fx_rates(&base, query.date)?;
+    Ok(Json(rates))
+}
+
 #[derive(Debug, Deserialize)]
 pub struct HistoryQuery {
     pub currency: Option<String>,
@@ -116,34 +398,10 @@ pub async fn get_price_history(
     let currency = query.currency.unwrap_or_else(|| "USD".to_string());
     
     // Parse exchange parameter if provided
-    let exchange = match query.exchange.as_deref() {
-        Some("coinbase") => Some(Exchange::Coinbase),
-        Some("binance") => Some(Exchange::Binance),
-        Some(unknown) => {
-            return Err(CommonError::ParseError(format!(
-                "Unknown exchange: {}. Supported exchanges: coinbase, binance",
-                unknown
-            )).into())
-        }
-        None => None,
-    };
+    let exchange = parse_exchange(query.exchange.as_deref())?;
 
-    // Parse interval parameter, default to daily
-    let interval = match query.interval.as_deref() {
-        Some("1m") => PriceInterval::OneMinute,
-        Some("5m") => PriceInterval::FiveMinutes,
-        Some("15m") => PriceInterval::FifteenMinutes,
-        Some("1h") => PriceInterval::OneHour,
-        Some("4h") => PriceInterval::FourHours,
-        Some("1d") | None => PriceInterval::OneDay,
-        Some("1w") => PriceInterval::OneWeek,
-        Some(unknown) => {
-            return Err(CommonError::ParseError(format!(
-                "Unknown interval: {}. Supported intervals: 1m, 5m, 15m, 1h, 4h, 1d, 1w",
-                unknown
-            )).into())
-        }
-    };
+    // Parse interval and limit parameters, default to daily
+    let (interval, limit) = parse_history_params(query.interval.as_deref(), query.limit)?;
 
     let history = service
         .get_price_history(
@@ -153,9 +411,468 @@ pub async fn get_price_history(
             exchange,
             query.start,
             query.end,
-            query.limit,
+            limit,
+        )
+        .await?;
+
+    Ok(Json(history))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConvertedHistoryQuery {
+    pub currency: Option<String>,
+    pub via: Option<String>,
+    pub exchange: Option<String>,
+    pub interval: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+// Get a coin's price history converted from another fiat currency
+pub async fn get_converted_history(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<ConvertedHistoryQuery>,
+) -> Result<Json<ConvertedPriceHistory>, ApiError> {
+    let service = service.read().await;
+
+    // Default to USD if no currency specified
+    let currency = query.currency.unwrap_or_else(|| "USD".to_string());
+
+    // Parse exchange parameter if provided
+    let exchange = parse_exchange(query.exchange.as_deref())?;
+
+    // Parse interval and limit parameters, default to daily
+    let (interval, limit) = parse_history_params(query.interval.as_deref(), query.limit)?;
+
+    let history = service
+        .get_converted_history(
+            &coin_id,
+            &currency,
+            query.via.as_deref(),
+            interval,
+            exchange,
+            query.start,
+            query.end,
+            limit,
         )
         .await?;
 
     Ok(Json(history))
-} 
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RoutedHistoryQuery {
+    pub currency: Option<String>,
+    pub interval: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+// Get a coin's price history derived through intermediate markets
+pub async fn get_routed_history(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<RoutedHistoryQuery>,
+) -> Result<Json<RoutedPriceHistory>, ApiError> {
+    let service = service.read().await;
+
+    // Default to USD if no currency specified
+    let currency = query.currency.unwrap_or_else(|| "USD".to_string());
+
+    // Parse interval and limit parameters, default to daily
+    let (interval, limit) = parse_history_params(query.interval.as_deref(), query.limit)?;
+
+    let history = service
+        .get_routed_history(&coin_id, &currency, interval, query.start, query.end, limit)
+        .await?;
+
+    Ok(Json(history))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PerpetualQuery {
+    pub currency: Option<String>,
+}
+
+// Get the mark price, index price and open interest of a coin's perpetual contract
+pub async fn get_perpetual_market(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<PerpetualQuery>,
+) -> Result<Json<PerpetualMarket>, ApiError> {
+    let service = service.read().await;
+
+    // Perpetual contracts are margined in USDT by default
+    let currency = query.currency.unwrap_or_else(|| "USDT".to_string());
+
+    let market = service.get_perpetual_market(&coin_id, &currency).await?;
+    Ok(Json(market))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FundingQuery {
+    pub currency: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+// Get the funding rate history of a coin's perpetual contract
+pub async fn get_funding_rates(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<FundingQuery>,
+) -> Result<Json<FundingRateHistory>, ApiError> {
+    let service = service.read().await;
+
+    // Perpetual contracts are margined in USDT by default
+    let currency = query.currency.unwrap_or_else(|| "USDT".to_string());
+
+    let history = service
+        .get_funding_rates(&coin_id, &currency, query.start, query.end, query.limit)
+        .await?;
+    Ok(Json(history))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PerpetualHistoryQuery {
+    pub currency: Option<String>,
+    pub interval: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+// Get price history of a coin's perpetual contract
+pub async fn get_perpetual_history(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<PerpetualHistoryQuery>,
+) -> Result<Json<PerpetualHistory>, ApiError> {
+    let service = service.read().await;
+
+    // Perpetual contracts are margined in USDT by default
+    let currency = query.currency.unwrap_or_else(|| "USDT".to_string());
+
+    // Parse interval and limit parameters, default to daily
+    let (interval, limit) = parse_history_params(query.interval.as_deref(), query.limit)?;
+
+    let history = service
+        .get_perpetual_history(&coin_id, &currency, interval, query.start, query.end, limit)
+        .await?;
+    Ok(Json(history))
+}
+
+// Get the basis of a coin's perpetual contract against spot
+pub async fn get_basis(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<PerpetualQuery>,
+) -> Result<Json<Basis>, ApiError> {
+    let service = service.read().await;
+
+    // Perpetual contracts are margined in USDT by default
+    let currency = query.currency.unwrap_or_else(|| "USDT".to_string());
+
+    let basis = service.get_basis(&coin_id, &currency).await?;
+    Ok(Json(basis))
+}
+
+// Get the basis history of a coin's perpetual contract against spot
+pub async fn get_basis_history(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<PerpetualHistoryQuery>,
+) -> Result<Json<BasisHistory>, ApiError> {
+    let service = service.read().await;
+
+    // Perpetual contracts are margined in USDT by default
+    let currency = query.currency.unwrap_or_else(|| "USDT".to_string());
+
+    // Parse interval and limit parameters, default to daily
+    let (interval, limit) = parse_history_params(query.interval.as_deref(), query.limit)?;
+
+    let history = service
+        .get_basis_history(&coin_id, &currency, interval, query.start, query.end, limit)
+        .await?;
+    Ok(Json(history))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IndicatorQuery {
+    pub name: String,
+    pub period: Option<usize>,
+    pub fast: Option<usize>,
+    pub slow: Option<usize>,
+    pub signal: Option<usize>,
+    pub std_dev: Option<f64>,
+    pub currency: Option<String>,
+    pub exchange: Option<String>,
+    pub interval: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+// Compute a technical indicator over a coin's price history
+pub async fn get_indicator(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<IndicatorQuery>,
+) -> Result<Json<IndicatorSeries>, ApiError> {
+    let service = service.read().await;
+
+    let indicator = Indicator::from_params(
+        &query.name.to_lowercase(),
+        IndicatorParams {
+            period: query.period,
+            fast: query.fast,
+            slow: query.slow,
+            signal: query.signal,
+            std_dev: query.std_dev,
+        },
+    )?;
+
+    // Default to USD if no currency specified
+    let currency = query.currency.unwrap_or_else(|| "USD".to_string());
+
+    // Parse exchange parameter if provided
+    let exchange = parse_exchange(query.exchange.as_deref())?;
+
+    // Parse interval and limit parameters, default to daily
+    let (interval, limit) = parse_history_params(query.interval.as_deref(), query.limit)?;
+
+    let series = service
+        .get_indicator(
+            &coin_id,
+            &currency,
+            indicator,
+            interval,
+            exchange,
+            query.start,
+            query.end,
+            limit,
+        )
+        .await?;
+
+    Ok(Json(series))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TradesQuery {
+    pub currency: Option<String>,
+    pub exchange: Option<String>,
+    pub limit: Option<usize>,
+}
+
+// Get recent trades for a coin
+pub async fn get_recent_trades(
+    State(service): State<SharedService>,
+    Path(coin_id): Path<String>,
+    Query(query): Query<TradesQuery>,
+) -> Result<Json<Vec<Trade>>, ApiError> {
+    let service = service.read().await;
+
+    // Default to USD if no currency specified
+    let currency = query.currency.unwrap_or_else(|| "USD".to_string());
+
+    // Parse exchange parameter if provided
+    let exchange = parse_exchange(query.exchange.as_deref())?;
+
+    let trades = service
+        .get_recent_trades(&coin_id, &currency, exchange, query.limit)
+        .await?;
+
+    Ok(Json(trades))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarketsQuery {
+    pub exchange: Option<String>,
+    pub base: Option<String>,
+    pub quote: Option<String>,
+}
+
+// List market metadata across exchanges
+pub async fn list_markets(
+    State(service): State<SharedService>,
+    Query(query): Query<MarketsQuery>,
+) -> Result<Json<Vec<MarketInfo>>, ApiError> {
+    let service = service.read().await;
+
+    let filter = MarketFilter {
+        exchange: parse_exchange(query.exchange.as_deref())?,
+        base: query.base,
+        quote: query.quote,
+    };
+
+    let markets = service.list_markets(&filter).await?;
+    Ok(Json(markets))
+}
+
+// Get the measured clock skew of each exchange
+pub async fn get_clock_skews(
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<ClockSkew>>, ApiError> {
+    let service = service.read().await;
+    Ok(Json(service.get_clock_skews()))
+}
+
+// Get circuit breaker state and latency of each exchange
+pub async fn get_exchange_status(
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<ExchangeStatus>>, ApiError> {
+    let service = service.read().await;
+    Ok(Json(service.get_exchange_status()))
+}
+
+// List all alert rules
+pub async fn list_alerts(
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<AlertRule>>, ApiError> {
+    let service = service.read().await;
+    Ok(Json(service.list_alerts()))
+}
+
+// Get an alert rule
+pub async fn get_alert(
+    State(service): State<SharedService>,
+    Path(alert_id): Path<String>,
+) -> Result<Json<AlertRule>, ApiError> {
+    let service = service.read().await;
+    Ok(Json(service.get_alert(&alert_id)?))
+}
+
+// Register a new alert rule
+pub async fn create_alert(
+    State(service): State<SharedService>,
+    Json(spec): Json<AlertRuleSpec>,
+) -> Result<(StatusCode, Json<AlertRule>), ApiError> {
+    let service = service.read().await;
+    let rule = service.create_alert(spec).await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+// Replace an alert rule
+pub async fn update_alert(
+    State(service): State<SharedService>,
+    Path(alert_id): Path<String>,
+    Json(spec): Json<AlertRuleSpec>,
+) -> Result<Json<AlertRule>, ApiError> {
+    let service = service.read().await;
+    let rule = service.update_alert(&alert_id, spec).await?;
+    Ok(Json(rule))
+}
+
+// Delete an alert rule
+pub async fn delete_alert(
+    State(service): State<SharedService>,
+    Path(alert_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let service = service.read().await;
+    service.delete_alert(&alert_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// List all portfolios
+pub async fn list_portfolios(
+    State(service): State<SharedService>,
+) -> Result<Json<Vec<Portfolio>>, ApiError> {
+    let service = service.read().await;
+    Ok(Json(service.list_portfolios()))
+}
+
+// Get a portfolio
+pub async fn get_portfolio(
+    State(service): State<SharedService>,
+    Path(portfolio_id): Path<String>,
+) -> Result<Json<Portfolio>, ApiError> {
+    let service = service.read().await;
+    Ok(Json(service.get_portfolio(&portfolio_id)?))
+}
+
+// Create a portfolio
+pub async fn create_portfolio(
+    State(service): State<SharedService>,
+    Json(spec): Json<PortfolioSpec>,
+) -> Result<(StatusCode, Json<Portfolio>), ApiError> {
+    let service = service.read().await;
+    let portfolio = service.create_portfolio(spec).await?;
+    Ok((StatusCode::CREATED, Json(portfolio)))
+}
+
+// Replace a portfolio
+pub async fn update_portfolio(
+    State(service): State<SharedService>,
+    Path(portfolio_id): Path<String>,
+    Json(spec): Json<PortfolioSpec>,
+) -> Result<Json<Portfolio>, ApiError> {
+    let service = service.read().await;
+    let portfolio = service.update_portfolio(&portfolio_id, spec).await?;
+    Ok(Json(portfolio))
+}
+
+// Delete a portfolio
+pub async fn delete_portfolio(
+    State(service): State<SharedService>,
+    Path(portfolio_id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let service = service.read().await;
+    service.delete_portfolio(&portfolio_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ValuationQuery {
+    pub currency: Option<String>,
+}
+
+// Value a portfolio at current prices
+pub async fn get_portfolio_valuation(
+    State(service): State<SharedService>,
+    Path(portfolio_id): Path<String>,
+    Query(query): Query<ValuationQuery>,
+) -> Result<Json<PortfolioValuation>, ApiError> {
+    let service = service.read().await;
+    let valuation = service
+        .get_portfolio_valuation(&portfolio_id, query.currency.as_deref())
+        .await?;
+    Ok(Json(valuation))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PortfolioHistoryQuery {
+    pub currency: Option<String>,
+    pub interval: Option<String>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+// Get the historical value of a portfolio
+pub async fn get_portfolio_history(
+    State(service): State<SharedService>,
+    Path(portfolio_id): Path<String>,
+    Query(query): Query<PortfolioHistoryQuery>,
+) -> Result<Json<PortfolioValueHistory>, ApiError> {
+    let service = service.read().await;
+
+    // Parse interval and limit parameters, default to daily
+    let (interval, limit) = parse_history_params(query.interval.as_deref(), query.limit)?;
+
+    let history = service
+        .get_portfolio_history(
+            &portfolio_id,
+            query.currency.as_deref(),
+            interval,
+            query.start,
+            query.end,
+            limit,
+        )
+        .await?;
+
+    Ok(Json(history))
+}
@@ -0,0 +1,420 @@
+use common::{Error, Result};
+use std::collections::BTreeMap;
+
+/// Technical indicators computed over closing prices
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Indicator {
+    /// Simple moving average
+    Sma { period: usize },
+    /// Exponential moving average, seeded with the SMA of the first period
+    Ema { period: usize },
+    /// Relative strength index with Wilder's smoothing
+    Rsi { period: usize },
+    /// Moving average convergence/divergence
+    Macd {
+        fast: usize,
+        slow: usize,
+        signal: usize,
+    },
+    /// Bollinger Bands around an SMA, `std_dev` population standard deviations wide
+    Bollinger { period: usize, std_dev: f64 },
+}
+
+/// Optional parameters used to build an [`Indicator`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IndicatorParams {
+    pub period: Option<usize>,
+    pub fast: Option<usize>,
+    pub slow: Option<usize>,
+    pub signal: Option<usize>,
+    pub std_dev: Option<f64>,
+}
+
+impl Indicator {
+    /// Build an indicator from its name, using standard defaults for missing parameters
+    pub fn from_params(name: &str, params: IndicatorParams) -> Result<Self> {
+        let indicator = match name {
+            "sma" => Indicator::Sma {
+                period: params.period.unwrap_or(20),
+            },
+            "ema" => Indicator::Ema {
+                period: params.period.unwrap_or(20),
+            },
+            "rsi" => Indicator::Rsi {
+                period: params.period.unwrap_or(14),
+            },
+            "macd" => Indicator::Macd {
+                fast: params.fast.unwrap_or(12),
+                slow: params.slow.unwrap_or(26),
+                signal: params.signal.unwrap_or(9),
+            },
+            "bbands" | "bollinger" => Indicator::Bollinger {
+                period: params.period.unwrap_or(20),
+                std_dev: params.std_dev.unwrap_or(2.0),
+            },
+            unknown => {
+                return Err(Error::ParseError(format!(
+                    "Unknown indicator: {}. Supported indicators: sma, ema, rsi, macd, bbands",
+                    unknown
+                )))
+            }
+        };
+
+        let valid = match indicator {
+            Indicator::Sma { period } | Indicator::Ema { period } | Indicator::Rsi { period } => {
+                period > 0
+            }
+            Indicator::Macd { fast, slow, signal } => fast > 0 && slow > fast && signal > 0,
+            Indicator::Bollinger { period, std_dev } => period > 0 && std_dev > 0.0,
+        };
+        if !valid {
+            return Err(Error::ParseError(format!(
+                "Invalid parameters for indicator {}",
+                indicator
+            )));
+        }
+
+        Ok(indicator)
+    }
+
+    /// Number of bars consumed before the first valid value
+    pub fn lookback(&self) -> usize {
+        match *self {
+            Indicator::Sma { period } | Indicator::Ema { period } => period - 1,
+            Indicator::Rsi { period } => period,
+            Indicator::Macd { slow, signal, .. } => slow + signal - 2,
+            Indicator::Bollinger { period, .. } => period - 1,
+        }
+    }
+
+    /// Compute the indicator over closing prices in chronological order.
+    ///
+    /// The result has one entry per input; entries within the warm-up period are `None`.
+    pub fn compute(&self, closes: &[f64]) -> Vec<Option<BTreeMap<String, f64>>> {
+        let single = |values: Vec<Option<f64>>| {
+            values
+                .into_iter()
+                .map(|v| v.map(|v| BTreeMap::from([("value".to_string(), v)])))
+                .collect()
+        };
+
+        match *self {
+            Indicator::Sma { period } => single(sma(closes, period)),
+            Indicator::Ema { period } => single(ema(closes, period)),
+            Indicator::Rsi { period } => single(rsi(closes, period)),
+            Indicator::Macd { fast, slow, signal } => macd(closes, fast, slow, signal),
+            Indicator::Bollinger { period, std_dev } => bollinger(closes, period, std_dev),
+        }
+    }
+}
+
+impl std::fmt::Display for Indicator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Indicator::Sma { period } => write!(f, "sma({})", period),
+            Indicator::Ema { period } => write!(f, "ema({})", period),
+            Indicator::Rsi { period } => write!(f, "rsi({})", period),
+            Indicator::Macd { fast, slow, signal } => {
+                write!(f, "macd({},{},{})", fast, slow, signal)
+            }
+            Indicator::Bollinger { period, std_dev } => write!(f, "bbands({},{})", period, std_dev),
+        }
+    }
+}
+
+fn sma(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    let mut sum = 0.0;
+
+    for (i, value) in values.iter().enumerate() {
+        sum += value;
+        if i >= period {
+            sum -= values[i - period];
+        }
+        if i + 1 >= period {
+            result[i] = Some(sum / period as f64);
+        }
+    }
+
+    result
+}
+
+fn ema(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if values.len() < period {
+        return result;
+    }
+
+    let k = 2.0 / (period as f64 + 1.0);
+    let mut current = values[..period].iter().sum::<f64>() / period as f64;
+    result[period - 1] = Some(current);
+
+    for (value, slot) in values.iter().zip(result.iter_mut()).skip(period) {
+        current = value * k + current * (1.0 - k);
+        *slot = Some(current);
+    }
+
+    result
+}
+
+fn rsi(values: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut result = vec![None; values.len()];
+    if values.len() <= period {
+        return result;
+    }
+
+    let change = |i: usize| values[i] - values[i - 1];
+    // A flat stretch, with neither gains nor losses, is neutral
+    let rsi_value = |gain: f64, loss: f64| {
+        if gain == 0.0 && loss == 0.0 {
+            50.0
+        } else if loss == 0.0 {
+            100.0
+        } else {
+            100.0 - 100.0 / (1.0 + gain / loss)
+        }
+    };
+
+    let mut avg_gain = (1..=period).map(|i| change(i).max(0.0)).sum::<f64>() / period as f64;
+    let mut avg_loss = (1..=period).map(|i| (-change(i)).max(0.0)).sum::<f64>() / period as f64;
+    result[period] = Some(rsi_value(avg_gain, avg_loss));
+
+    for (i, slot) in result.iter_mut().enumerate().skip(period + 1) {
+        let delta = change(i);
+        avg_gain = (avg_gain * (period - 1) as f64 + delta.max(0.0)) / period as f64;
+        avg_loss = (avg_loss * (period - 1) as f64 + (-delta).max(0.0)) / period as f64;
+        *slot = Some(rsi_value(avg_gain, avg_loss));
+    }
+
+    result
+}
+
+fn macd(
+    values: &[f64],
+    fast: usize,
+    slow: usize,
+    signal: usize,
+) -> Vec<Option<BTreeMap<String, f64>>> {
+    let mut result = vec![None; values.len()];
+    if values.len() < slow {
+        return result;
+    }
+
+    let fast_ema = ema(values, fast);
+    let slow_ema = ema(values, slow);
+
+    // The MACD line is defined once the slow EMA is
+    let line: Vec<f64> = (slow - 1..values.len())
+        .map(|i| fast_ema[i].unwrap_or_default() - slow_ema[i].unwrap_or_default())
+        .collect();
+    let signal_line = ema(&line, signal);
+
+    for (j, signal_value) in signal_line.into_iter().enumerate() {
+        if let Some(signal_value) = signal_value {
+            result[slow - 1 + j] = Some(BTreeMap::from([
+                ("macd".to_string(), line[j]),
+                ("signal".to_string(), signal_value),
+                ("histogram".to_string(), line[j] - signal_value),
+            ]));
+        }
+    }
+
+    result
+}
+
+fn bollinger(values: &[f64], period: usize, std_dev: f64) -> Vec<Option<BTreeMap<String, f64>>> {
+    let middle = sma(values, period);
+
+    middle
+        .iter()
+        .enumerate()
+        .map(|(i, mean)| {
+            let mean = (*mean)?;
+            let window = &values[i + 1 - period..=i];
+            let variance = window.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / period as f64;
+            let width = std_dev * variance.sqrt();
+
+            Some(BTreeMap::from([
+                ("middle".to_string(), mean),
+                ("upper".to_string(), mean + width),
+                ("lower".to_string(), mean - width),
+            ]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("value expected");
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    // The named output of each entry
+    fn series(indicator: Indicator, closes: &[f64], key: &str) -> Vec<Option<f64>> {
+        indicator
+            .compute(closes)
+            .into_iter()
+            .map(|entry| entry.map(|values| values[key]))
+            .collect()
+    }
+
+    #[test]
+    fn sma_averages_the_last_period() {
+        let values = series(
+            Indicator::Sma { period: 3 },
+            &[2.0, 4.0, 6.0, 8.0, 4.0],
+            "value",
+        );
+
+        assert_eq!(values[..2], [None, None]);
+        assert_close(values[2], 4.0);
+        assert_close(values[3], 6.0);
+        assert_close(values[4], 6.0);
+    }
+
+    #[test]
+    fn ema_is_seeded_with_the_first_sma() {
+        let values = series(
+            Indicator::Ema { period: 3 },
+            &[2.0, 4.0, 6.0, 8.0, 4.0],
+            "value",
+        );
+
+        assert_eq!(values[..2], [None, None]);
+        assert_close(values[2], 4.0);
+        // k = 2 / (3 + 1)
+        assert_close(values[3], 6.0);
+        assert_close(values[4], 5.0);
+    }
+
+    #[test]
+    fn rsi_uses_wilders_smoothing() {
+        let rsi = Indicator::Rsi { period: 2 };
+        let values = series(rsi, &[10.0, 12.0, 11.0, 13.0, 12.0], "value");
+
+        assert_eq!(values[..2], [None, None]);
+        // Average gain 1 and loss 0.5
+        assert_close(values[2], 100.0 - 100.0 / 3.0);
+        // Average gain (1 + 2) / 2 and loss (0.5 + 0) / 2
+        assert_close(values[3], 100.0 - 100.0 / 7.0);
+        // Average gain (1.5 + 0) / 2 and loss (0.25 + 1) / 2
+        assert_close(values[4], 100.0 - 100.0 / 2.2);
+
+        let rising = series(rsi, &[1.0, 2.0, 3.0, 4.0], "value");
+        assert_close(rising[2], 100.0);
+        assert_close(rising[3], 100.0);
+
+        let flat = series(rsi, &[5.0, 5.0, 5.0, 5.0], "value");
+        assert_close(flat[2], 50.0);
+        assert_close(flat[3], 50.0);
+    }
+
+    #[test]
+    fn macd_signal_and_histogram() {
+        let macd = Indicator::Macd {
+            fast: 2,
+            slow: 3,
+            signal: 2,
+        };
+        let closes = [2.0, 4.0, 6.0, 8.0, 4.0, 6.0];
+        let line = series(macd, &closes, "macd");
+        let signal = series(macd, &closes, "signal");
+        let histogram = series(macd, &closes, "histogram");
+
+        assert_eq!(line[..3], [None, None, None]);
+        // The fast EMA is 7, 5 and 17/3, the slow EMA 6, 5 and 5.5
+        assert_close(line[3], 1.0);
+        assert_close(line[4], 0.0);
+        assert_close(line[5], 1.0 / 6.0);
+        // Seeded with the mean of the first two MACD values, 1 and 1
+        assert_close(signal[3], 1.0);
+        assert_close(signal[4], 1.0 / 3.0);
+        assert_close(signal[5], 2.0 / 9.0);
+        assert_close(histogram[3], 0.0);
+        assert_close(histogram[4], -1.0 / 3.0);
+        assert_close(histogram[5], 1.0 / 6.0 - 2.0 / 9.0);
+    }
+
+    #[test]
+    fn bollinger_bands_are_population_standard_deviations_wide() {
+        let bollinger = Indicator::Bollinger {
+            period: 3,
+            std_dev: 2.0,
+        };
+        let closes = [2.0, 4.0, 6.0, 5.0, 5.0, 5.0];
+        let middle = series(bollinger, &closes, "middle");
+        let upper = series(bollinger, &closes, "upper");
+        let lower = series(bollinger, &closes, "lower");
+
+        assert_eq!(middle[..2], [None, None]);
+        // Variance of 2, 4 and 6 is 8 / 3
+        let width = 2.0 * (8.0f64 / 3.0).sqrt();
+        assert_close(middle[2], 4.0);
+        assert_close(upper[2], 4.0 + width);
+        assert_close(lower[2], 4.0 - width);
+        // A flat window has no width
+        assert_close(upper[5], 5.0);
+        assert_close(lower[5], 5.0);
+    }
+
+    #[test]
+    fn warm_up_matches_lookback() {
+        let closes: Vec<f64> = (0..60)
+            .map(|i| 100.0 + (i as f64 * 0.7).sin() * 10.0)
+            .collect();
+        let indicators = [
+            Indicator::Sma { period: 20 },
+            Indicator::Ema { period: 20 },
+            Indicator::Rsi { period: 14 },
+            Indicator::Macd {
+                fast: 12,
+                slow: 26,
+                signal: 9,
+            },
+            Indicator::Bollinger {
+                period: 20,
+                std_dev: 2.0,
+            },
+        ];
+
+        for indicator in indicators {
+            let values = indicator.compute(&closes);
+            let lookback = indicator.lookback();
+
+            assert_eq!(values.len(), closes.len());
+            assert!(
+                values[..lookback].iter().all(Option::is_none),
+                "{} defined during warm-up",
+                indicator
+            );
+            assert!(
+                values[lookback..].iter().all(Option::is_some),
+                "{} undefined after warm-up",
+                indicator
+            );
+        }
+    }
+
+    #[test]
+    fn short_series_are_all_warm_up() {
+        let macd = Indicator::Macd {
+            fast: 2,
+            slow: 3,
+            signal: 2,
+        };
+
+        assert!(macd.compute(&[1.0, 2.0, 3.0]).iter().all(Option::is_none));
+        assert!(Indicator::Rsi { period: 3 }
+            .compute(&[1.0, 2.0, 3.0])
+            .iter()
+            .all(Option::is_none));
+    }
+}
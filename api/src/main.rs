@@ -1,22 +1,61 @@
+mod accounts;
+mod aggregate;
+mod alerts;
+mod batch;
+mod catalog;
+mod clock;
+mod coingecko;
 mod config;
+mod decimal;
+mod fx;
 mod handler;
+mod indicators;
+mod markets;
+mod portfolio;
+mod quality;
+mod resample;
+mod routing;
 mod service;
 
+use accounts::AccountConfig;
+use aggregate::AggregationConfig;
+use alerts::AlertConfig;
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{get, post, put},
     Router,
 };
-use common::models::{TradingPair, Exchange, PriceInterval};
-use connectors::{binance::BinanceConnector, coinbase::CoinbaseConnector, ExchangeConnector};
+use batch::BatchConfig;
+use catalog::CatalogConfig;
+use clock::ClockConfig;
+use common::models::Exchange;
+use config::{ApiConfig, CoinbaseApi};
+use connectors::{
+    binance::BinanceConnector,
+    binance_futures::BinanceFuturesConnector,
+    breaker::{CircuitBreakerConfig, CircuitBreakerConnector},
+    coinbase::CoinbaseConnector,
+    coinbase_advanced::CoinbaseAdvancedConnector,
+    dex::{DexConnector, DexSpec},
+    ecb::EcbConnector,
+    http::{HttpClient, HttpMode},
+    rest::{RestConnector, RestSpec},
+    simulated::{SimulatedConnector, SimulationConfig},
+    ExchangeConnector,
+};
+use fx::FxConfig;
+use quality::QualityConfig;
+use routing::RoutingConfig;
 use service::CoinService;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
 };
-use tracing::{debug, info};
+use tracing::{error, info};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -26,6 +65,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Starting CoinLizard API");
 
     // Load configuration from environment
+    let api_config = ApiConfig::from_env();
     let store_config = store::StoreConfig::from_env()
         .map_err(|e| format!("Failed to load store configuration: {}", e))?;
 
@@ -33,16 +73,221 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let price_store = store::PriceStore::new(store_config)
         .map_err(|e| format!("Failed to create price store: {}", e))?;
 
-    // Create exchange connectors
-    let coinbase = Arc::new(CoinbaseConnector::new());
-    let binance = Arc::new(BinanceConnector::new());
+    // Connectors share one HTTP client, which may record or replay responses
+    let http = HttpClient::from_env();
+    match http.mode() {
+        HttpMode::Live => {}
+        HttpMode::Record(dir) => info!("Recording exchange responses to {}", dir.display()),
+        HttpMode::Replay(dir) => info!("Replaying exchange responses from {}", dir.display()),
+    }
 
-    // Create coin service
-    let service = Arc::new(RwLock::new(CoinService::new(
+    // Create exchange connectors, or simulated markets in their place
+    let simulation = api_config
+        .simulated_exchanges
+        .then(SimulationConfig::from_env);
+    let (coinbase, binance): (Arc<dyn ExchangeConnector>, Arc<dyn ExchangeConnector>) =
+        if let Some(simulation) = &simulation {
+            info!("Serving simulated markets (seed {})", simulation.seed);
+            (
+                Arc::new(SimulatedConnector::new(
+                    Exchange::Coinbase,
+                    simulation.clone(),
+                )),
+                Arc::new(SimulatedConnector::new(
+                    Exchange::Binance,
+                    simulation.clone(),
+                )),
+            )
+        } else {
+            let coinbase: Arc<dyn ExchangeConnector> = match api_config.coinbase_api {
+                CoinbaseApi::Exchange => {
+                    let coinbase = match &api_config.coinbase_url {
+                        Some(url) => CoinbaseConnector::with_base_url(url),
+                        None => CoinbaseConnector::new(),
+                    };
+                    Arc::new(coinbase.with_http(http.clone()))
+                }
+                CoinbaseApi::Advanced => {
+                    info!("Reading Coinbase market data from the Advanced Trade API");
+                    let coinbase = match &api_config.coinbase_advanced_url {
+                        Some(url) => CoinbaseAdvancedConnector::with_base_url(url),
+                        None => CoinbaseAdvancedConnector::new(),
+                    };
+                    Arc::new(coinbase.with_http(http.clone()))
+                }
+            };
+            let binance = match &api_config.binance_url {
+                Some(url) => BinanceConnector::with_base_url(url),
+                None => BinanceConnector::new(),
+            };
+            (coinbase, Arc::new(binance.with_http(http.clone())))
+        };
+
+    // Put each exchange behind a circuit breaker
+    let breaker_config = CircuitBreakerConfig::from_env();
+    let coinbase = Arc::new(CircuitBreakerConnector::new(
+        "Coinbase",
         coinbase,
+        breaker_config,
+    ));
+    let binance = Arc::new(CircuitBreakerConnector::new(
+        "Binance",
         binance,
-        Arc::new(price_store),
-    )));
+        breaker_config,
+    ));
+    let binance_futures = match &api_config.binance_futures_url {
+        Some(url) => BinanceFuturesConnector::with_base_url(url),
+        None => BinanceFuturesConnector::new(),
+    };
+    let binance_futures = Arc::new(binance_futures.with_http(http.clone()));
+
+    // Add the exchanges described by REST connector and DEX spec files
+    let mut added: Vec<(Exchange, Arc<dyn ExchangeConnector>, &PathBuf)> = Vec::new();
+    for path in &api_config.rest_connector_specs {
+        let connector = RestSpec::load(path)
+            .and_then(RestConnector::new)
+            .map_err(|e| format!("Failed to create REST connector: {}", e))?;
+        added.push((
+            connector.exchange(),
+            Arc::new(connector.with_http(http.clone())),
+            path,
+        ));
+    }
+    for path in &api_config.dex_connector_specs {
+        let connector = DexSpec::load(path)
+            .and_then(DexConnector::new)
+            .map_err(|e| format!("Failed to create DEX connector: {}", e))?;
+        added.push((
+            connector.exchange(),
+            Arc::new(connector.with_http(http.clone())),
+            path,
+        ));
+    }
+
+    let mut custom_exchanges: Vec<(Exchange, Arc<dyn ExchangeConnector>)> = Vec::new();
+    for (exchange, connector, path) in added {
+        let connector: Arc<dyn ExchangeConnector> = match &simulation {
+            Some(simulation) => Arc::new(SimulatedConnector::new(exchange, simulation.clone())),
+            None => connector,
+        };
+        info!("Added exchange {} from {}", exchange, path.display());
+        let name = exchange.to_string();
+        let connector = CircuitBreakerConnector::new(name, connector, breaker_config);
+        custom_exchanges.push((exchange, Arc::new(connector)));
+    }
+
+    // Create fiat exchange rate connector
+    let fx_config = FxConfig::from_env();
+    let fx_refresh = std::time::Duration::from_secs(fx_config.refresh_secs);
+    let fx_rates = match &fx_config.rates_url {
+        Some(url) => EcbConnector::with_base_url(url),
+        None => EcbConnector::new(),
+    };
+    let fx_rates = Arc::new(fx_rates.with_http(http.clone()));
+
+    // Exchange accounts reach the same hosts as the public connectors
+    let account_config = AccountConfig::from_env()
+        .map_err(|e| format!("Failed to load account configuration: {}", e))?;
+    let account_config = AccountConfig {
+        binance_url: api_config.binance_url.clone(),
+        coinbase_url: api_config.coinbase_url.clone(),
+        ..account_config
+    };
+
+    // Create coin service
+    let catalog_config = CatalogConfig::from_env();
+    let catalog_refresh = std::time::Duration::from_secs(catalog_config.refresh_secs);
+    let clock_config = ClockConfig::from_env();
+    let clock_sync = std::time::Duration::from_secs(clock_config.sync_secs);
+    let alert_config = AlertConfig::from_env();
+    let alert_evaluation = std::time::Duration::from_secs(alert_config.evaluate_secs);
+    let mut service = CoinService::new(coinbase, binance, Arc::new(price_store))
+        .with_catalog(catalog_config)
+        .with_aggregation(AggregationConfig::from_env())
+        .with_batch(BatchConfig::from_env())
+        .with_routing(RoutingConfig::from_env())
+        .with_quality(QualityConfig::from_env())
+        .with_clock(clock_config)
+        .with_alerts(alert_config)
+        .with_derivatives(binance_futures)
+        .with_fx(fx_rates, fx_config)
+        .with_accounts(account_config, http);
+    for (exchange, connector) in custom_exchanges {
+        service = service.with_exchange(exchange, connector);
+    }
+    let service = Arc::new(RwLock::new(service));
+
+    // Load the coin catalog, then keep refreshing it in the background
+    if let Err(e) = service.read().await.refresh_catalog().await {
+        error!("Failed to load coin catalog, using built-in coins: {}", e);
+    }
+    if let Err(e) = service.read().await.load_alerts().await {
+        error!("Failed to load alert rules: {}", e);
+    }
+    if let Err(e) = service.read().await.load_portfolios().await {
+        error!("Failed to load portfolios: {}", e);
+    }
+    if let Err(e) = service.read().await.load_accounts().await {
+        error!("Failed to load exchange accounts: {}", e);
+    }
+    let catalog_service = service.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(catalog_refresh);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = catalog_service.read().await.refresh_catalog().await {
+                error!("Failed to refresh coin catalog: {}", e);
+            }
+        }
+    });
+
+    // Load stored FX rates and fetch new ones, then keep them up to date
+    let fx_service = service.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(fx_refresh);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = fx_service.read().await.refresh_fx_rates().await {
+                error!("Failed to refresh FX rates: {}", e);
+            }
+        }
+    });
+
+    // Measure the clock skew of each exchange, then keep it up to date
+    let clock_service = service.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(clock_sync);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = clock_service.read().await.sync_clocks().await {
+                error!("Failed to sync exchange clocks: {}", e);
+            }
+        }
+    });
+
+    // Fetch the prices watched by alert rules and evaluate the rules on a schedule
+    let alert_service = service.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(alert_evaluation);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = alert_service.read().await.evaluate_alerts().await {
+                error!("Failed to evaluate alerts: {}", e);
+            }
+        }
+    });
+
+    // Periodically refresh market metadata in the background
+    let market_service = service.clone();
+    let market_refresh = std::time::Duration::from_secs(api_config.market_refresh_secs);
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(market_refresh);
+        loop {
+            ticker.tick().await;
+            let _ = market_service.read().await.refresh_markets().await;
+        }
+    });
 
     // Create CORS middleware
     let cors = CorsLayer::new()
@@ -50,23 +295,127 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .allow_origin(Any)
         .allow_headers(Any);
 
+    // Admin routes require the configured bearer token. Alert rules are among
+    // them, as their webhooks make the server send requests to any URL.
+    let admin = Router::new()
+        .route(
+            "/api/v1/admin/coins/:id/metadata",
+            put(handler::update_coin_metadata),
+        )
+        .route(
+            "/api/v1/admin/accounts",
+            get(handler::list_accounts).post(handler::create_account),
+        )
+        .route(
+            "/api/v1/admin/accounts/:id",
+            get(handler::get_account).delete(handler::delete_account),
+        )
+        .route(
+            "/api/v1/admin/accounts/:id/import",
+            post(handler::import_account),
+        )
+        .route(
+            "/api/v1/alerts",
+            get(handler::list_alerts).post(handler::create_alert),
+        )
+        .route(
+            "/api/v1/alerts/:id",
+            get(handler::get_alert)
+                .put(handler::update_alert)
+                .delete(handler::delete_alert),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            api_config.admin_token.clone(),
+            handler::require_admin,
+        ));
+
     // Create Axum router with API routes
     let app = Router::new()
         .route("/api/v1/coins", get(handler::list_coins))
+        .route("/api/v1/coins/:id", get(handler::get_coin_profile))
+        .route("/api/v1/markets", get(handler::list_markets))
+        .route("/api/v1/exchanges/clock", get(handler::get_clock_skews))
+        .route(
+            "/api/v1/exchanges/status",
+            get(handler::get_exchange_status),
+        )
+        .route("/api/v1/prices", get(handler::get_batch_prices))
+        .route("/api/v1/fx/rates", get(handler::get_fx_rates))
         .route(
             "/api/v1/coins/:id/price",
             get(handler::get_current_price),
         )
+        .route(
+            "/api/v1/coins/:id/price/aggregated",
+            get(handler::get_aggregated_price),
+        )
+        .route(
+            "/api/v1/coins/:id/price/routed",
+            get(handler::get_routed_price),
+        )
+        .route(
+            "/api/v1/coins/:id/price/converted",
+            get(handler::get_converted_price),
+        )
         .route(
             "/api/v1/coins/:id/history/daily",
             get(handler::get_price_history),
         )
+        .route(
+            "/api/v1/coins/:id/history/routed",
+            get(handler::get_routed_history),
+        )
+        .route(
+            "/api/v1/coins/:id/history/converted",
+            get(handler::get_converted_history),
+        )
+        .route("/api/v1/coins/:id/indicators", get(handler::get_indicator))
+        .route(
+            "/api/v1/coins/:id/perpetual",
+            get(handler::get_perpetual_market),
+        )
+        .route(
+            "/api/v1/coins/:id/perpetual/funding",
+            get(handler::get_funding_rates),
+        )
+        .route(
+            "/api/v1/coins/:id/perpetual/history",
+            get(handler::get_perpetual_history),
+        )
+        .route("/api/v1/coins/:id/basis", get(handler::get_basis))
+        .route(
+            "/api/v1/coins/:id/basis/history",
+            get(handler::get_basis_history),
+        )
+        .route("/api/v1/coins/:id/trades", get(handler::get_recent_trades))
+        .route(
+            "/api/v1/portfolios",
+            get(handler::list_portfolios).post(handler::create_portfolio),
+        )
+        .route(
+            "/api/v1/portfolios/:id",
+            get(handler::get_portfolio)
+                .put(handler::update_portfolio)
+                .delete(handler::delete_portfolio),
+        )
+        .route(
+            "/api/v1/portfolios/:id/valuation",
+            get(handler::get_portfolio_valuation),
+        )
+        .route(
+            "/api/v1/portfolios/:id/history",
+            get(handler::get_portfolio_history),
+        )
+        .nest("/api/v3", coingecko::router())
+        .merge(admin)
         .layer(TraceLayer::new_for_http())
         .layer(cors)
         .with_state(service);
 
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
+    let addr: SocketAddr = format!("{}:{}", api_config.host, api_config.port)
+        .parse()
+        .map_err(|e| format!("Invalid API address: {}", e))?;
     info!("Listening on {}", addr);
 
     axum::Server::bind(&addr)
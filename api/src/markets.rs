@@ -0,0 +1,59 @@
+use common::models::{Exchange, MarketInfo};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// Filter applied when listing cached markets
+#[derive(Debug, Clone, Default)]
+pub struct MarketFilter {
+    pub exchange: Option<Exchange>,
+    pub base: Option<String>,
+    pub quote: Option<String>,
+}
+
+impl MarketFilter {
+    fn matches(&self, market: &MarketInfo) -> bool {
+        self.exchange.is_none_or(|ex| market.exchange == ex)
+            && self
+                .base
+                .as_deref()
+                .is_none_or(|base| market.pair.base.eq_ignore_ascii_case(base))
+            && self
+                .quote
+                .as_deref()
+                .is_none_or(|quote| market.pair.quote.eq_ignore_ascii_case(quote))
+    }
+}
+
+/// In-memory cache of market metadata per exchange
+#[derive(Default)]
+pub struct MarketCache {
+    markets: RwLock<HashMap<Exchange, Vec<MarketInfo>>>,
+}
+
+impl MarketCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the cached markets of an exchange
+    pub async fn update(&self, exchange: Exchange, markets: Vec<MarketInfo>) {
+        self.markets.write().await.insert(exchange, markets);
+    }
+
+    /// Whether the markets of any exchange have been loaded
+    pub async fn is_empty(&self) -> bool {
+        self.markets.read().await.is_empty()
+    }
+
+    /// List the cached markets matching a filter
+    pub async fn list(&self, filter: &MarketFilter) -> Vec<MarketInfo> {
+        self.markets
+            .read()
+            .await
+            .values()
+            .flatten()
+            .filter(|market| filter.matches(market))
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,439 @@
+use chrono::{DateTime, Utc};
+use common::models::{Holding, Portfolio, PortfolioValuePoint, PriceHistory, Timeframe, TradeSide};
+use common::{Error, Result};
+use rust_decimal::Decimal;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::RwLock;
+
+/// Amount held and P&L of a holding after applying its transactions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub quantity: Decimal,
+    pub cost_basis: Option<Decimal>,
+    pub realized_pnl: Decimal,
+}
+
+/// Apply a holding's transactions using average-cost accounting.
+///
+/// Holdings without transactions keep their given quantity and cost basis.
+pub fn position(holding: &Holding) -> Result<Position> {
+    if holding.transactions.is_empty() {
+        return Ok(Position {
+            quantity: holding.quantity,
+            cost_basis: holding.cost_basis,
+            realized_pnl: Decimal::ZERO,
+        });
+    }
+
+    let out_of_range = || {
+        Error::ParseError(format!(
+            "Quantities or prices of {} are out of range",
+            holding.coin_id
+        ))
+    };
+
+    let mut quantity = Decimal::ZERO;
+    let mut cost = Decimal::ZERO;
+    let mut realized_pnl = Decimal::ZERO;
+
+    for tx in &holding.transactions {
+        let amount = tx.quantity.checked_mul(tx.price).ok_or_else(out_of_range)?;
+
+        match tx.side {
+            TradeSide::Buy => {
+                quantity = quantity.checked_add(tx.quantity).ok_or_else(out_of_range)?;
+                cost = cost
+                    .checked_add(amount)
+                    .and_then(|c| c.checked_add(tx.fee))
+                    .ok_or_else(out_of_range)?;
+            }
+            TradeSide::Sell => {
+                if tx.quantity > quantity {
+                    return Err(Error::ParseError(format!(
+                        "Sell of {} {} at {} exceeds the {} held",
+                        tx.quantity, holding.coin_id, tx.timestamp, quantity
+                    )));
+                }
+                let sold_cost = cost
+                    .checked_mul(tx.quantity)
+                    .and_then(|c| c.checked_div(quantity))
+                    .ok_or_else(out_of_range)?;
+                realized_pnl = (amount - tx.fee - sold_cost)
+                    .checked_add(realized_pnl)
+                    .ok_or_else(out_of_range)?;
+                quantity -= tx.quantity;
+                cost -= sold_cost;
+            }
+        }
+    }
+
+    Ok(Position {
+        quantity,
+        cost_basis: Some(cost.max(Decimal::ZERO)),
+        realized_pnl,
+    })
+}
+
+/// Amount of a holding held at a point in time
+pub fn quantity_at(holding: &Holding, time: DateTime<Utc>) -> Decimal {
+    if holding.transactions.is_empty() {
+        return holding.quantity;
+    }
+
+    holding
+        .transactions
+        .iter()
+        .filter(|tx| tx.timestamp <= time)
+        .map(|tx| match tx.side {
+            TradeSide::Buy => tx.quantity,
+            TradeSide::Sell => -tx.quantity,
+        })
+        .sum::<Decimal>()
+        .max(Decimal::ZERO)
+}
+
+/// Check holdings and derive quantities and cost bases from transactions
+pub fn normalize_holdings(holdings: &mut [Holding]) -> Result<()> {
+    let mut seen = HashSet::new();
+
+    for holding in holdings.iter_mut() {
+        if !seen.insert(holding.coin_id.clone()) {
+            return Err(Error::ParseError(format!(
+                "Coin {} is held more than once",
+                holding.coin_id
+            )));
+        }
+
+        let invalid_tx = holding.transactions.iter().any(|tx| {
+            tx.quantity <= Decimal::ZERO || tx.price < Decimal::ZERO || tx.fee < Decimal::ZERO
+        });
+        if invalid_tx
+            || holding.quantity < Decimal::ZERO
+            || holding.cost_basis.is_some_and(|c| c < Decimal::ZERO)
+        {
+            return Err(Error::ParseError(format!(
+                "Invalid quantity, price or cost for {}",
+                holding.coin_id
+            )));
+        }
+
+        holding.transactions.sort_by_key(|tx| tx.timestamp);
+
+        let position = position(holding)?;
+        holding.quantity = position.quantity;
+        holding.cost_basis = position.cost_basis;
+    }
+
+    Ok(())
+}
+
+/// Value holdings over time from the price history of each holding.
+///
+/// `histories` holds the history of each holding, in the same order. Prices
+/// are grouped into bars of `timeframe`, and each bar uses the latest known
+/// close of every coin; bars before every coin held at the time has a price
+/// are skipped. Values are returned newest first.
+pub fn value_series(
+    holdings: &[Holding],
+    histories: &[PriceHistory],
+    timeframe: Timeframe,
+) -> Vec<PortfolioValuePoint> {
+    let timestamps: BTreeSet<DateTime<Utc>> = histories
+        .iter()
+        .flat_map(|history| history.data.iter())
+        .filter_map(|p| timeframe.bucket_start(p.timestamp))
+        .collect();
+
+    // Closes of each coin by bar, oldest first; the latest close in a bar wins
+    let closes: Vec<Vec<(DateTime<Utc>, Decimal)>> = histories
+        .iter()
+        .map(|history| {
+            let mut points: Vec<_> = history
+                .data
+                .iter()
+                .map(|p| (p.timestamp, p.price))
+                .collect();
+            points.sort_by_key(|(timestamp, _)| *timestamp);
+            points
+                .into_iter()
+                .filter_map(|(timestamp, price)| Some((timeframe.bucket_start(timestamp)?, price)))
+                .collect()
+        })
+        .collect();
+    let mut cursors = vec![0; closes.len()];
+
+    let mut series = Vec::with_capacity(timestamps.len());
+
+    'bars: for timestamp in timestamps {
+        let mut value = Decimal::ZERO;
+
+        for (i, holding) in holdings.iter().enumerate() {
+            let coin_closes = &closes[i];
+            while cursors[i] < coin_closes.len() && coin_closes[cursors[i]].0 <= timestamp {
+                cursors[i] += 1;
+            }
+
+            let quantity = quantity_at(holding, timestamp);
+            if quantity.is_zero() {
+                continue;
+            }
+
+            let total = cursors[i]
+                .checked_sub(1)
+                .and_then(|j| quantity.checked_mul(coin_closes[j].1))
+                .and_then(|v| v.checked_add(value));
+            match total {
+                Some(total) => value = total,
+                None => continue 'bars,
+            }
+        }
+
+        series.push(PortfolioValuePoint { timestamp, value });
+    }
+
+    series.reverse();
+    series
+}
+
+/// Registered portfolios
+#[derive(Default)]
+pub struct PortfolioBook {
+    portfolios: RwLock<HashMap<String, Portfolio>>,
+}
+
+impl PortfolioBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All portfolios, oldest first
+    pub fn list(&self) -> Vec<Portfolio> {
+        let portfolios = self.portfolios.read().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<Portfolio> = portfolios.values().cloned().collect();
+        list.sort_by_key(|portfolio| portfolio.created_at);
+        list
+    }
+
+    /// Look up a portfolio by ID
+    pub fn get(&self, id: &str) -> Option<Portfolio> {
+        let portfolios = self.portfolios.read().unwrap_or_else(|e| e.into_inner());
+        portfolios.get(id).cloned()
+    }
+
+    /// Add or replace a portfolio
+    pub fn upsert(&self, portfolio: Portfolio) {
+        let mut portfolios = self.portfolios.write().unwrap_or_else(|e| e.into_inner());
+        portfolios.insert(portfolio.id.clone(), portfolio);
+    }
+
+    /// Remove a portfolio, returning it if it existed
+    pub fn remove(&self, id: &str) -> Option<Portfolio> {
+        let mut portfolios = self.portfolios.write().unwrap_or_else(|e| e.into_inner());
+        portfolios.remove(id)
+    }
+
+    /// Replace all portfolios, e.g. after loading them from the store
+    pub fn replace(&self, new_portfolios: Vec<Portfolio>) {
+        let mut portfolios = self.portfolios.write().unwrap_or_else(|e| e.into_inner());
+        *portfolios = new_portfolios
+            .into_iter()
+            .map(|portfolio| (portfolio.id.clone(), portfolio))
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use common::models::{
+        Exchange, PortfolioTransaction, PriceHistoryPoint, PriceInterval, TradingPair,
+    };
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    // 2024-03-01 plus `minutes`
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn tx(
+        side: TradeSide,
+        quantity: &str,
+        price: &str,
+        fee: &str,
+        minutes: i64,
+    ) -> PortfolioTransaction {
+        PortfolioTransaction {
+            side,
+            quantity: decimal(quantity),
+            price: decimal(price),
+            fee: decimal(fee),
+            timestamp: at(minutes),
+        }
+    }
+
+    fn holding(coin_id: &str, quantity: &str, transactions: Vec<PortfolioTransaction>) -> Holding {
+        Holding {
+            coin_id: coin_id.to_string(),
+            quantity: decimal(quantity),
+            cost_basis: None,
+            transactions,
+        }
+    }
+
+    // Hourly closes at the given minutes, newest first like stored history
+    fn history(closes: &[(i64, &str)]) -> PriceHistory {
+        let mut data: Vec<_> = closes
+            .iter()
+            .map(|(minutes, price)| PriceHistoryPoint {
+                timestamp: at(*minutes),
+                price: decimal(price),
+                open: None,
+                high: None,
+                low: None,
+                volume: None,
+            })
+            .collect();
+        data.reverse();
+
+        PriceHistory {
+            exchange: Exchange::Coinbase,
+            pair: TradingPair {
+                base: "BTC".to_string(),
+                quote: "USD".to_string(),
+            },
+            interval: PriceInterval::OneHour.into(),
+            data,
+        }
+    }
+
+    #[test]
+    fn position_uses_average_cost() {
+        let btc = holding(
+            "bitcoin",
+            "0",
+            vec![
+                tx(TradeSide::Buy, "2", "100", "2", 0),
+                tx(TradeSide::Buy, "2", "150", "0", 60),
+                // Sells a quarter of the 502 paid: 200 - 1 - 125.5
+                tx(TradeSide::Sell, "1", "200", "1", 120),
+            ],
+        );
+
+        assert_eq!(
+            position(&btc).unwrap(),
+            Position {
+                quantity: decimal("3"),
+                cost_basis: Some(decimal("376.5")),
+                realized_pnl: decimal("73.5"),
+            }
+        );
+
+        // Selling the rest at a loss of 76.5
+        let mut closed = btc.clone();
+        closed
+            .transactions
+            .push(tx(TradeSide::Sell, "3", "100", "0", 180));
+        assert_eq!(
+            position(&closed).unwrap(),
+            Position {
+                quantity: Decimal::ZERO,
+                cost_basis: Some(Decimal::ZERO),
+                realized_pnl: decimal("-3"),
+            }
+        );
+    }
+
+    #[test]
+    fn position_without_transactions_is_given() {
+        let mut btc = holding("bitcoin", "1.5", Vec::new());
+        btc.cost_basis = Some(decimal("90000"));
+
+        assert_eq!(
+            position(&btc).unwrap(),
+            Position {
+                quantity: decimal("1.5"),
+                cost_basis: Some(decimal("90000")),
+                realized_pnl: Decimal::ZERO,
+            }
+        );
+    }
+
+    #[test]
+    fn selling_more_than_held_is_rejected() {
+        let btc = holding(
+            "bitcoin",
+            "0",
+            vec![
+                tx(TradeSide::Buy, "1", "100", "0", 0),
+                tx(TradeSide::Sell, "2", "100", "0", 60),
+            ],
+        );
+
+        match position(&btc) {
+            Err(Error::ParseError(message)) => assert!(message.contains("exceeds the 1 held")),
+            other => panic!("Expected an oversell error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn quantity_at_applies_earlier_transactions() {
+        let btc = holding(
+            "bitcoin",
+            "3",
+            vec![
+                tx(TradeSide::Buy, "2", "100", "0", 60),
+                tx(TradeSide::Buy, "2", "150", "0", 120),
+                tx(TradeSide::Sell, "1", "200", "0", 180),
+            ],
+        );
+
+        assert_eq!(quantity_at(&btc, at(0)), Decimal::ZERO);
+        assert_eq!(quantity_at(&btc, at(60)), decimal("2"));
+        assert_eq!(quantity_at(&btc, at(150)), decimal("4"));
+        assert_eq!(quantity_at(&btc, at(180)), decimal("3"));
+        // Without transactions the given quantity is always held
+        assert_eq!(
+            quantity_at(&holding("bitcoin", "1.5", Vec::new()), at(0)),
+            decimal("1.5")
+        );
+    }
+
+    #[test]
+    fn value_series_carries_prices_forward_and_skips_unpriced_bars() {
+        let holdings = [
+            holding("bitcoin", "2", Vec::new()),
+            holding(
+                "ethereum",
+                "0",
+                vec![tx(TradeSide::Buy, "10", "4", "0", 60)],
+            ),
+        ];
+        let histories = [
+            history(&[(0, "100"), (60, "110"), (180, "120")]),
+            // The later close of a bar wins
+            history(&[(120, "5"), (180, "5.5"), (210, "6")]),
+        ];
+
+        let series = value_series(&holdings, &histories, PriceInterval::OneHour.into());
+
+        let values: Vec<(DateTime<Utc>, Decimal)> = series
+            .iter()
+            .map(|point| (point.timestamp, point.value))
+            .collect();
+        assert_eq!(
+            values,
+            [
+                (at(180), decimal("300")),
+                // Bitcoin has no close in this bar, so its last one is used
+                (at(120), decimal("270")),
+                // Ether is held from 01:00 but has no price yet, so that
+                // bar is skipped; before then it is not held
+                (at(0), decimal("200")),
+            ]
+        );
+    }
+}
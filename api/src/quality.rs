@@ -0,0 +1,271 @@
+use chrono::{DateTime, Duration, Utc};
+use common::models::{CurrentPrice, Exchange, PriceInterval, QualityFlag, TradingPair};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use store::{PriceQuery, PriceStore};
+use tracing::{debug, warn};
+
+use crate::aggregate::{self, median};
+use crate::clock::ClockMonitor;
+use crate::decimal::to_f64;
+
+/// Thresholds used to flag suspicious exchange prices
+#[derive(Debug, Clone, Copy)]
+pub struct QualityConfig {
+    /// Maximum deviation from the cross-exchange median, in percent
+    pub max_consensus_deviation_percent: f64,
+    /// Maximum deviation from the exchange's own recent median, in percent
+    pub max_history_deviation_percent: f64,
+    /// How far back the exchange's own history is compared
+    pub history_window: Duration,
+    /// How long a price may go without its timestamp advancing
+    pub stale_after: Duration,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            max_consensus_deviation_percent: 2.0,
+            max_history_deviation_percent: 10.0,
+            history_window: Duration::hours(1),
+            stale_after: Duration::minutes(5),
+        }
+    }
+}
+
+impl QualityConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_f64 = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<f64>().ok());
+        let env_secs = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<i64>().ok())
+                .map(Duration::seconds)
+        };
+
+        Self {
+            max_consensus_deviation_percent: env_f64("QUALITY_MAX_CONSENSUS_DEVIATION_PERCENT")
+                .unwrap_or(default.max_consensus_deviation_percent),
+            max_history_deviation_percent: env_f64("QUALITY_MAX_HISTORY_DEVIATION_PERCENT")
+                .unwrap_or(default.max_history_deviation_percent),
+            history_window: env_secs("QUALITY_HISTORY_WINDOW_SECS")
+                .unwrap_or(default.history_window),
+            stale_after: env_secs("QUALITY_STALE_AFTER_SECS").unwrap_or(default.stale_after),
+        }
+    }
+}
+
+// Last exchange timestamp seen for a quote, and when we first saw it
+#[derive(Debug, Clone, Copy)]
+struct LastSeen {
+    timestamp: DateTime<Utc>,
+    observed_at: DateTime<Utc>,
+}
+
+/// Flags exchange prices that disagree with other exchanges, with their own
+/// recent history, or that have stopped updating
+pub struct QualityMonitor {
+    config: QualityConfig,
+    last_seen: Mutex<HashMap<(Exchange, TradingPair), LastSeen>>,
+}
+
+impl QualityMonitor {
+    pub fn new(config: QualityConfig) -> Self {
+        Self {
+            config,
+            last_seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Assess a set of prices for the same pair, attaching flags to each price.
+    ///
+    /// Price ages are measured on each exchange's own clock, correcting for
+    /// the skew of the local clock.
+    pub async fn assess(
+        &self,
+        prices: &mut [CurrentPrice],
+        store: &PriceStore,
+        clocks: &ClockMonitor,
+    ) {
+        // With fewer than three quotes the median cannot tell which one deviates
+        let consensus = aggregate::consensus(prices);
+
+        for price in prices.iter_mut() {
+            let mut flags = Vec::new();
+            let now = clocks.now(price.exchange);
+
+            if let Some(flag) = self.check_stale(price, now) {
+                flags.push(flag);
+            }
+
+            if let Some(consensus) = consensus.filter(|c| *c > 0.0) {
+                let deviation_percent = (to_f64(price.price) - consensus).abs() / consensus * 100.0;
+                if deviation_percent > self.config.max_consensus_deviation_percent {
+                    flags.push(QualityFlag::ConsensusDeviation {
+                        consensus,
+                        deviation_percent,
+                    });
+                }
+            }
+
+            if let Some(flag) = self.check_history(price, store, now).await {
+                flags.push(flag);
+            }
+
+            for flag in &flags {
+                warn!(
+                    "Quality flag on {} {}/{} price {}: {:?}",
+                    price.exchange, price.pair.base, price.pair.quote, price.price, flag
+                );
+            }
+
+            // Keep flags already attached by an earlier assessment
+            for flag in flags {
+                if !price.flags.contains(&flag) {
+                    price.flags.push(flag);
+                }
+            }
+        }
+    }
+
+    fn check_stale(&self, price: &CurrentPrice, now: DateTime<Utc>) -> Option<QualityFlag> {
+        let key = (price.exchange, price.pair.clone());
+        let mut last_seen = self.last_seen.lock().unwrap_or_else(|e| e.into_inner());
+
+        let entry = last_seen.entry(key).or_insert(LastSeen {
+            timestamp: price.timestamp,
+            observed_at: now,
+        });
+
+        // A newer exchange timestamp resets the staleness clock
+        if price.timestamp > entry.timestamp {
+            *entry = LastSeen {
+                timestamp: price.timestamp,
+                observed_at: now,
+            };
+        }
+
+        let unchanged_for = now - entry.observed_at;
+        let age = now - price.timestamp;
+        let stale_for = unchanged_for.max(age);
+
+        (stale_for > self.config.stale_after).then(|| QualityFlag::Stale {
+            last_update: price.timestamp,
+            age_secs: stale_for.num_seconds(),
+        })
+    }
+
+    async fn check_history(
+        &self,
+        price: &CurrentPrice,
+        store: &PriceStore,
+        now: DateTime<Utc>,
+    ) -> Option<QualityFlag> {
+        let query = PriceQuery {
+            pair: price.pair.clone(),
+            exchange: Some(price.exchange),
+            interval: PriceInterval::OneMinute,
+            start_time: Some(now - self.config.history_window),
+            end_time: Some(now),
+            limit: None,
+        };
+
+        let history = match store.get_price_history(&query).await {
+            Ok(history) => history,
+            Err(e) => {
+                debug!(
+                    "No history to assess {} price against: {}",
+                    price.exchange, e
+                );
+                return None;
+            }
+        };
+
+        let values: Vec<f64> = history.data.iter().map(|p| to_f64(p.price)).collect();
+        let reference = median(&values).filter(|r| *r > 0.0)?;
+        let deviation_percent = (to_f64(price.price) - reference).abs() / reference * 100.0;
+
+        (deviation_percent > self.config.max_history_deviation_percent).then_some(
+            QualityFlag::HistoryDeviation {
+                reference,
+                deviation_percent,
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ClockConfig;
+    use rust_decimal::Decimal;
+
+    fn price(exchange: Exchange, price: &str) -> CurrentPrice {
+        CurrentPrice {
+            exchange,
+            pair: TradingPair {
+                base: "BTC".to_string(),
+                quote: "USD".to_string(),
+            },
+            price: price.parse::<Decimal>().unwrap(),
+            bid: None,
+            ask: None,
+            open_24h: None,
+            high_24h: None,
+            low_24h: None,
+            change_24h: None,
+            change_percent_24h: None,
+            base_volume_24h: None,
+            volume_24h: None,
+            timestamp: Utc::now(),
+            flags: Vec::new(),
+        }
+    }
+
+    // Store nothing listens on, so prices have no history to compare against
+    fn store() -> PriceStore {
+        PriceStore::new(store::StoreConfig {
+            url: "http://127.0.0.1:1".to_string(),
+            token: "token".to_string(),
+            org: "org".to_string(),
+            bucket: "bucket".to_string(),
+            store_trades: false,
+            price_max_age_secs: 60,
+        })
+        .unwrap()
+    }
+
+    fn deviates(price: &CurrentPrice) -> bool {
+        price
+            .flags
+            .iter()
+            .any(|flag| matches!(flag, QualityFlag::ConsensusDeviation { .. }))
+    }
+
+    #[tokio::test]
+    async fn consensus_deviation_needs_three_quotes() {
+        let monitor = QualityMonitor::new(QualityConfig::default());
+        let clocks = ClockMonitor::new(ClockConfig::default());
+        let store = store();
+
+        // Two quotes deviate equally from their median, so neither is flagged
+        let mut prices = [
+            price(Exchange::Coinbase, "100"),
+            price(Exchange::Binance, "110"),
+        ];
+        monitor.assess(&mut prices, &store, &clocks).await;
+        assert!(!prices.iter().any(deviates));
+
+        // A third quote singles out the deviating one
+        let mut prices = [
+            price(Exchange::Coinbase, "100"),
+            price(Exchange::Binance, "110"),
+            price(Exchange::Custom("kraken"), "100.5"),
+        ];
+        monitor.assess(&mut prices, &store, &clocks).await;
+        let flagged: Vec<_> = prices.iter().filter(|p| deviates(p)).collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].exchange, Exchange::Binance);
+    }
+}
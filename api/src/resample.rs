@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use common::models::{PriceHistory, PriceHistoryPoint, Timeframe};
+use std::collections::BTreeMap;
+
+/// Resample price history into bars of a coarser timeframe.
+///
+/// Each output bar is stamped with its bucket start (see [`Timeframe`] for the
+/// alignment rules) and combines the source bars as open = first, close = last,
+/// high = max, low = min and volume = sum. Output is sorted newest first.
+pub fn resample(history: &PriceHistory, timeframe: Timeframe) -> PriceHistory {
+    let mut source: Vec<&PriceHistoryPoint> = history.data.iter().collect();
+    source.sort_by_key(|point| point.timestamp);
+
+    let mut buckets: BTreeMap<DateTime<Utc>, PriceHistoryPoint> = BTreeMap::new();
+
+    for point in source {
+        let open = point.open.unwrap_or(point.price);
+        let high = point.high.unwrap_or(point.price);
+        let low = point.low.unwrap_or(point.price);
+        let Some(start) = timeframe.bucket_start(point.timestamp) else {
+            continue;
+        };
+
+        buckets
+            .entry(start)
+            .and_modify(|bar| {
+                bar.price = point.price;
+                bar.high = bar.high.map(|h| h.max(high));
+                bar.low = bar.low.map(|l| l.min(low));
+                bar.volume = match (bar.volume, point.volume) {
+                    (Some(total), Some(volume)) => Some(total + volume),
+                    (total, volume) => total.or(volume),
+                };
+            })
+            .or_insert_with_key(|start| PriceHistoryPoint {
+                timestamp: *start,
+                price: point.price,
+                open: Some(open),
+                high: Some(high),
+                low: Some(low),
+                volume: point.volume,
+            });
+    }
+
+    PriceHistory {
+        exchange: history.exchange,
+        pair: history.pair.clone(),
+        interval: timeframe,
+        data: buckets.into_values().rev().collect(),
+    }
+}
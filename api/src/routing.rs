@@ -0,0 +1,296 @@
+use chrono::{DateTime, Utc};
+use common::models::{
+    CurrentPrice, Exchange, MarketInfo, PriceHistory, PriceHistoryPoint, PriceRoute, RouteLeg,
+    Timeframe, TradingPair,
+};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::aggregate::quote_volume;
+use crate::decimal::to_f64;
+
+/// Settings for deriving prices through intermediate markets
+#[derive(Debug, Clone, Copy)]
+pub struct RoutingConfig {
+    /// Maximum number of markets a route may go through
+    pub max_hops: usize,
+    /// Number of candidate routes priced before choosing one
+    pub max_candidates: usize,
+    /// Factor applied to a route's liquidity for every hop beyond the first
+    pub hop_penalty: f64,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            max_hops: 3,
+            max_candidates: 5,
+            hop_penalty: 0.5,
+        }
+    }
+}
+
+impl RoutingConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_usize = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|v| *v > 0)
+        };
+
+        Self {
+            max_hops: env_usize("ROUTING_MAX_HOPS").unwrap_or(default.max_hops),
+            max_candidates: env_usize("ROUTING_MAX_CANDIDATES").unwrap_or(default.max_candidates),
+            hop_penalty: std::env::var("ROUTING_HOP_PENALTY")
+                .ok()
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v > 0.0 && *v <= 1.0)
+                .unwrap_or(default.hop_penalty),
+        }
+    }
+}
+
+/// Graph of the assets that tradable markets convert between
+pub struct RateGraph {
+    // Conversions out of each asset
+    edges: HashMap<String, Vec<RouteLeg>>,
+    // Conversions between each pair of assets
+    links: HashMap<(String, String), Vec<RouteLeg>>,
+}
+
+impl RateGraph {
+    pub fn new(markets: &[MarketInfo]) -> Self {
+        let mut edges: HashMap<String, Vec<RouteLeg>> = HashMap::new();
+        let mut links: HashMap<(String, String), Vec<RouteLeg>> = HashMap::new();
+
+        for market in markets.iter().filter(|m| m.status.is_tradable()) {
+            let base = market.pair.base.to_uppercase();
+            let quote = market.pair.quote.to_uppercase();
+            let leg = |from: &str, to: &str, inverted| RouteLeg {
+                exchange: market.exchange,
+                pair: market.pair.clone(),
+                from: from.to_string(),
+                to: to.to_string(),
+                inverted,
+                rate: None,
+                volume_24h: None,
+            };
+
+            for leg in [leg(&base, &quote, false), leg(&quote, &base, true)] {
+                links
+                    .entry((leg.from.clone(), leg.to.clone()))
+                    .or_default()
+                    .push(leg.clone());
+                edges.entry(leg.from.clone()).or_default().push(leg);
+            }
+        }
+
+        Self { edges, links }
+    }
+
+    // Number of markets an asset trades in, as a proxy for how liquid a hub it is
+    fn degree(&self, asset: &str) -> usize {
+        self.edges.get(asset).map_or(0, Vec::len)
+    }
+
+    /// Candidate routes to `target`, fewest hops first and, among routes with
+    /// as many hops, through the best connected intermediate assets first.
+    ///
+    /// `starts` gives the coin's symbol on each exchange; the first leg must be
+    /// a market of that exchange.
+    pub fn routes(
+        &self,
+        starts: &[(Exchange, String)],
+        target: &str,
+        max_hops: usize,
+    ) -> Vec<Vec<RouteLeg>> {
+        let target = target.to_uppercase();
+        let mut routes = Vec::new();
+
+        for (exchange, symbol) in starts {
+            let symbol = symbol.to_uppercase();
+            let Some(first_legs) = self.edges.get(&symbol) else {
+                continue;
+            };
+
+            for leg in first_legs.iter().filter(|leg| leg.exchange == *exchange) {
+                let mut visited = HashSet::from([symbol.clone(), leg.to.clone()]);
+                let mut path = vec![leg.clone()];
+                self.extend(&mut path, &mut visited, &target, max_hops, &mut routes);
+            }
+        }
+
+        // Prefer short routes through well-connected hubs
+        routes.sort_by_key(|route: &Vec<RouteLeg>| {
+            let hub = route[..route.len() - 1]
+                .iter()
+                .map(|leg| self.degree(&leg.to))
+                .min()
+                .unwrap_or(usize::MAX);
+            (route.len(), std::cmp::Reverse(hub))
+        });
+        routes
+    }
+
+    // Depth-first search for routes ending in `target`
+    fn extend(
+        &self,
+        path: &mut Vec<RouteLeg>,
+        visited: &mut HashSet<String>,
+        target: &str,
+        max_hops: usize,
+        routes: &mut Vec<Vec<RouteLeg>>,
+    ) {
+        let current = path[path.len() - 1].to.clone();
+        if current == target {
+            routes.push(path.clone());
+            return;
+        }
+        if path.len() >= max_hops {
+            return;
+        }
+
+        // Only the target is worth visiting on the last hop
+        let next_legs = if path.len() + 1 == max_hops {
+            self.links.get(&(current, target.to_string()))
+        } else {
+            self.edges.get(&current)
+        };
+
+        for leg in next_legs.into_iter().flatten() {
+            if visited.contains(&leg.to) {
+                continue;
+            }
+
+            visited.insert(leg.to.clone());
+            path.push(leg.clone());
+            self.extend(path, visited, target, max_hops, routes);
+            path.pop();
+            visited.remove(&leg.to);
+        }
+    }
+}
+
+/// Price a route from current market prices keyed by exchange and market.
+///
+/// Returns the synthetic price, the route with leg rates, volumes and
+/// liquidity filled in, and the timestamp of the oldest leg price, or `None`
+/// if a leg has no usable price.
+pub fn price_route(
+    legs: &[RouteLeg],
+    prices: &HashMap<(Exchange, TradingPair), CurrentPrice>,
+) -> Option<(Decimal, PriceRoute, DateTime<Utc>)> {
+    let mut rate_from_coin = Decimal::ONE;
+    let mut volumes_in_coin = Vec::with_capacity(legs.len());
+    let mut timestamp: Option<DateTime<Utc>> = None;
+    let mut priced = Vec::with_capacity(legs.len());
+
+    for leg in legs {
+        let price = prices.get(&(leg.exchange, leg.pair.clone()))?;
+        if price.price <= Decimal::ZERO {
+            return None;
+        }
+
+        let (rate, volume) = if leg.inverted {
+            // Converting from the quote: volume is the market's quote volume
+            (Decimal::ONE.checked_div(price.price)?, quote_volume(price))
+        } else {
+            let base_volume = price
+                .base_volume_24h
+                .or_else(|| price.volume_24h?.checked_div(price.price));
+            (price.price, base_volume)
+        };
+
+        // Express the leg volume in units of the coin
+        volumes_in_coin.push(volume.and_then(|v| v.checked_div(rate_from_coin)));
+        rate_from_coin = rate_from_coin.checked_mul(rate).filter(|r| !r.is_zero())?;
+        timestamp = Some(timestamp.map_or(price.timestamp, |t| t.min(price.timestamp)));
+
+        priced.push(RouteLeg {
+            rate: Some(rate),
+            volume_24h: volume,
+            ..leg.clone()
+        });
+    }
+
+    // The least liquid leg, if every leg's volume is known
+    let liquidity = volumes_in_coin
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .and_then(|volumes| volumes.into_iter().min());
+
+    Some((
+        rate_from_coin,
+        PriceRoute {
+            legs: priced,
+            liquidity,
+        },
+        timestamp?,
+    ))
+}
+
+/// Score of a priced route: its liquidity, discounted for every extra hop
+pub fn score(route: &PriceRoute, hop_penalty: f64) -> f64 {
+    let hops = route.legs.len().saturating_sub(1) as i32;
+    to_f64(route.liquidity.unwrap_or_default()) * hop_penalty.powi(hops)
+}
+
+/// Combine the price history of every leg of a route into a synthetic
+/// history, at bars where every leg has a price. High and low prices cannot
+/// be derived from the legs and are left out.
+pub fn combine_histories(
+    route: &PriceRoute,
+    histories: &[PriceHistory],
+    timeframe: Timeframe,
+) -> Vec<PriceHistoryPoint> {
+    let mut bars: BTreeMap<DateTime<Utc>, (Option<Decimal>, Option<Decimal>, usize)> =
+        BTreeMap::new();
+
+    for (leg, history) in route.legs.iter().zip(histories) {
+        let rate = |price: Decimal| {
+            if leg.inverted {
+                Decimal::ONE.checked_div(price)
+            } else {
+                Some(price)
+            }
+        };
+
+        // One bar per bucket per leg, taking the latest point in the bucket
+        let mut points: BTreeMap<DateTime<Utc>, &PriceHistoryPoint> = BTreeMap::new();
+        let mut sorted: Vec<&PriceHistoryPoint> = history.data.iter().collect();
+        sorted.sort_by_key(|p| p.timestamp);
+        for point in sorted.into_iter().filter(|p| p.price > Decimal::ZERO) {
+            if let Some(start) = timeframe.bucket_start(point.timestamp) {
+                points.insert(start, point);
+            }
+        }
+
+        for (timestamp, point) in points {
+            let close = point.price;
+            let open = point.open.filter(|o| *o > Decimal::ZERO).unwrap_or(close);
+            let bar = bars
+                .entry(timestamp)
+                .or_insert((Some(Decimal::ONE), Some(Decimal::ONE), 0));
+            bar.0 = bar.0.zip(rate(open)).and_then(|(o, r)| o.checked_mul(r));
+            bar.1 = bar.1.zip(rate(close)).and_then(|(c, r)| c.checked_mul(r));
+            bar.2 += 1;
+        }
+    }
+
+    bars.into_iter()
+        .rev()
+        .filter(|(_, (_, _, legs))| *legs == route.legs.len())
+        .filter_map(|(timestamp, (open, close, _))| {
+            Some(PriceHistoryPoint {
+                timestamp,
+                price: close?,
+                open: Some(open?),
+                high: None,
+                low: None,
+                volume: None,
+            })
+        })
+        .collect()
+}
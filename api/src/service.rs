@@ -1,24 +1,203 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
 use common::{
-    models::{Coin, CurrentPrice, Exchange, PriceHistory, PriceInterval, TradingPair},
+    models::{
+        AccountImport, AggregatedPrice, AlertCondition, AlertRule, AlertRuleSpec, Basis,
+        BasisHistory, BasisPoint, BatchPriceCell, BatchPrices, ClockSkew, Coin, CoinMetadata,
+        CoinProfile, ConvertedPrice, ConvertedPriceHistory, ConvertedPricePoint, CurrentPrice,
+        EncryptedAccount, Exchange, ExchangeAccount, ExchangeAccountSpec, ExchangeStatus,
+        FundingRateHistory, FxConversion, FxRates, HoldingValuation, IndicatorPoint,
+        IndicatorSeries, MarketInfo, PerpetualHistory, PerpetualMarket, Portfolio, PortfolioSpec,
+        PortfolioValuation, PortfolioValueHistory, PriceHistory, PriceHistoryPoint, PriceInterval,
+        RoutedPrice, RoutedPriceHistory, Timeframe, Trade, TradingPair,
+    },
     Error, Result,
 };
-use connectors::ExchangeConnector;
-use std::collections::HashMap;
+use connectors::{
+    http::HttpClient, signing::Credentials, DerivativesConnector, ExchangeConnector,
+    FxRateConnector,
+};
+use futures::stream::{self, StreamExt};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 use store::{PriceQuery, PriceStore};
 use tracing::{debug, error, info};
 
+use crate::accounts::{AccountBook, AccountConfig};
+use crate::aggregate::{self, AggregationConfig};
+use crate::alerts::{AlertConfig, AlertEngine};
+use crate::batch::BatchConfig;
+use crate::catalog::{self, CatalogConfig, CoinCatalog};
+use crate::clock::{self, ClockConfig, ClockMonitor};
+use crate::decimal::{checked_sum, to_f64};
+use crate::fx::{FxConfig, FxTable};
+use crate::indicators::Indicator;
+use crate::markets::{MarketCache, MarketFilter};
+use crate::portfolio::{self, PortfolioBook};
+use crate::quality::{QualityConfig, QualityMonitor};
+use crate::resample;
+use crate::routing::{self, RateGraph, RoutingConfig};
+
+/// Number of bars returned for resampled history when neither a start time nor
+/// a limit is given
+const DEFAULT_RESAMPLED_BARS: usize = 100;
+
+/// Number of indicator values returned when neither a start time nor a limit is given
+const DEFAULT_INDICATOR_POINTS: usize = 100;
+
+/// Most source bars requested from an exchange at once when resampling; Coinbase
+/// returns at most 300 candles per request, Binance at most 1000
+const SOURCE_PAGE_BARS: i32 = 300;
+
+/// Most requests made for the source bars of one resampled history
+const MAX_SOURCE_PAGES: usize = 50;
+
+// First bar boundary of a resampled range ending at `end`
+fn resampled_start(
+    timeframe: Timeframe,
+    start_time: Option<DateTime<Utc>>,
+    end: DateTime<Utc>,
+    limit: Option<usize>,
+) -> Result<DateTime<Utc>> {
+    match start_time {
+        Some(start) => timeframe
+            .bucket_start(start)
+            .ok_or_else(|| Error::InvalidRequest(format!("Invalid start time: {}", start))),
+        None => timeframe.bars_before(end, limit.unwrap_or(DEFAULT_RESAMPLED_BARS)),
+    }
+}
+
+// Split a range into windows of at most SOURCE_PAGE_BARS bars of an interval,
+// newest first. Adjacent windows share their boundary, so pages may overlap
+// by a bar.
+fn source_pages(
+    interval: PriceInterval,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+    let span = interval.duration() * (SOURCE_PAGE_BARS - 1);
+    let mut pages = Vec::new();
+    let mut page_end = end;
+
+    loop {
+        let page_start = page_end
+            .checked_sub_signed(span)
+            .map_or(start, |page_start| page_start.max(start));
+        pages.push((page_start, page_end));
+
+        if page_start <= start {
+            return Ok(pages);
+        }
+        if pages.len() == MAX_SOURCE_PAGES {
+            return Err(Error::InvalidRequest(format!(
+                "Range from {} to {} needs more than {} {} bars; \
+                 request a shorter range or a longer interval",
+                start,
+                end,
+                MAX_SOURCE_PAGES * SOURCE_PAGE_BARS as usize,
+                interval
+            )));
+        }
+        page_end = page_start;
+    }
+}
+
+// Join pages of history into one, newest first without duplicate bars
+fn join_pages(pages: Vec<PriceHistory>) -> Option<PriceHistory> {
+    let mut pages = pages.into_iter();
+    let mut history = pages.next()?;
+    for page in pages {
+        history.data.extend(page.data);
+    }
+    history
+        .data
+        .sort_by_key(|point| std::cmp::Reverse(point.timestamp));
+    history.data.dedup_by_key(|point| point.timestamp);
+    Some(history)
+}
+
+// Whether stored history, newest first, covers a request: its oldest bar
+// reaches the start time, its newest bar the end time (now if open-ended),
+// and it holds `limit` bars. A range is also covered once `limit` bars from
+// its start are stored, which is all an exchange would return for it.
+fn covers(
+    history: &PriceHistory,
+    interval: PriceInterval,
+    start_time: Option<DateTime<Utc>>,
+    end_time: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+) -> bool {
+    let (Some(newest), Some(oldest)) = (history.data.first(), history.data.last()) else {
+        return false;
+    };
+    let step = interval.duration();
+    let full = limit.is_none_or(|limit| history.data.len() >= limit);
+    let reaches_end = newest.timestamp + step >= end_time.unwrap_or_else(Utc::now);
+
+    match start_time {
+        Some(start) => {
+            oldest.timestamp < start + step && (reaches_end || (limit.is_some() && full))
+        }
+        None => reaches_end && full,
+    }
+}
+
+// `part` as a percentage of a positive `whole`
+fn percent_of(part: Option<Decimal>, whole: Option<Decimal>) -> Option<f64> {
+    let whole = whole.filter(|w| *w > Decimal::ZERO)?;
+    Some(to_f64(part?.checked_div(whole)?) * 100.0)
+}
+
+// Latest quote of an exchange market, prefetched for a batch lookup
+struct MarketQuote {
+    // Empty if the market could not be priced
+    prices: Vec<CurrentPrice>,
+    // Whether the prices were fetched from the exchange rather than the store
+    fetched: bool,
+}
+
+type MarketQuotes = HashMap<(Exchange, TradingPair), MarketQuote>;
+
 /// Service for managing coin data and interacting with exchanges
 pub struct CoinService {
     /// Coinbase API connector
     coinbase: Arc<dyn ExchangeConnector>,
     /// Binance API connector
     binance: Arc<dyn ExchangeConnector>,
+    /// Connectors of exchanges added through configuration
+    custom: Vec<(Exchange, Arc<dyn ExchangeConnector>)>,
+    /// Perpetual futures connector
+    derivatives: Option<Arc<dyn DerivativesConnector>>,
     /// InfluxDB store for price data
     store: Arc<PriceStore>,
-    /// Cache of available coins
-    coins: HashMap<String, Coin>,
+    /// Catalog of available coins
+    catalog: CoinCatalog,
+    /// Where the coin catalog is loaded from
+    catalog_config: CatalogConfig,
+    /// Cache of market metadata per exchange
+    markets: MarketCache,
+    /// Default settings for cross-exchange price aggregation
+    aggregation: AggregationConfig,
+    /// Limits on batch price lookups
+    batch: BatchConfig,
+    /// Settings for deriving prices through intermediate markets
+    routing: RoutingConfig,
+    /// Source of fiat exchange rates
+    fx: Option<Arc<dyn FxRateConnector>>,
+    /// Settings for converting prices between fiat currencies
+    fx_config: FxConfig,
+    /// Known daily fiat exchange rates
+    fx_rates: FxTable,
+    /// Detector for outlier and stale exchange prices
+    quality: QualityMonitor,
+    /// Measured clock skew of each exchange
+    clocks: ClockMonitor,
+    /// Price alert rules and their webhook notifications
+    alerts: AlertEngine,
+    /// Registered portfolios
+    portfolios: PortfolioBook,
+    /// Connected exchange accounts
+    accounts: AccountBook,
 }
 
 impl CoinService {
@@ -27,151 +206,359 @@ impl CoinService {
         binance: Arc<dyn ExchangeConnector>,
         store: Arc<PriceStore>,
     ) -> Self {
-        // Initialize with some popular coins
-        let mut coins = HashMap::new();
-        
-        let popular_coins = vec![
-            Coin {
-                id: "bitcoin".to_string(),
-                name: "Bitcoin".to_string(),
-                symbol: "BTC".to_string(),
-            },
-            Coin {
-                id: "ethereum".to_string(),
-                name: "Ethereum".to_string(),
-                symbol: "ETH".to_string(),
-            },
-            Coin {
-                id: "ripple".to_string(),
-                name: "XRP".to_string(),
-                symbol: "XRP".to_string(),
-            },
-            Coin {
-                id: "cardano".to_string(),
-                name: "Cardano".to_string(),
-                symbol: "ADA".to_string(),
-            },
-            Coin {
-                id: "solana".to_string(),
-                name: "Solana".to_string(),
-                symbol: "SOL".to_string(),
-            },
-        ];
-
-        for coin in popular_coins {
-            coins.insert(coin.id.clone(), coin);
-        }
-
         Self {
             coinbase,
             binance,
+            custom: Vec::new(),
+            derivatives: None,
             store,
-            coins,
+            catalog: CoinCatalog::new(catalog::default_coins()),
+            catalog_config: CatalogConfig::default(),
+            markets: MarketCache::new(),
+            aggregation: AggregationConfig::default(),
+            batch: BatchConfig::default(),
+            routing: RoutingConfig::default(),
+            fx: None,
+            fx_config: FxConfig::default(),
+            fx_rates: FxTable::new(),
+            quality: QualityMonitor::new(QualityConfig::default()),
+            clocks: ClockMonitor::new(ClockConfig::default()),
+            alerts: AlertEngine::new(AlertConfig::default()),
+            portfolios: PortfolioBook::new(),
+            accounts: AccountBook::new(AccountConfig::default(), HttpClient::default()),
+        }
+    }
+
+    /// Set where the coin catalog is loaded from
+    pub fn with_catalog(mut self, config: CatalogConfig) -> Self {
+        self.catalog_config = config;
+        self
+    }
+
+    /// Set the thresholds used to flag suspicious prices
+    pub fn with_quality(mut self, config: QualityConfig) -> Self {
+        self.quality = QualityMonitor::new(config);
+        self
+    }
+
+    /// Set how exchange clock skew is measured
+    pub fn with_clock(mut self, config: ClockConfig) -> Self {
+        self.clocks = ClockMonitor::new(config);
+        self
+    }
+
+    /// Set the alert evaluation and webhook delivery settings
+    pub fn with_alerts(mut self, config: AlertConfig) -> Self {
+        self.alerts = AlertEngine::new(config);
+        self
+    }
+
+    /// Set how exchange accounts are stored and reached
+    pub fn with_accounts(mut self, config: AccountConfig, http: HttpClient) -> Self {
+        self.accounts = AccountBook::new(config, http);
+        self
+    }
+
+    /// Set the default price aggregation settings
+    pub fn with_aggregation(mut self, aggregation: AggregationConfig) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    /// Set the limits on batch price lookups
+    pub fn with_batch(mut self, batch: BatchConfig) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    /// Set how prices are derived through intermediate markets
+    pub fn with_routing(mut self, routing: RoutingConfig) -> Self {
+        self.routing = routing;
+        self
+    }
+
+    /// Add the connector of an exchange added through configuration
+    pub fn with_exchange(
+        mut self,
+        exchange: Exchange,
+        connector: Arc<dyn ExchangeConnector>,
+    ) -> Self {
+        self.custom.push((exchange, connector));
+        self
+    }
+
+    /// Set the connector for perpetual futures market data
+    pub fn with_derivatives(mut self, connector: Arc<dyn DerivativesConnector>) -> Self {
+        self.derivatives = Some(connector);
+        self
+    }
+
+    /// Set the source of fiat exchange rates and how prices are converted
+    pub fn with_fx(mut self, connector: Arc<dyn FxRateConnector>, config: FxConfig) -> Self {
+        self.fx = Some(connector);
+        self.fx_config = config;
+        self
+    }
+
+    /// Default price aggregation settings
+    pub fn aggregation_config(&self) -> &AggregationConfig {
+        &self.aggregation
+    }
+
+    /// Connectors to query, restricted to a single exchange if one is given
+    fn connectors(
+        &self,
+        exchange: Option<Exchange>,
+    ) -> Vec<(Exchange, &Arc<dyn ExchangeConnector>)> {
+        match exchange {
+            Some(Exchange::Coinbase) => vec![(Exchange::Coinbase, &self.coinbase)],
+            Some(Exchange::Binance) => vec![(Exchange::Binance, &self.binance)],
+            Some(exchange) => self
+                .custom
+                .iter()
+                .filter(|(custom, _)| *custom == exchange)
+                .map(|(custom, connector)| (*custom, connector))
+                .collect(),
+            None => [
+                (Exchange::Coinbase, &self.coinbase),
+                (Exchange::Binance, &self.binance),
+            ]
+            .into_iter()
+            .chain(
+                self.custom
+                    .iter()
+                    .map(|(custom, connector)| (*custom, connector)),
+            )
+            .collect(),
         }
     }
 
     /// List all available coins
     pub async fn list_coins(&self) -> Result<Vec<Coin>> {
-        Ok(self.coins.values().cloned().collect())
+        Ok(self.catalog.list())
     }
 
     /// Get coin by ID
     pub fn get_coin(&self, id: &str) -> Result<Coin> {
-        self.coins
+        self.catalog
             .get(id)
-            .cloned()
             .ok_or_else(|| Error::NotFound(format!("Coin with ID '{}' not found", id)))
     }
 
-    /// Get current price for a coin
+    /// Rebuild the coin catalog from the catalog file and, if enabled, the
+    /// symbols listed on the exchanges. Coins no longer in either are dropped.
+    pub async fn refresh_catalog(&self) -> Result<()> {
+        let mut coins = match &self.catalog_config.path {
+            Some(path) => catalog::load_file(path)?,
+            None => catalog::default_coins(),
+        };
+        catalog::validate(&coins)?;
+
+        if self.catalog_config.discover {
+            let mut listing_failed = false;
+            for (ex, connector) in self.connectors(None) {
+                match connector.list_trading_pairs().await {
+                    Ok(pairs) => {
+                        let listed = pairs.into_iter().map(|pair| pair.base);
+                        catalog::merge(&mut coins, catalog::discovered_coins(listed));
+                    }
+                    Err(e) => {
+                        error!("Failed to list {} trading pairs: {}", ex, e);
+                        listing_failed = true;
+                    }
+                }
+            }
+
+            // Keep the coins discovered last time while an exchange is
+            // unreachable, rather than dropping them until the next refresh
+            if listing_failed {
+                match self.store.get_coins().await {
+                    Ok(stored) => catalog::merge(&mut coins, stored),
+                    Err(e) => error!("Failed to load persisted coin catalog: {}", e),
+                }
+            }
+        }
+
+        if let Err(e) = self.store.store_coins(&coins).await {
+            error!("Failed to persist coin catalog: {}", e);
+        }
+
+        // Metadata edited through the admin API takes precedence over the file
+        let mut metadata = match &self.catalog_config.metadata_path {
+            Some(path) => catalog::load_metadata_file(path)?,
+            None => Default::default(),
+        };
+        match self.store.get_coin_metadata().await {
+            Ok(stored) => metadata.extend(stored),
+            Err(e) => error!("Failed to load persisted coin metadata: {}", e),
+        }
+
+        info!("Loaded {} coins into the catalog", coins.len());
+        self.catalog.replace(coins)?;
+        self.catalog.replace_metadata(metadata);
+
+        Ok(())
+    }
+
+    /// Get the full profile of a coin, with market data in a quote currency
+    pub async fn get_coin_profile(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+    ) -> Result<CoinProfile> {
+        let coin = self.get_coin(coin_id)?;
+        let metadata = self.catalog.metadata(coin_id);
+
+        // A profile is still useful without market data
+        let price = match self
+            .get_aggregated_price(coin_id, quote_currency, &self.aggregation)
+            .await
+        {
+            Ok(aggregated) => Some(aggregated.price),
+            Err(e) => {
+                error!("No aggregated price for {} profile: {}", coin_id, e);
+                None
+            }
+        };
+
+        let market_cap = price
+            .zip(metadata.circulating_supply)
+            .and_then(|(price, supply)| price.checked_mul(supply));
+        let fully_diluted_valuation = price
+            .zip(metadata.max_supply.or(metadata.total_supply))
+            .and_then(|(price, supply)| price.checked_mul(supply));
+
+        Ok(CoinProfile {
+            coin,
+            metadata,
+            currency: quote_currency.to_uppercase(),
+            price,
+            market_cap,
+            fully_diluted_valuation,
+        })
+    }
+
+    /// Get the metadata of a coin
+    pub fn get_coin_metadata(&self, coin_id: &str) -> Result<CoinMetadata> {
+        self.get_coin(coin_id)?;
+        Ok(self.catalog.metadata(coin_id))
+    }
+
+    /// Replace the metadata of a coin and persist it
+    pub async fn update_coin_metadata(
+        &self,
+        coin_id: &str,
+        metadata: CoinMetadata,
+    ) -> Result<CoinMetadata> {
+        self.get_coin(coin_id)?;
+
+        self.store.store_coin_metadata(coin_id, &metadata).await?;
+        self.catalog.set_metadata(coin_id, metadata.clone());
+
+        info!("Updated metadata for coin {}", coin_id);
+        Ok(metadata)
+    }
+
+    /// Get current price for a coin, with data-quality flags attached
     pub async fn get_current_price(
         &self,
         coin_id: &str,
         quote_currency: &str,
         exchange: Option<Exchange>,
     ) -> Result<Vec<CurrentPrice>> {
-        let coin = self.get_coin(coin_id)?;
-        
-        let pair = TradingPair {
-            base: coin.symbol.clone(),
-            quote: quote_currency.to_uppercase(),
+        self.current_price(coin_id, quote_currency, exchange, None)
+            .await
+    }
+
+    // Get current prices, taking them from prefetched market quotes if given
+    async fn current_price(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        exchange: Option<Exchange>,
+        quotes: Option<&MarketQuotes>,
+    ) -> Result<Vec<CurrentPrice>> {
+        let mut prices = match quotes {
+            Some(quotes) => {
+                self.quoted_price(coin_id, quote_currency, exchange, quotes)
+                    .await?
+            }
+            None => {
+                self.fetch_current_price(coin_id, quote_currency, exchange)
+                    .await?
+            }
         };
 
+        self.quality
+            .assess(&mut prices, &self.store, &self.clocks)
+            .await;
+
+        Ok(prices)
+    }
+
+    // Get current prices from the store or the exchanges
+    async fn fetch_current_price(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        exchange: Option<Exchange>,
+    ) -> Result<Vec<CurrentPrice>> {
+        let coin = self.get_coin(coin_id)?;
+
+        let pair = coin.pair(&quote_currency.to_uppercase());
+
         debug!(
             "Getting current price for {} ({}/{})",
             coin_id, pair.base, pair.quote
         );
 
-        // Try to get price from store first
-        match self.store.get_current_price(&pair, exchange).await {
-            Ok(prices) if !prices.is_empty() => {
-                debug!("Retrieved prices from store: {} results", prices.len());
-                return Ok(prices);
-            }
-            _ => {
-                debug!("No prices found in store, fetching from exchanges");
-            }
-        }
+        // Take each exchange's price from the store, fetching only the
+        // exchanges without one. Prices are stored under each exchange's own
+        // symbol, which is unique even when coins share a ticker.
+        let markets = self
+            .connectors(exchange)
+            .into_iter()
+            .map(|(ex, _)| (ex, coin.pair_on(ex, &pair.quote)));
+        let mut quotes = MarketQuotes::new();
+        self.fetch_market_quotes(markets, &mut quotes).await;
+
+        self.quoted_price(coin_id, quote_currency, exchange, &quotes)
+            .await
+    }
 
-        // Fetch prices from exchanges
+    // Fetch current prices from the exchanges, store them and evaluate the
+    // coin's alert rules against them
+    async fn ingest_current_price(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        exchange: Option<Exchange>,
+    ) -> Result<Vec<CurrentPrice>> {
+        let coin = self.get_coin(coin_id)?;
+        let pair = coin.pair(&quote_currency.to_uppercase());
+
+        // Fetch prices from exchanges, using each exchange's own symbol for the coin
         let mut prices = Vec::new();
 
-        // If a specific exchange is requested, only query that one
-        if let Some(ex) = exchange {
-            match ex {
-                Exchange::Coinbase => {
-                    match self.coinbase.get_current_price(&pair).await {
-                        Ok(price) => {
-                            // Store the price for future queries
-                            let _ = self.store.store_current_price(&price).await;
-                            prices.push(price);
-                        }
-                        Err(e) => {
-                            error!("Failed to get Coinbase price: {}", e);
-                        }
-                    }
-                }
-                Exchange::Binance => {
-                    match self.binance.get_current_price(&pair).await {
-                        Ok(price) => {
-                            // Store the price for future queries
-                            let _ = self.store.store_current_price(&price).await;
-                            prices.push(price);
-                        }
-                        Err(e) => {
-                            error!("Failed to get Binance price: {}", e);
-                        }
-                    }
-                }
-            }
-        } else {
-            // Try both exchanges
-            
-            // Coinbase
-            match self.coinbase.get_current_price(&pair).await {
-                Ok(price) => {
-                    // Store the price for future queries
-                    let _ = self.store.store_current_price(&price).await;
-                    prices.push(price);
-                }
-                Err(e) => {
-                    error!("Failed to get Coinbase price: {}", e);
-                }
-            }
-            
-            // Binance
-            match self.binance.get_current_price(&pair).await {
+        for (ex, connector) in self.connectors(exchange) {
+            match connector
+                .get_current_price(&coin.pair_on(ex, &pair.quote))
+                .await
+            {
                 Ok(price) => {
                     // Store the price for future queries
                     let _ = self.store.store_current_price(&price).await;
                     prices.push(price);
                 }
                 Err(e) => {
-                    error!("Failed to get Binance price: {}", e);
+                    error!("Failed to get {} price: {}", ex, e);
                 }
             }
         }
 
+        // Newly ingested prices may trigger alerts
+        self.alerts.evaluate(coin_id, &prices, &self.store).await;
+
         if prices.is_empty() {
             return Err(Error::ExchangeError(format!(
                 "Failed to get current price for {}/{}",
@@ -182,81 +569,2303 @@ impl CoinService {
         Ok(prices)
     }
 
-    /// Get historical price data for a coin
-    pub async fn get_price_history(
+    // Get current prices from prefetched market quotes
+    async fn quoted_price(
         &self,
         coin_id: &str,
         quote_currency: &str,
-        interval: PriceInterval,
         exchange: Option<Exchange>,
-        start_time: Option<DateTime<Utc>>,
-        end_time: Option<DateTime<Utc>>,
-        limit: Option<usize>,
-    ) -> Result<PriceHistory> {
+        quotes: &MarketQuotes,
+    ) -> Result<Vec<CurrentPrice>> {
         let coin = self.get_coin(coin_id)?;
-        
-        let pair = TradingPair {
-            base: coin.symbol.clone(),
-            quote: quote_currency.to_uppercase(),
-        };
+        let pair = coin.pair(&quote_currency.to_uppercase());
 
-        debug!(
-            "Getting price history for {} ({}/{}) with interval {:?}",
-            coin_id, pair.base, pair.quote, interval
-        );
+        let mut prices = Vec::new();
+        let mut fetched = Vec::new();
+        for (ex, _) in self.connectors(exchange) {
+            if let Some(quote) = quotes.get(&(ex, coin.pair_on(ex, &pair.quote))) {
+                prices.extend(quote.prices.iter().cloned());
+                if quote.fetched {
+                    fetched.extend(quote.prices.iter().cloned());
+                }
+            }
+        }
 
-        // Try to get history from store first
-        let query = PriceQuery {
-            pair: pair.clone(),
-            exchange,
-            interval,
-            start_time,
-            end_time,
-            limit,
-        };
+        if prices.is_empty() {
+            return Err(Error::ExchangeError(format!(
+                "Failed to get current price for {}/{}",
+                pair.base, pair.quote
+            )));
+        }
 
-        match self.store.get_price_history(&query).await {
-            Ok(history) if !history.data.is_empty() => {
-                debug!("Retrieved history from store: {} points", history.data.len());
-                return Ok(history);
-            }
-            _ => {
-                debug!("No history found in store, fetching from exchanges");
+        // Prices newly ingested for the lookup may trigger alerts
+        if !fetched.is_empty() {
+            self.alerts.evaluate(coin_id, &fetched, &self.store).await;
+        }
+
+        Ok(prices)
+    }
+
+    // Get the latest quote of a single market from the store or the exchange
+    async fn market_quote(&self, exchange: Exchange, pair: &TradingPair) -> Option<MarketQuote> {
+        if let Ok(prices) = self.store.get_current_price(pair, Some(exchange)).await {
+            if !prices.is_empty() {
+                return Some(MarketQuote {
+                    prices,
+                    fetched: false,
+                });
             }
         }
 
-        // Fetch history from exchange(s)
-        let history = match exchange {
-            Some(Exchange::Coinbase) => {
-                self.coinbase
-                    .get_price_history(&pair, interval, start_time, end_time, limit)
-                    .await?
+        let (_, connector) = self.connectors(Some(exchange)).into_iter().next()?;
+        match connector.get_current_price(pair).await {
+            Ok(price) => {
+                let _ = self.store.store_current_price(&price).await;
+                Some(MarketQuote {
+                    prices: vec![price],
+                    fetched: true,
+                })
             }
-            Some(Exchange::Binance) => {
-                self.binance
-                    .get_price_history(&pair, interval, start_time, end_time, limit)
-                    .await?
+            Err(e) => {
+                error!("Failed to get {} price: {}", exchange, e);
+                None
             }
-            None => {
-                // Try Coinbase first, then Binance if Coinbase fails
-                match self
-                    .coinbase
-                    .get_price_history(&pair, interval, start_time, end_time, limit)
-                    .await
-                {
-                    Ok(history) => history,
-                    Err(_) => {
-                        self.binance
-                            .get_price_history(&pair, interval, start_time, end_time, limit)
-                            .await?
+        }
+    }
+
+    // Fetch the quotes of markets not yet in `quotes`, each market once
+    async fn fetch_market_quotes(
+        &self,
+        markets: impl IntoIterator<Item = (Exchange, TradingPair)>,
+        quotes: &mut MarketQuotes,
+    ) {
+        let mut pending = HashSet::new();
+        for market in markets {
+            if !quotes.contains_key(&market) {
+                pending.insert(market);
+            }
+        }
+
+        let fetched: Vec<_> = stream::iter(pending)
+            .map(|(ex, pair)| async move {
+                let quote = self.market_quote(ex, &pair).await;
+                ((ex, pair), quote)
+            })
+            .buffer_unordered(self.batch.concurrency)
+            .collect()
+            .await;
+
+        // Markets without a quote are kept so they are not requested again
+        for (market, quote) in fetched {
+            quotes.insert(
+                market,
+                quote.unwrap_or(MarketQuote {
+                    prices: Vec::new(),
+                    fetched: false,
+                }),
+            );
+        }
+    }
+
+    /// Get a single price for a coin aggregated across all exchanges.
+    ///
+    /// If no exchange prices the pair directly, the price is converted from
+    /// another fiat currency, or derived through intermediate markets, and the
+    /// conversion or route is reported.
+    pub async fn get_aggregated_price(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        config: &AggregationConfig,
+    ) -> Result<AggregatedPrice> {
+        self.aggregated_price(coin_id, quote_currency, config, None)
+            .await
+    }
+
+    // Get an aggregated price, taking exchange prices from prefetched market
+    // quotes if given
+    async fn aggregated_price(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        config: &AggregationConfig,
+        quotes: Option<&MarketQuotes>,
+    ) -> Result<AggregatedPrice> {
+        let pair = self.get_coin(coin_id)?.pair(&quote_currency.to_uppercase());
+
+        let prices = match self
+            .current_price(coin_id, quote_currency, None, quotes)
+            .await
+        {
+            Ok(prices) => prices,
+            Err(e) => {
+                // Fiat currencies are converted at the reference rate before
+                // trying routes through crypto markets
+                if self.fx_rates.supports(&pair.quote) {
+                    match self
+                        .converted_price(coin_id, quote_currency, None, config, quotes)
+                        .await
+                    {
+                        Ok(converted) => {
+                            let rate = converted.conversion.rate;
+                            return Ok(AggregatedPrice {
+                                pair,
+                                price: converted.price,
+                                method: config.method,
+                                volume_24h: converted
+                                    .source_price
+                                    .volume_24h
+                                    .and_then(|v| v.checked_mul(rate)),
+                                sources: Vec::new(),
+                                excluded: Vec::new(),
+                                timestamp: converted.timestamp,
+                                route: None,
+                                conversion: Some(converted.conversion),
+                            });
+                        }
+                        Err(fx_err) => debug!(
+                            "No FX conversion for {}/{}: {}",
+                            pair.base, pair.quote, fx_err
+                        ),
                     }
                 }
+
+                let routed = self
+                    .get_routed_price(coin_id, quote_currency)
+                    .await
+                    .map_err(|route_err| {
+                        debug!("No route for {}/{}: {}", pair.base, pair.quote, route_err);
+                        e
+                    })?;
+
+                return Ok(AggregatedPrice {
+                    pair,
+                    price: routed.price,
+                    method: config.method,
+                    volume_24h: None,
+                    sources: Vec::new(),
+                    excluded: Vec::new(),
+                    timestamp: routed.timestamp,
+                    route: Some(routed.route),
+                    conversion: None,
+                });
             }
         };
 
-        // Store the history for future queries
-        let _ = self.store.store_price_history(&history).await;
+        let aggregated = aggregate::aggregate(&pair, &prices, config)?;
 
-        Ok(history)
+        for excluded in &aggregated.excluded {
+            info!(
+                "Excluded {} price {} for {}/{} from aggregate: {:?}",
+                excluded.exchange, excluded.price, pair.base, pair.quote, excluded.reason
+            );
+        }
+
+        Ok(aggregated)
+    }
+
+    /// Derive the price of a coin through intermediate markets, choosing the
+    /// route by liquidity and hop count
+    pub async fn get_routed_price(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+    ) -> Result<RoutedPrice> {
+        let coin = self.get_coin(coin_id)?;
+        let pair = coin.pair(&quote_currency.to_uppercase());
+
+        let markets = self.list_markets(&MarketFilter::default()).await?;
+        let graph = RateGraph::new(&markets);
+        let starts: Vec<(Exchange, String)> = self
+            .connectors(None)
+            .into_iter()
+            .map(|(ex, _)| (ex, coin.symbol_on(ex).to_string()))
+            .collect();
+
+        let candidates = graph.routes(&starts, &pair.quote, self.routing.max_hops);
+        if candidates.is_empty() {
+            return Err(Error::NotFound(format!(
+                "No route from {} to {} within {} hops",
+                pair.base, pair.quote, self.routing.max_hops
+            )));
+        }
+
+        // Price the most promising routes, fetching each market at most once
+        let mut prices: HashMap<(Exchange, TradingPair), CurrentPrice> = HashMap::new();
+        let mut failed: HashSet<(Exchange, TradingPair)> = HashSet::new();
+        let mut best: Option<RoutedPrice> = None;
+
+        for legs in candidates.iter().take(self.routing.max_candidates) {
+            for leg in legs {
+                let key = (leg.exchange, leg.pair.clone());
+                if prices.contains_key(&key) || failed.contains(&key) {
+                    continue;
+                }
+                let Some((_, connector)) = self.connectors(Some(leg.exchange)).into_iter().next()
+                else {
+                    continue;
+                };
+                match connector.get_current_price(&leg.pair).await {
+                    Ok(price) => {
+                        prices.insert(key, price);
+                    }
+                    Err(e) => {
+                        debug!(
+                            "Failed to price route leg {} {}/{}: {}",
+                            leg.exchange, leg.pair.base, leg.pair.quote, e
+                        );
+                        failed.insert(key);
+                    }
+                }
+            }
+
+            let Some((price, route, timestamp)) = routing::price_route(legs, &prices) else {
+                continue;
+            };
+            let better = best.as_ref().is_none_or(|b| {
+                routing::score(&route, self.routing.hop_penalty)
+                    > routing::score(&b.route, self.routing.hop_penalty)
+            });
+            if better {
+                best = Some(RoutedPrice {
+                    pair: pair.clone(),
+                    price,
+                    route,
+                    timestamp,
+                });
+            }
+        }
+
+        let routed = best.ok_or_else(|| {
+            Error::ExchangeError(format!(
+                "Failed to price any route from {} to {}",
+                pair.base, pair.quote
+            ))
+        })?;
+
+        debug!(
+            "Routed {}/{} through {}",
+            pair.base,
+            pair.quote,
+            routed
+                .route
+                .legs
+                .iter()
+                .map(|leg| format!("{} {}/{}", leg.exchange, leg.pair.base, leg.pair.quote))
+                .collect::<Vec<_>>()
+                .join(" -> ")
+        );
+
+        Ok(routed)
     }
-} 
\ No newline at end of file
+
+    /// Derive the price history of a coin through the route currently chosen
+    /// for its price
+    pub async fn get_routed_history(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        timeframe: Timeframe,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<RoutedPriceHistory> {
+        let routed = self.get_routed_price(coin_id, quote_currency).await?;
+
+        let mut histories = Vec::with_capacity(routed.route.legs.len());
+        for leg in &routed.route.legs {
+            let history = self
+                .fetch_market_history(
+                    leg.exchange,
+                    &leg.pair,
+                    timeframe,
+                    start_time,
+                    end_time,
+                    limit,
+                )
+                .await?;
+            histories.push(history);
+        }
+
+        let mut data = routing::combine_histories(&routed.route, &histories, timeframe);
+        if let Some(limit) = limit {
+            data.truncate(limit);
+        }
+
+        Ok(RoutedPriceHistory {
+            pair: routed.pair,
+            interval: timeframe,
+            route: routed.route,
+            data,
+        })
+    }
+
+    // Get the history of a single market from its exchange, resampling
+    // intervals the exchange doesn't serve
+    async fn fetch_market_history(
+        &self,
+        exchange: Exchange,
+        pair: &TradingPair,
+        timeframe: Timeframe,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PriceHistory> {
+        let Some((_, connector)) = self.connectors(Some(exchange)).into_iter().next() else {
+            return Err(Error::NotFound(format!(
+                "Exchange {} is not available",
+                exchange
+            )));
+        };
+
+        if let Some(interval) = timeframe.native() {
+            return connector
+                .get_price_history(pair, interval, start_time, end_time, limit)
+                .await;
+        }
+
+        let source_interval = timeframe.source_interval();
+        let end = end_time.unwrap_or_else(Utc::now);
+        let start = resampled_start(timeframe, start_time, end, limit)?;
+
+        let mut pages = Vec::new();
+        for (page_start, page_end) in source_pages(source_interval, start, end)? {
+            let page = connector
+                .get_price_history(
+                    pair,
+                    source_interval,
+                    Some(page_start),
+                    Some(page_end),
+                    None,
+                )
+                .await?;
+            let exhausted = page.data.is_empty();
+            pages.push(page);
+            if exhausted {
+                break;
+            }
+        }
+        let source = join_pages(pages).ok_or_else(|| {
+            Error::ExchangeError(format!(
+                "No {} price history for {}/{}",
+                exchange, pair.base, pair.quote
+            ))
+        })?;
+
+        let mut history = resample::resample(&source, timeframe);
+        if let Some(limit) = limit {
+            history.data.truncate(limit);
+        }
+
+        Ok(history)
+    }
+
+    /// Load stored fiat exchange rates on first use, then fetch the days
+    /// published since the latest known one
+    pub async fn refresh_fx_rates(&self) -> Result<()> {
+        let Some(connector) = &self.fx else {
+            return Ok(());
+        };
+        let today = Utc::now().date_naive();
+
+        if self.fx_rates.latest_date().is_none() {
+            let start = today - Duration::days(self.fx_config.history_days);
+            match self.store.get_fx_rates(start).await {
+                Ok(stored) => {
+                    info!("Loaded {} days of stored FX rates", stored.len());
+                    self.fx_rates.insert(stored);
+                }
+                Err(e) => error!("Failed to load stored FX rates: {}", e),
+            }
+        }
+
+        // Rates are published on working days, so a gap of a weekend is caught
+        // up with the latest rates alone
+        let latest = self.fx_rates.latest_date();
+        let fetched = match latest {
+            Some(latest) if latest >= today => return Ok(()),
+            Some(latest) if latest + Duration::days(3) >= today => {
+                vec![connector.get_latest_rates().await?]
+            }
+            Some(latest) => {
+                connector
+                    .get_rate_history(latest + Duration::days(1))
+                    .await?
+            }
+            None => {
+                connector
+                    .get_rate_history(today - Duration::days(self.fx_config.history_days))
+                    .await?
+            }
+        };
+
+        let new_days: Vec<FxRates> = fetched
+            .into_iter()
+            .filter(|day| latest.is_none_or(|latest| day.date > latest))
+            .collect();
+        if new_days.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "Fetched {} new days of FX rates from {}",
+            new_days.len(),
+            connector.source()
+        );
+        if let Err(e) = self.store.store_fx_rates(&new_days).await {
+            error!("Failed to persist FX rates: {}", e);
+        }
+        self.fx_rates.insert(new_days);
+
+        Ok(())
+    }
+
+    /// Get the fiat exchange rates of a day, or the latest day before it with
+    /// rates, quoted against `base`
+    pub fn get_fx_rates(&self, base: &str, date: Option<NaiveDate>) -> Result<FxRates> {
+        if !self.fx_rates.supports(base) {
+            return Err(Error::NotFound(format!(
+                "No FX rates for {}",
+                base.to_uppercase()
+            )));
+        }
+
+        let date = date.unwrap_or_else(|| Utc::now().date_naive());
+        self.fx_rates
+            .rates_on(base, date)
+            .ok_or_else(|| Error::NotFound(format!("No FX rates on or before {}", date)))
+    }
+
+    // Fiat currencies to convert prices from: the requested one, or the
+    // configured ones other than the target
+    fn fx_sources(&self, target: &str, via: Option<&str>) -> Vec<String> {
+        match via {
+            Some(via) => vec![via.to_uppercase()],
+            None => self
+                .fx_config
+                .source_currencies
+                .iter()
+                .filter(|currency| *currency != target)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Get a coin's price in a fiat currency by converting its aggregated price
+    /// in another fiat currency, `via` or the first configured one it trades in
+    pub async fn get_converted_price(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        via: Option<&str>,
+        config: &AggregationConfig,
+    ) -> Result<ConvertedPrice> {
+        self.converted_price(coin_id, quote_currency, via, config, None)
+            .await
+    }
+
+    // Currencies a price in `target` can be converted from today, with their
+    // conversions
+    fn fx_conversions(&self, target: &str, via: Option<&str>) -> Vec<(String, FxConversion)> {
+        let today = Utc::now().date_naive();
+        self.fx_sources(target, via)
+            .into_iter()
+            .filter_map(|source| {
+                let conversion = self.fx_rates.conversion(
+                    &source,
+                    target,
+                    today,
+                    self.fx_config.max_age_days,
+                )?;
+                Some((source, conversion))
+            })
+            .collect()
+    }
+
+    // Get a converted price, taking exchange prices from prefetched market
+    // quotes if given
+    async fn converted_price(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        via: Option<&str>,
+        config: &AggregationConfig,
+        quotes: Option<&MarketQuotes>,
+    ) -> Result<ConvertedPrice> {
+        let coin = self.get_coin(coin_id)?;
+        let target = quote_currency.to_uppercase();
+        let mut last_error = None;
+
+        for (source, conversion) in self.fx_conversions(&target, via) {
+            let source_pair = coin.pair(&source);
+            let source_price = match self.current_price(coin_id, &source, None, quotes).await {
+                Ok(prices) => aggregate::aggregate(&source_pair, &prices, config),
+                Err(e) => Err(e),
+            };
+
+            match source_price {
+                Ok(source_price) => match source_price.price.checked_mul(conversion.rate) {
+                    Some(price) => {
+                        return Ok(ConvertedPrice {
+                            pair: coin.pair(&target),
+                            price,
+                            timestamp: source_price.timestamp,
+                            source_price,
+                            conversion,
+                        });
+                    }
+                    None => {
+                        last_error = Some(Error::ExchangeError(format!(
+                            "{} price of {} converted into {} is out of range",
+                            source, coin_id, target
+                        )));
+                    }
+                },
+                Err(e) => {
+                    debug!("No {} price of {} to convert: {}", source, coin_id, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            Error::NotFound(format!(
+                "No FX rate to convert {} prices into {}",
+                coin.symbol, target
+            ))
+        }))
+    }
+
+    /// Get a coin's price history in a fiat currency by converting its
+    /// history in another fiat currency, each bar at the rate of its day
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_converted_history(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        via: Option<&str>,
+        timeframe: Timeframe,
+        exchange: Option<Exchange>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<ConvertedPriceHistory> {
+        let target = quote_currency.to_uppercase();
+        if !self.fx_rates.supports(&target) {
+            return Err(Error::NotFound(format!("No FX rates for {}", target)));
+        }
+        let mut last_error = None;
+
+        for source in self.fx_sources(&target, via) {
+            if !self.fx_rates.supports(&source) {
+                continue;
+            }
+
+            let history = match self
+                .get_price_history(
+                    coin_id, &source, timeframe, exchange, start_time, end_time, limit,
+                )
+                .await
+            {
+                Ok(history) => history,
+                Err(e) => {
+                    debug!("No {} history of {} to convert: {}", source, coin_id, e);
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            let mut data: Vec<ConvertedPricePoint> = history
+                .data
+                .into_iter()
+                .filter_map(|point| {
+                    let conversion = self.fx_rates.conversion(
+                        &source,
+                        &target,
+                        point.timestamp.date_naive(),
+                        self.fx_config.max_age_days,
+                    )?;
+                    let rate = conversion.rate;
+                    let convert = |price: Decimal| price.checked_mul(rate);
+                    Some(ConvertedPricePoint {
+                        point: PriceHistoryPoint {
+                            price: convert(point.price)?,
+                            open: point.open.and_then(convert),
+                            high: point.high.and_then(convert),
+                            low: point.low.and_then(convert),
+                            ..point
+                        },
+                        rate,
+                        rate_timestamp: conversion.timestamp,
+                    })
+                })
+                .collect();
+            data.sort_by_key(|p| std::cmp::Reverse(p.point.timestamp));
+
+            return Ok(ConvertedPriceHistory {
+                exchange: history.exchange,
+                pair: TradingPair {
+                    base: history.pair.base.clone(),
+                    quote: target,
+                },
+                source_pair: history.pair,
+                source: self
+                    .fx
+                    .as_ref()
+                    .map_or("unknown", |fx| fx.source())
+                    .to_string(),
+                interval: history.interval,
+                data,
+            });
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            Error::NotFound(format!("No FX rate to convert prices into {}", target))
+        }))
+    }
+
+    // Derivatives connector, if one is configured
+    fn derivatives(&self) -> Result<&Arc<dyn DerivativesConnector>> {
+        self.derivatives
+            .as_ref()
+            .ok_or_else(|| Error::ConfigError("No derivatives exchange is configured".to_string()))
+    }
+
+    // Perpetual contract of a coin, which is listed like the Binance spot market
+    fn perpetual_pair(&self, coin_id: &str, quote_currency: &str) -> Result<TradingPair> {
+        let coin = self.get_coin(coin_id)?;
+        Ok(coin.pair_on(Exchange::Binance, &quote_currency.to_uppercase()))
+    }
+
+    // Native interval of a timeframe; derivatives history is not resampled
+    fn perpetual_interval(timeframe: Timeframe) -> Result<PriceInterval> {
+        timeframe.native().ok_or_else(|| {
+            Error::ParseError(format!(
+                "Unsupported interval for perpetual history: {}. Supported intervals: 1m, 5m, 15m, 1h, 4h, 1d, 1w",
+                timeframe
+            ))
+        })
+    }
+
+    /// Get the mark price, index price and open interest of a coin's perpetual
+    /// contract
+    pub async fn get_perpetual_market(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+    ) -> Result<PerpetualMarket> {
+        let connector = self.derivatives()?;
+        let pair = self.perpetual_pair(coin_id, quote_currency)?;
+
+        let (mark, mut open_interest) = tokio::try_join!(
+            connector.get_mark_price(&pair),
+            connector.get_open_interest(&pair)
+        )?;
+        open_interest.open_interest_value =
+            open_interest.open_interest.checked_mul(mark.mark_price);
+
+        let _ = self.store.store_mark_price(&mark).await;
+        let _ = self.store.store_open_interest(&open_interest).await;
+
+        Ok(PerpetualMarket {
+            mark,
+            open_interest,
+        })
+    }
+
+    /// Get the funding payments of a coin's perpetual contract, newest first
+    pub async fn get_funding_rates(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<FundingRateHistory> {
+        let connector = self.derivatives()?;
+        let pair = self.perpetual_pair(coin_id, quote_currency)?;
+
+        let history = connector
+            .get_funding_rates(&pair, start_time, end_time, limit)
+            .await?;
+        let _ = self.store.store_funding_rates(&history).await;
+
+        Ok(history)
+    }
+
+    /// Get price history of a coin's perpetual contract
+    pub async fn get_perpetual_history(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        timeframe: Timeframe,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PerpetualHistory> {
+        let connector = self.derivatives()?;
+        let pair = self.perpetual_pair(coin_id, quote_currency)?;
+        let interval = Self::perpetual_interval(timeframe)?;
+
+        let history = connector
+            .get_perpetual_history(&pair, interval, start_time, end_time, limit)
+            .await?;
+        let _ = self.store.store_perpetual_history(&history).await;
+
+        Ok(history)
+    }
+
+    /// Get the basis of a coin's perpetual contract: its mark price against
+    /// the Binance spot price
+    pub async fn get_basis(&self, coin_id: &str, quote_currency: &str) -> Result<Basis> {
+        let connector = self.derivatives()?;
+        let pair = self.perpetual_pair(coin_id, quote_currency)?;
+
+        let (mark, spot) = tokio::try_join!(
+            connector.get_mark_price(&pair),
+            self.binance.get_current_price(&pair)
+        )?;
+        if spot.price <= Decimal::ZERO {
+            return Err(Error::ExchangeError(format!(
+                "No spot price for {}/{}",
+                pair.base, pair.quote
+            )));
+        }
+
+        let (basis, ratio) = mark
+            .mark_price
+            .checked_sub(spot.price)
+            .and_then(|basis| Some((basis, basis.checked_div(spot.price)?)))
+            .ok_or_else(|| {
+                Error::ExchangeError(format!(
+                    "Basis of {}/{} is out of range",
+                    pair.base, pair.quote
+                ))
+            })?;
+
+        Ok(Basis {
+            exchange: mark.exchange,
+            spot_exchange: Exchange::Binance,
+            pair,
+            perp_price: mark.mark_price,
+            index_price: mark.index_price,
+            spot_price: spot.price,
+            basis,
+            basis_percent: to_f64(ratio) * 100.0,
+            funding_rate: mark.last_funding_rate,
+            timestamp: mark.timestamp.min(spot.timestamp),
+        })
+    }
+
+    /// Get the basis history of a coin's perpetual contract from closing
+    /// prices of the contract and the Binance spot market
+    pub async fn get_basis_history(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        timeframe: Timeframe,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<BasisHistory> {
+        let connector = self.derivatives()?;
+        let pair = self.perpetual_pair(coin_id, quote_currency)?;
+        let interval = Self::perpetual_interval(timeframe)?;
+
+        let (perp, spot) = tokio::try_join!(
+            connector.get_perpetual_history(&pair, interval, start_time, end_time, limit),
+            self.binance
+                .get_price_history(&pair, interval, start_time, end_time, limit)
+        )?;
+
+        let spot_closes: HashMap<DateTime<Utc>, Decimal> = spot
+            .data
+            .iter()
+            .map(|point| (point.timestamp, point.price))
+            .collect();
+
+        let data = perp
+            .data
+            .iter()
+            .filter_map(|point| {
+                let spot_price = *spot_closes.get(&point.timestamp)?;
+                if spot_price <= Decimal::ZERO {
+                    return None;
+                }
+                let basis = point.price.checked_sub(spot_price)?;
+                Some(BasisPoint {
+                    timestamp: point.timestamp,
+                    perp_price: point.price,
+                    spot_price,
+                    basis,
+                    basis_percent: to_f64(basis.checked_div(spot_price)?) * 100.0,
+                })
+            })
+            .collect();
+
+        Ok(BasisHistory {
+            exchange: perp.exchange,
+            spot_exchange: Exchange::Binance,
+            pair,
+            interval: timeframe,
+            data,
+        })
+    }
+
+    /// Get aggregated prices of several coins in several quote currencies.
+    ///
+    /// Each exchange market is fetched once, concurrently, however many cells
+    /// are priced from it; a failed lookup is reported in its cell instead of
+    /// failing the whole batch.
+    pub async fn get_batch_prices(
+        &self,
+        ids: &[String],
+        currencies: &[String],
+    ) -> Result<BatchPrices> {
+        let cells = ids.len() * currencies.len();
+        if cells == 0 {
+            return Err(Error::ParseError(
+                "At least one coin ID and one currency are required".to_string(),
+            ));
+        }
+        if cells > self.batch.max_cells {
+            return Err(Error::ParseError(format!(
+                "Batch of {} prices exceeds the limit of {}",
+                cells, self.batch.max_cells
+            )));
+        }
+
+        let cells: Vec<(String, String)> = ids
+            .iter()
+            .flat_map(|id| {
+                currencies
+                    .iter()
+                    .map(|currency| (id.clone(), currency.clone()))
+            })
+            .collect();
+        let exchanges: Vec<Exchange> = self
+            .connectors(None)
+            .into_iter()
+            .map(|(ex, _)| ex)
+            .collect();
+
+        // Fetch every market the cells quote directly, each once, then the
+        // markets fiat cells without a direct quote are converted from
+        let mut quotes = MarketQuotes::new();
+        let direct: Vec<_> = cells
+            .iter()
+            .filter_map(|(id, currency)| Some((self.get_coin(id).ok()?, currency)))
+            .flat_map(|(coin, currency)| {
+                exchanges
+                    .iter()
+                    .map(move |&ex| (ex, coin.pair_on(ex, &currency.to_uppercase())))
+            })
+            .collect();
+        self.fetch_market_quotes(direct, &mut quotes).await;
+
+        let mut sources = Vec::new();
+        for (id, currency) in &cells {
+            let Ok(coin) = self.get_coin(id) else {
+                continue;
+            };
+            let target = currency.to_uppercase();
+            let quoted = exchanges.iter().any(|&ex| {
+                quotes
+                    .get(&(ex, coin.pair_on(ex, &target)))
+                    .is_some_and(|quote| !quote.prices.is_empty())
+            });
+            if quoted || !self.fx_rates.supports(&target) {
+                continue;
+            }
+            for (source, _) in self.fx_conversions(&target, None) {
+                sources.extend(exchanges.iter().map(|&ex| (ex, coin.pair_on(ex, &source))));
+            }
+        }
+        self.fetch_market_quotes(sources, &mut quotes).await;
+
+        let quotes = &quotes;
+        let lookups: Vec<_> = cells
+            .into_iter()
+            .map(|(id, currency)| async move {
+                let cell = match self
+                    .aggregated_price(&id, &currency, &self.aggregation, Some(quotes))
+                    .await
+                {
+                    Ok(price) => BatchPriceCell::Price(Box::new(price)),
+                    Err(e) => BatchPriceCell::Error {
+                        error: e.to_string(),
+                    },
+                };
+                (id, currency, cell)
+            })
+            .collect();
+
+        let results: Vec<_> = stream::iter(lookups)
+            .buffer_unordered(self.batch.concurrency)
+            .collect()
+            .await;
+
+        let mut prices: BTreeMap<String, BTreeMap<String, BatchPriceCell>> = BTreeMap::new();
+        for (id, currency, cell) in results {
+            prices.entry(id).or_default().insert(currency, cell);
+        }
+
+        Ok(BatchPrices {
+            ids: ids.to_vec(),
+            currencies: currencies.to_vec(),
+            prices,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Get historical price data for a coin.
+    ///
+    /// Timeframes that are not native exchange intervals are resampled from the
+    /// coarsest native interval that fits into them.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_price_history(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        timeframe: Timeframe,
+        exchange: Option<Exchange>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PriceHistory> {
+        if let Some(interval) = timeframe.native() {
+            return self
+                .fetch_price_history(
+                    coin_id,
+                    quote_currency,
+                    interval,
+                    exchange,
+                    start_time,
+                    end_time,
+                    limit,
+                )
+                .await;
+        }
+
+        let source_interval = timeframe.source_interval();
+        let end = end_time.unwrap_or_else(Utc::now);
+        // Start at a bar boundary so the first bar is complete
+        let start = resampled_start(timeframe, start_time, end, limit)?;
+
+        debug!(
+            "Resampling {} history to {} for {}",
+            source_interval, timeframe, coin_id
+        );
+
+        // Exchanges cap the bars returned per request, so fetch the range in
+        // pages, newest first, all from the exchange that served the first
+        let mut source_exchange = exchange;
+        let mut pages = Vec::new();
+        for (page_start, page_end) in source_pages(source_interval, start, end)? {
+            let page = self
+                .fetch_price_history(
+                    coin_id,
+                    quote_currency,
+                    source_interval,
+                    source_exchange,
+                    Some(page_start),
+                    Some(page_end),
+                    None,
+                )
+                .await?;
+            source_exchange = Some(page.exchange);
+            let exhausted = page.data.is_empty();
+            pages.push(page);
+            if exhausted {
+                break;
+            }
+        }
+        let source = join_pages(pages)
+            .ok_or_else(|| Error::ExchangeError(format!("No price history for {}", coin_id)))?;
+
+        let mut history = resample::resample(&source, timeframe);
+        if let Some(limit) = limit {
+            history.data.truncate(limit);
+        }
+
+        Ok(history)
+    }
+
+    /// Compute a technical indicator over price history.
+    ///
+    /// Extra bars before the requested range are fetched to cover the indicator's
+    /// warm-up, so every returned value is fully formed.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_indicator(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        indicator: Indicator,
+        timeframe: Timeframe,
+        exchange: Option<Exchange>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<IndicatorSeries> {
+        let lookback = indicator.lookback();
+
+        // Widen the requested range by the warm-up bars, plus one to absorb a
+        // partially covered bar at the start
+        let (fetch_start, fetch_limit) = match start_time {
+            Some(start) => (
+                Some(timeframe.bars_before(start, lookback.saturating_add(1))?),
+                None,
+            ),
+            None => {
+                let points = limit.unwrap_or(DEFAULT_INDICATOR_POINTS);
+                let bars = points.checked_add(lookback).ok_or_else(|| {
+                    Error::InvalidRequest(format!("Too many bars for {}", indicator))
+                })?;
+                (None, Some(bars))
+            }
+        };
+
+        debug!(
+            "Computing {} over {} history for {} with {} warm-up bars",
+            indicator, timeframe, coin_id, lookback
+        );
+
+        let history = self
+            .get_price_history(
+                coin_id,
+                quote_currency,
+                timeframe,
+                exchange,
+                fetch_start,
+                end_time,
+                fetch_limit,
+            )
+            .await?;
+
+        // Indicators run over chronological closes
+        let mut bars = history.data;
+        bars.reverse();
+        let closes: Vec<f64> = bars.iter().map(|bar| to_f64(bar.price)).collect();
+
+        let mut data: Vec<IndicatorPoint> = bars
+            .iter()
+            .zip(indicator.compute(&closes))
+            .filter_map(|(bar, values)| {
+                Some(IndicatorPoint {
+                    timestamp: bar.timestamp,
+                    values: values?,
+                })
+            })
+            .filter(|point| start_time.is_none_or(|start| point.timestamp >= start))
+            .collect();
+
+        data.reverse();
+        data.truncate(limit.unwrap_or(DEFAULT_INDICATOR_POINTS));
+
+        Ok(IndicatorSeries {
+            exchange: history.exchange,
+            pair: history.pair,
+            interval: history.interval,
+            indicator: indicator.to_string(),
+            data,
+        })
+    }
+
+    // Get native-interval price history from the store or the exchanges
+    #[allow(clippy::too_many_arguments)]
+    async fn fetch_price_history(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        interval: PriceInterval,
+        exchange: Option<Exchange>,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PriceHistory> {
+        let coin = self.get_coin(coin_id)?;
+
+        let pair = coin.pair(&quote_currency.to_uppercase());
+
+        debug!(
+            "Getting price history for {} ({}/{}) with interval {:?}",
+            coin_id, pair.base, pair.quote, interval
+        );
+
+        // Try to get history from store first, under each exchange's own symbol
+        for (ex, _) in self.connectors(exchange) {
+            let query = PriceQuery {
+                pair: coin.pair_on(ex, &pair.quote),
+                exchange: Some(ex),
+                interval,
+                start_time,
+                end_time,
+                limit,
+            };
+
+            // A partially cached range is refetched rather than truncated
+            match self.store.get_price_history(&query).await {
+                Ok(history) if covers(&history, interval, start_time, end_time, limit) => {
+                    debug!(
+                        "Retrieved history from store: {} points",
+                        history.data.len()
+                    );
+                    return Ok(history);
+                }
+                _ => {}
+            }
+        }
+        debug!("History not fully stored, fetching from exchanges");
+
+        // Fetch history from the exchange(s), falling back to the next one on failure
+        let mut last_error = None;
+        let mut fetched = None;
+
+        for (ex, connector) in self.connectors(exchange) {
+            match connector
+                .get_price_history(
+                    &coin.pair_on(ex, &pair.quote),
+                    interval,
+                    start_time,
+                    end_time,
+                    limit,
+                )
+                .await
+            {
+                Ok(history) => {
+                    fetched = Some(history);
+                    break;
+                }
+                Err(e) => {
+                    error!("Failed to get {} price history: {}", ex, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        let history = match (fetched, last_error) {
+            (Some(history), _) => history,
+            (None, Some(e)) => return Err(e),
+            (None, None) => {
+                return Err(Error::ExchangeError(format!(
+                    "Failed to get price history for {}/{}",
+                    pair.base, pair.quote
+                )))
+            }
+        };
+
+        // Store the history for future queries
+        let _ = self.store.store_price_history(&history).await;
+
+        Ok(history)
+    }
+
+    /// Get the most recent trades for a coin, newest first
+    pub async fn get_recent_trades(
+        &self,
+        coin_id: &str,
+        quote_currency: &str,
+        exchange: Option<Exchange>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Trade>> {
+        let coin = self.get_coin(coin_id)?;
+
+        let pair = coin.pair(&quote_currency.to_uppercase());
+
+        debug!(
+            "Getting recent trades for {} ({}/{})",
+            coin_id, pair.base, pair.quote
+        );
+
+        // Trades are always fetched live; the store only keeps them for auditing
+        let mut trades = Vec::new();
+
+        for (ex, connector) in self.connectors(exchange) {
+            match connector
+                .get_recent_trades(&coin.pair_on(ex, &pair.quote), limit)
+                .await
+            {
+                Ok(exchange_trades) => {
+                    if let Err(e) = self.store.store_trades(&exchange_trades).await {
+                        error!("Failed to store {} trades: {}", ex, e);
+                    }
+                    trades.extend(exchange_trades);
+                }
+                Err(e) => {
+                    error!("Failed to get {} trades: {}", ex, e);
+                }
+            }
+        }
+
+        if trades.is_empty() {
+            return Err(Error::ExchangeError(format!(
+                "Failed to get recent trades for {}/{}",
+                pair.base, pair.quote
+            )));
+        }
+
+        trades.sort_by_key(|trade| std::cmp::Reverse(trade.timestamp));
+
+        if let Some(limit) = limit {
+            trades.truncate(limit);
+        }
+
+        Ok(trades)
+    }
+
+    /// Reload market metadata from all exchanges.
+    ///
+    /// Exchanges that fail to respond keep their previously cached markets.
+    pub async fn refresh_markets(&self) -> Result<()> {
+        let mut failures = Vec::new();
+
+        for (ex, connector) in self.connectors(None) {
+            match connector.list_markets().await {
+                Ok(markets) => {
+                    info!("Loaded {} markets from {}", markets.len(), ex);
+                    self.markets.update(ex, markets).await;
+                }
+                // Some exchanges added through configuration cannot list their markets
+                Err(Error::NotFound(e)) => debug!("Not refreshing {} markets: {}", ex, e),
+                Err(e) => {
+                    error!("Failed to refresh {} markets: {}", ex, e);
+                    failures.push(ex.to_string());
+                }
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(Error::ExchangeError(format!(
+                "Failed to refresh markets for: {}",
+                failures.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Sample the server time of every exchange to measure its clock skew
+    pub async fn sync_clocks(&self) -> Result<()> {
+        let mut failures = Vec::new();
+
+        for (ex, connector) in self.connectors(None) {
+            let sent = Utc::now();
+            match connector.get_server_time().await {
+                Ok(server_time) => {
+                    let skew = clock::measure(ex, sent, server_time, Utc::now());
+                    let _ = self.store.store_clock_skew(&skew).await;
+                    self.clocks.record(skew);
+                }
+                Err(Error::NotFound(e)) => debug!("Not syncing {} clock: {}", ex, e),
+                Err(e) => {
+                    error!("Failed to get {} server time: {}", ex, e);
+                    failures.push(ex.to_string());
+                }
+            }
+        }
+
+        if !failures.is_empty() {
+            return Err(Error::ExchangeError(format!(
+                "Failed to sync clocks for: {}",
+                failures.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Latest measured clock skew of each exchange
+    pub fn get_clock_skews(&self) -> Vec<ClockSkew> {
+        self.clocks.list()
+    }
+
+    /// Circuit breaker state and recent latency of each exchange connector
+    pub fn get_exchange_status(&self) -> Vec<ExchangeStatus> {
+        self.connectors(None)
+            .into_iter()
+            .filter_map(|(exchange, connector)| {
+                connector
+                    .health()
+                    .map(|health| ExchangeStatus { exchange, health })
+            })
+            .collect()
+    }
+
+    /// List cached markets, loading them on first use
+    pub async fn list_markets(&self, filter: &MarketFilter) -> Result<Vec<MarketInfo>> {
+        if self.markets.is_empty().await {
+            debug!("Market cache is empty, loading markets from exchanges");
+            if let Err(e) = self.refresh_markets().await {
+                if self.markets.is_empty().await {
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(self.markets.list(filter).await)
+    }
+
+    /// Load persisted alert rules
+    pub async fn load_alerts(&self) -> Result<()> {
+        let rules = self.store.get_alert_rules().await?;
+        info!("Loaded {} alert rules", rules.len());
+        self.alerts.replace(rules);
+        Ok(())
+    }
+
+    /// Fetch fresh prices of every coin and currency watched by an enabled
+    /// alert rule, so rules are evaluated even when nobody queries the coin
+    pub async fn evaluate_alerts(&self) -> Result<()> {
+        let watched: BTreeSet<(String, String)> = self
+            .alerts
+            .list()
+            .into_iter()
+            .filter(|rule| rule.spec.enabled)
+            .map(|rule| (rule.spec.coin_id, rule.spec.currency))
+            .collect();
+
+        let mut failed = 0;
+        for (coin_id, currency) in &watched {
+            if let Err(e) = self.ingest_current_price(coin_id, currency, None).await {
+                debug!(
+                    "No prices to evaluate alerts of {}/{}: {}",
+                    coin_id, currency, e
+                );
+                failed += 1;
+            }
+        }
+
+        if failed > 0 {
+            return Err(Error::ExchangeError(format!(
+                "Failed to get prices of {} of {} watched coins",
+                failed,
+                watched.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// List all alert rules
+    pub fn list_alerts(&self) -> Vec<AlertRule> {
+        self.alerts.list()
+    }
+
+    /// Get an alert rule by ID
+    pub fn get_alert(&self, id: &str) -> Result<AlertRule> {
+        self.alerts
+            .get(id)
+            .ok_or_else(|| Error::NotFound(format!("Alert with ID '{}' not found", id)))
+    }
+
+    /// Register a new alert rule
+    pub async fn create_alert(&self, spec: AlertRuleSpec) -> Result<AlertRule> {
+        let spec = self.validate_alert(spec)?;
+        let now = Utc::now();
+        let rule = AlertRule {
+            id: uuid::Uuid::new_v4().to_string(),
+            spec,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.store.store_alert_rule(&rule).await?;
+        self.alerts.upsert(rule.clone());
+
+        info!("Created alert {} for {}", rule.id, rule.spec.coin_id);
+        Ok(rule)
+    }
+
+    /// Replace the definition of an alert rule
+    pub async fn update_alert(&self, id: &str, spec: AlertRuleSpec) -> Result<AlertRule> {
+        let existing = self.get_alert(id)?;
+        let rule = AlertRule {
+            spec: self.validate_alert(spec)?,
+            updated_at: Utc::now(),
+            ..existing
+        };
+
+        self.store.store_alert_rule(&rule).await?;
+        self.alerts.upsert(rule.clone());
+
+        info!("Updated alert {}", id);
+        Ok(rule)
+    }
+
+    /// Delete an alert rule
+    pub async fn delete_alert(&self, id: &str) -> Result<()> {
+        self.get_alert(id)?;
+
+        self.store.delete_alert_rule(id).await?;
+        self.alerts.remove(id);
+
+        info!("Deleted alert {}", id);
+        Ok(())
+    }
+
+    // Check an alert rule definition, normalizing its currency
+    fn validate_alert(&self, mut spec: AlertRuleSpec) -> Result<AlertRuleSpec> {
+        self.get_coin(&spec.coin_id)?;
+        spec.currency = spec.currency.to_uppercase();
+
+        let url = reqwest::Url::parse(&spec.webhook_url)
+            .map_err(|e| Error::ParseError(format!("Invalid webhook URL: {}", e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(Error::ParseError(
+                "Webhook URL must use http or https".to_string(),
+            ));
+        }
+
+        let valid = match spec.condition {
+            AlertCondition::PriceAbove { threshold } | AlertCondition::PriceBelow { threshold } => {
+                threshold > 0.0
+            }
+            AlertCondition::PercentChange {
+                percent,
+                window_secs,
+            } => percent != 0.0 && window_secs > 0,
+            AlertCondition::Spread {
+                exchanges: [a, b],
+                threshold_percent,
+            } => a != b && threshold_percent > 0.0,
+        };
+        if !valid {
+            return Err(Error::ParseError(format!(
+                "Invalid alert condition: {:?}",
+                spec.condition
+            )));
+        }
+
+        Ok(spec)
+    }
+
+    /// Load persisted portfolios
+    pub async fn load_portfolios(&self) -> Result<()> {
+        let portfolios = self.store.get_portfolios().await?;
+        info!("Loaded {} portfolios", portfolios.len());
+        self.portfolios.replace(portfolios);
+        Ok(())
+    }
+
+    /// List all portfolios
+    pub fn list_portfolios(&self) -> Vec<Portfolio> {
+        self.portfolios.list()
+    }
+
+    /// Get a portfolio by ID
+    pub fn get_portfolio(&self, id: &str) -> Result<Portfolio> {
+        self.portfolios
+            .get(id)
+            .ok_or_else(|| Error::NotFound(format!("Portfolio with ID '{}' not found", id)))
+    }
+
+    /// Create a portfolio
+    pub async fn create_portfolio(&self, spec: PortfolioSpec) -> Result<Portfolio> {
+        let spec = self.validate_portfolio(spec)?;
+        let now = Utc::now();
+        let portfolio = Portfolio {
+            id: uuid::Uuid::new_v4().to_string(),
+            spec,
+            created_at: now,
+            updated_at: now,
+        };
+
+        self.store.store_portfolio(&portfolio).await?;
+        self.portfolios.upsert(portfolio.clone());
+
+        info!("Created portfolio {}", portfolio.id);
+        Ok(portfolio)
+    }
+
+    /// Replace the name, currency and holdings of a portfolio
+    pub async fn update_portfolio(&self, id: &str, spec: PortfolioSpec) -> Result<Portfolio> {
+        let existing = self.get_portfolio(id)?;
+        let portfolio = Portfolio {
+            spec: self.validate_portfolio(spec)?,
+            updated_at: Utc::now(),
+            ..existing
+        };
+
+        self.store.store_portfolio(&portfolio).await?;
+        self.portfolios.upsert(portfolio.clone());
+
+        info!("Updated portfolio {}", id);
+        Ok(portfolio)
+    }
+
+    /// Delete a portfolio
+    pub async fn delete_portfolio(&self, id: &str) -> Result<()> {
+        self.get_portfolio(id)?;
+
+        self.store.delete_portfolio(id).await?;
+        self.portfolios.remove(id);
+
+        info!("Deleted portfolio {}", id);
+        Ok(())
+    }
+
+    // Check a portfolio definition, deriving holdings from their transactions
+    fn validate_portfolio(&self, mut spec: PortfolioSpec) -> Result<PortfolioSpec> {
+        spec.currency = spec.currency.to_uppercase();
+        for holding in &spec.holdings {
+            self.get_coin(&holding.coin_id)?;
+        }
+        portfolio::normalize_holdings(&mut spec.holdings)?;
+        Ok(spec)
+    }
+
+    /// Value a portfolio at aggregated prices, in the portfolio currency unless
+    /// another is given
+    pub async fn get_portfolio_valuation(
+        &self,
+        id: &str,
+        currency: Option<&str>,
+    ) -> Result<PortfolioValuation> {
+        let portfolio = self.get_portfolio(id)?;
+        let currency = currency
+            .map(str::to_uppercase)
+            .unwrap_or_else(|| portfolio.spec.currency.clone());
+        // Cost bases are only meaningful in the currency they were given in
+        let with_pnl = currency == portfolio.spec.currency;
+
+        let mut holdings = Vec::with_capacity(portfolio.spec.holdings.len());
+        for holding in &portfolio.spec.holdings {
+            let position = portfolio::position(holding)?;
+            let price = self
+                .get_aggregated_price(&holding.coin_id, &currency, &self.aggregation)
+                .await?
+                .price;
+            let value = position.quantity.checked_mul(price).ok_or_else(|| {
+                Error::InvalidRequest(format!(
+                    "Value of {} in portfolio {} is out of range",
+                    holding.coin_id, portfolio.id
+                ))
+            })?;
+            let cost_basis = position.cost_basis.filter(|_| with_pnl);
+            let unrealized_pnl = cost_basis.map(|cost| value - cost);
+
+            holdings.push(HoldingValuation {
+                coin_id: holding.coin_id.clone(),
+                quantity: position.quantity,
+                price,
+                value,
+                weight_percent: 0.0,
+                cost_basis,
+                unrealized_pnl,
+                unrealized_pnl_percent: percent_of(unrealized_pnl, cost_basis),
+                realized_pnl: Some(position.realized_pnl).filter(|_| with_pnl),
+            });
+        }
+
+        let out_of_range = || {
+            Error::InvalidRequest(format!(
+                "Value of portfolio {} is out of range",
+                portfolio.id
+            ))
+        };
+
+        let value = checked_sum(holdings.iter().map(|h| h.value)).ok_or_else(out_of_range)?;
+        for holding in &mut holdings {
+            holding.weight_percent = percent_of(Some(holding.value), Some(value)).unwrap_or(0.0);
+        }
+
+        let costed: Vec<&HoldingValuation> =
+            holdings.iter().filter(|h| h.cost_basis.is_some()).collect();
+        let (cost_basis, unrealized_pnl) = if costed.is_empty() {
+            (None, None)
+        } else {
+            let cost =
+                checked_sum(costed.iter().filter_map(|h| h.cost_basis)).ok_or_else(out_of_range)?;
+            let costed_value =
+                checked_sum(costed.iter().map(|h| h.value)).ok_or_else(out_of_range)?;
+            (Some(cost), costed_value.checked_sub(cost))
+        };
+        let realized_pnl = if with_pnl {
+            Some(
+                checked_sum(holdings.iter().filter_map(|h| h.realized_pnl))
+                    .ok_or_else(out_of_range)?,
+            )
+        } else {
+            None
+        };
+
+        Ok(PortfolioValuation {
+            portfolio_id: portfolio.id,
+            currency,
+            value,
+            cost_basis,
+            unrealized_pnl,
+            unrealized_pnl_percent: percent_of(unrealized_pnl, cost_basis),
+            realized_pnl,
+            holdings,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Get the historical value of a portfolio from the price history of its holdings
+    pub async fn get_portfolio_history(
+        &self,
+        id: &str,
+        currency: Option<&str>,
+        timeframe: Timeframe,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PortfolioValueHistory> {
+        let portfolio = self.get_portfolio(id)?;
+        let currency = currency
+            .map(str::to_uppercase)
+            .unwrap_or_else(|| portfolio.spec.currency.clone());
+
+        let mut histories = Vec::with_capacity(portfolio.spec.holdings.len());
+        for holding in &portfolio.spec.holdings {
+            let history = self
+                .get_price_history(
+                    &holding.coin_id,
+                    &currency,
+                    timeframe,
+                    None,
+                    start_time,
+                    end_time,
+                    limit,
+                )
+                .await?;
+            histories.push(history);
+        }
+
+        let mut data = portfolio::value_series(&portfolio.spec.holdings, &histories, timeframe);
+        if let Some(limit) = limit {
+            data.truncate(limit);
+        }
+
+        Ok(PortfolioValueHistory {
+            portfolio_id: portfolio.id,
+            currency,
+            interval: timeframe,
+            data,
+        })
+    }
+
+    /// Load persisted exchange accounts
+    pub async fn load_accounts(&self) -> Result<()> {
+        let accounts = self.store.get_accounts().await?;
+        info!("Loaded {} exchange accounts", accounts.len());
+        self.accounts.replace(accounts);
+        Ok(())
+    }
+
+    /// List connected exchange accounts
+    pub fn list_accounts(&self) -> Vec<ExchangeAccount> {
+        self.accounts
+            .list()
+            .into_iter()
+            .map(|stored| stored.account)
+            .collect()
+    }
+
+    /// Get an exchange account by ID
+    pub fn get_account(&self, id: &str) -> Result<ExchangeAccount> {
+        self.get_stored_account(id).map(|stored| stored.account)
+    }
+
+    fn get_stored_account(&self, id: &str) -> Result<EncryptedAccount> {
+        self.accounts
+            .get(id)
+            .ok_or_else(|| Error::NotFound(format!("Exchange account with ID '{}' not found", id)))
+    }
+
+    /// Connect an exchange account, storing its credentials encrypted
+    pub async fn create_account(&self, spec: ExchangeAccountSpec) -> Result<ExchangeAccount> {
+        let cipher = self.accounts.cipher()?;
+
+        if spec.name.trim().is_empty()
+            || spec.api_key.trim().is_empty()
+            || spec.api_secret.trim().is_empty()
+        {
+            return Err(Error::ParseError(
+                "Account name, API key and API secret are required".to_string(),
+            ));
+        }
+        if spec.exchange == Exchange::Coinbase && spec.passphrase.is_none() {
+            return Err(Error::ParseError(
+                "Coinbase API keys need their passphrase".to_string(),
+            ));
+        }
+
+        let credentials = Credentials {
+            api_key: spec.api_key.trim().to_string(),
+            api_secret: spec.api_secret.trim().to_string(),
+            passphrase: spec.passphrase,
+        };
+        // Reject exchanges without account support before storing anything
+        self.accounts
+            .connector(spec.exchange, credentials.clone(), Duration::zero())?;
+
+        let key_chars: Vec<char> = credentials.api_key.chars().collect();
+        let stored = EncryptedAccount {
+            account: ExchangeAccount {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: spec.name,
+                exchange: spec.exchange,
+                api_key_hint: key_chars[key_chars.len().saturating_sub(4)..]
+                    .iter()
+                    .collect(),
+                created_at: Utc::now(),
+                last_import_at: None,
+            },
+            credentials: cipher.encrypt(&credentials)?,
+        };
+
+        self.store.store_account(&stored).await?;
+        self.accounts.upsert(stored.clone());
+
+        info!(
+            "Connected {} account {}",
+            stored.account.exchange, stored.account.id
+        );
+        Ok(stored.account)
+    }
+
+    /// Disconnect an exchange account, deleting its stored credentials
+    pub async fn delete_account(&self, id: &str) -> Result<()> {
+        self.get_stored_account(id)?;
+
+        self.store.delete_account(id).await?;
+        self.accounts.remove(id);
+
+        info!("Deleted exchange account {}", id);
+        Ok(())
+    }
+
+    /// Import the balances of an exchange account, and its fills on every
+    /// pair the exchange lists between an asset and one of the import quote
+    /// currencies. Assets default to those with a balance.
+    pub async fn import_account(
+        &self,
+        id: &str,
+        assets: Option<Vec<String>>,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<AccountImport> {
+        let mut stored = self.get_stored_account(id)?;
+        let exchange = stored.account.exchange;
+        let credentials = self.accounts.cipher()?.decrypt(&stored.credentials)?;
+        // Exchanges reject signed requests stamped too far from their own clock
+        let connector =
+            self.accounts
+                .connector(exchange, credentials, self.clocks.offset(exchange))?;
+
+        let balances = connector.get_balances().await?;
+
+        let filter = MarketFilter {
+            exchange: Some(exchange),
+            ..Default::default()
+        };
+        let mut markets = self.markets.list(&filter).await;
+        if markets.is_empty() {
+            if let Some((_, public)) = self.connectors(Some(exchange)).into_iter().next() {
+                markets = public.list_markets().await?;
+            }
+        }
+        let listed: HashSet<TradingPair> = markets.into_iter().map(|m| m.pair).collect();
+
+        let assets = assets.unwrap_or_else(|| balances.iter().map(|b| b.asset.clone()).collect());
+        let mut fills = Vec::new();
+        for asset in &assets {
+            for quote in &self.accounts.config().quotes {
+                let pair = TradingPair {
+                    base: asset.to_uppercase(),
+                    quote: quote.clone(),
+                };
+                if listed.contains(&pair) {
+                    fills.extend(connector.get_fills(&pair, since).await?);
+                }
+            }
+        }
+        fills.sort_by_key(|fill| std::cmp::Reverse(fill.timestamp));
+
+        let imported_at = Utc::now();
+        stored.account.last_import_at = Some(imported_at);
+        self.store.store_account(&stored).await?;
+        self.accounts.upsert(stored);
+
+        info!(
+            "Imported {} balances and {} fills from {} account {}",
+            balances.len(),
+            fills.len(),
+            exchange,
+            id
+        );
+        Ok(AccountImport {
+            account_id: id.to_string(),
+            exchange,
+            balances,
+            fills,
+            imported_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use common::models::{FxRates, MarketStatus};
+    use connectors::{
+        binance::BinanceConnector, binance_futures::BinanceFuturesConnector,
+        coinbase::CoinbaseConnector, http::HttpMode,
+    };
+    use std::sync::Mutex;
+
+    // Exchange listing and pricing a fixed set of markets, recording the
+    // markets it was asked to price
+    struct StubExchange {
+        exchange: Exchange,
+        markets: Mutex<Vec<(TradingPair, Decimal)>>,
+        requests: Mutex<Vec<TradingPair>>,
+    }
+
+    impl StubExchange {
+        fn new(exchange: Exchange, markets: &[(&str, &str, &str)]) -> Arc<Self> {
+            let stub = Arc::new(Self {
+                exchange,
+                markets: Mutex::new(Vec::new()),
+                requests: Mutex::new(Vec::new()),
+            });
+            stub.list(markets);
+            stub
+        }
+
+        fn list(&self, markets: &[(&str, &str, &str)]) {
+            *self.markets.lock().unwrap() = markets
+                .iter()
+                .map(|(base, quote, price)| (pair(base, quote), price.parse().unwrap()))
+                .collect();
+        }
+    }
+
+    #[async_trait]
+    impl ExchangeConnector for StubExchange {
+        async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
+            self.requests.lock().unwrap().push(pair.clone());
+            let markets = self.markets.lock().unwrap();
+            let (_, price) = markets
+                .iter()
+                .find(|(listed, _)| listed == pair)
+                .ok_or_else(|| Error::NotFound(format!("{}/{}", pair.base, pair.quote)))?;
+
+            Ok(CurrentPrice {
+                exchange: self.exchange,
+                pair: pair.clone(),
+                price: *price,
+                bid: None,
+                ask: None,
+                open_24h: None,
+                high_24h: None,
+                low_24h: None,
+                change_24h: None,
+                change_percent_24h: None,
+                base_volume_24h: None,
+                volume_24h: None,
+                timestamp: Utc::now(),
+                flags: Vec::new(),
+            })
+        }
+
+        async fn get_price_history(
+            &self,
+            pair: &TradingPair,
+            _interval: PriceInterval,
+            _start_time: Option<DateTime<Utc>>,
+            _end_time: Option<DateTime<Utc>>,
+            _limit: Option<usize>,
+        ) -> Result<PriceHistory> {
+            Err(Error::NotFound(format!("{}/{}", pair.base, pair.quote)))
+        }
+
+        async fn get_recent_trades(
+            &self,
+            pair: &TradingPair,
+            _limit: Option<usize>,
+        ) -> Result<Vec<Trade>> {
+            Err(Error::NotFound(format!("{}/{}", pair.base, pair.quote)))
+        }
+
+        async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+            let markets = self.markets.lock().unwrap();
+            Ok(markets
+                .iter()
+                .map(|(pair, _)| MarketInfo {
+                    exchange: self.exchange,
+                    pair: pair.clone(),
+                    symbol: format!("{}{}", pair.base, pair.quote),
+                    status: MarketStatus::Trading,
+                    price_increment: None,
+                    size_increment: None,
+                    min_size: None,
+                    max_size: None,
+                    min_notional: None,
+                })
+                .collect())
+        }
+
+        async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+            Ok(Utc::now())
+        }
+    }
+
+    fn pair(base: &str, quote: &str) -> TradingPair {
+        TradingPair {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        }
+    }
+
+    // Store nothing listens on
+    fn store() -> Arc<PriceStore> {
+        let store = PriceStore::new(store::StoreConfig {
+            url: "http://127.0.0.1:1".to_string(),
+            token: "token".to_string(),
+            org: "org".to_string(),
+            bucket: "bucket".to_string(),
+            store_trades: false,
+            price_max_age_secs: 60,
+        })
+        .unwrap();
+        Arc::new(store)
+    }
+
+    // Service over the stub exchanges
+    fn service(coinbase: Arc<StubExchange>, binance: Arc<StubExchange>) -> CoinService {
+        CoinService::new(coinbase, binance, store())
+    }
+
+    // Bitcoin, listed as XBT on Binance
+    fn bitcoin() -> Coin {
+        Coin {
+            id: "bitcoin".to_string(),
+            name: "Bitcoin".to_string(),
+            symbol: "BTC".to_string(),
+            exchange_symbols: BTreeMap::from([(Exchange::Binance, "XBT".to_string())]),
+        }
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    // Client replaying the connectors' recorded responses of an exchange
+    fn replay(name: &str) -> HttpClient {
+        let fixtures =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../connectors/tests/fixtures");
+        HttpClient::new(HttpMode::Replay(fixtures.join(name)))
+    }
+
+    // Service over the recorded Binance spot and futures responses
+    fn derivatives_service() -> CoinService {
+        let coinbase = StubExchange::new(Exchange::Coinbase, &[]);
+        let binance = BinanceConnector::with_base_url("http://binance.test/api/v3")
+            .with_http(replay("binance"));
+        let futures = BinanceFuturesConnector::with_base_url("http://binance.test/fapi/v1")
+            .with_http(replay("binance_futures"));
+
+        CoinService::new(coinbase, Arc::new(binance), store()).with_derivatives(Arc::new(futures))
+    }
+
+    #[tokio::test]
+    async fn routed_and_converted_prices_use_exchange_symbols() {
+        let coinbase = StubExchange::new(Exchange::Coinbase, &[]);
+        let binance = StubExchange::new(
+            Exchange::Binance,
+            &[
+                ("XBT", "USDT", "60000"),
+                ("USDT", "EUR", "0.9"),
+                ("XBT", "USD", "66000"),
+            ],
+        );
+        let service = service(coinbase, binance);
+        service.catalog.replace(vec![bitcoin()]).unwrap();
+
+        let routed = service.get_routed_price("bitcoin", "EUR").await.unwrap();
+        assert_eq!(routed.pair, pair("BTC", "EUR"));
+        assert_eq!(routed.price, decimal("54000"));
+        assert_eq!(routed.route.legs[0].pair, pair("XBT", "USDT"));
+
+        service.fx_rates.insert(vec![FxRates {
+            source: "ecb".to_string(),
+            base: "EUR".to_string(),
+            date: Utc::now().date_naive(),
+            rates: BTreeMap::from([
+                ("USD".to_string(), decimal("1.1")),
+                ("GBP".to_string(), decimal("0.88")),
+            ]),
+        }]);
+        let converted = service
+            .get_converted_price("bitcoin", "GBP", Some("USD"), service.aggregation_config())
+            .await
+            .unwrap();
+        assert_eq!(converted.pair, pair("BTC", "GBP"));
+        assert_eq!(converted.source_price.price, decimal("66000"));
+        assert_eq!(converted.price, decimal("52800"));
+    }
+
+    #[tokio::test]
+    async fn catalog_refresh_drops_delisted_coins() {
+        let coinbase = StubExchange::new(Exchange::Coinbase, &[("DOGE", "USD", "0.1")]);
+        let binance = StubExchange::new(Exchange::Binance, &[]);
+        let service = service(coinbase.clone(), binance).with_catalog(CatalogConfig {
+            discover: true,
+            ..CatalogConfig::default()
+        });
+
+        service.refresh_catalog().await.unwrap();
+        assert!(service.get_coin("doge").is_ok());
+        assert!(service.get_coin("bitcoin").is_ok());
+
+        coinbase.list(&[]);
+        service.refresh_catalog().await.unwrap();
+        assert!(service.get_coin("doge").is_err());
+        assert!(service.get_coin("bitcoin").is_ok());
+    }
+
+    #[tokio::test]
+    async fn batch_prices_fetch_each_market_once() {
+        let coinbase = StubExchange::new(Exchange::Coinbase, &[]);
+        let binance = StubExchange::new(Exchange::Binance, &[("XBT", "USD", "66000")]);
+        let service = service(coinbase.clone(), binance.clone());
+        service.catalog.replace(vec![bitcoin()]).unwrap();
+        service.fx_rates.insert(vec![FxRates {
+            source: "ecb".to_string(),
+            base: "EUR".to_string(),
+            date: Utc::now().date_naive(),
+            rates: BTreeMap::from([
+                ("USD".to_string(), decimal("1.1")),
+                ("GBP".to_string(), decimal("0.88")),
+                ("JPY".to_string(), decimal("165")),
+            ]),
+        }]);
+
+        let currencies = ["USD", "GBP", "JPY"].map(String::from);
+        let batch = service
+            .get_batch_prices(&["bitcoin".to_string()], &currencies)
+            .await
+            .unwrap();
+
+        let cells = &batch.prices["bitcoin"];
+        for (currency, expected) in [("USD", "66000"), ("GBP", "52800"), ("JPY", "9900000")] {
+            match &cells[currency] {
+                BatchPriceCell::Price(price) => assert_eq!(price.price, decimal(expected)),
+                BatchPriceCell::Error { error } => panic!("{}: {}", currency, error),
+            }
+        }
+
+        // Both converted cells are priced from the same USD and EUR markets
+        for stub in [&coinbase, &binance] {
+            let requests = stub.requests.lock().unwrap();
+            let distinct: HashSet<_> = requests.iter().collect();
+            assert_eq!(distinct.len(), requests.len(), "{:?}", requests);
+        }
+        assert_eq!(
+            binance
+                .requests
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| **p == pair("XBT", "USD"))
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn alert_evaluation_fetches_watched_prices() {
+        let coinbase = StubExchange::new(Exchange::Coinbase, &[]);
+        let binance = StubExchange::new(Exchange::Binance, &[("XBT", "USD", "66000")]);
+        let service = service(coinbase, binance.clone());
+        service.catalog.replace(vec![bitcoin()]).unwrap();
+
+        let now = Utc::now();
+        let rule = |id: &str, currency: &str, enabled| AlertRule {
+            id: id.to_string(),
+            spec: AlertRuleSpec {
+                coin_id: "bitcoin".to_string(),
+                currency: currency.to_string(),
+                exchange: None,
+                condition: AlertCondition::PriceAbove { threshold: 1.0 },
+                webhook_url: "http://127.0.0.1:1/hook".to_string(),
+                cooldown_secs: None,
+                enabled,
+            },
+            created_at: now,
+            updated_at: now,
+        };
+        service.alerts.upsert(rule("usd", "USD", true));
+        service.alerts.upsert(rule("eur", "EUR", false));
+
+        // Prices are fetched for enabled rules even though nobody queried them
+        service.evaluate_alerts().await.unwrap();
+        assert_eq!(*binance.requests.lock().unwrap(), vec![pair("XBT", "USD")]);
+    }
+
+    #[tokio::test]
+    async fn aggregates_replayed_exchange_responses() {
+        let coinbase =
+            CoinbaseConnector::with_base_url("http://coinbase.test/").with_http(replay("coinbase"));
+        let binance = BinanceConnector::with_base_url("http://binance.test/api/v3")
+            .with_http(replay("binance"));
+
+        // The recorded prices are old, so they must not be flagged stale
+        let service = CoinService::new(Arc::new(coinbase), Arc::new(binance), store())
+            .with_quality(QualityConfig {
+                stale_after: Duration::days(365 * 100),
+                ..QualityConfig::default()
+            });
+
+        let aggregated = service
+            .get_aggregated_price("bitcoin", "usd", service.aggregation_config())
+            .await
+            .unwrap();
+
+        assert_eq!(aggregated.pair, pair("BTC", "USD"));
+        let sources: Vec<(Exchange, Decimal)> = aggregated
+            .sources
+            .iter()
+            .map(|source| (source.exchange, source.price))
+            .collect();
+        assert_eq!(
+            sources,
+            [
+                (Exchange::Coinbase, decimal("61000.50")),
+                (Exchange::Binance, decimal("61001")),
+            ]
+        );
+        // Coinbase reports base volume, Binance quote volume
+        assert_eq!(aggregated.volume_24h, Some(decimal("759192685.739450")));
+        // Coinbase carries most of the volume, so it sets the median
+        assert_eq!(aggregated.price, decimal("61000.50"));
+    }
+
+    // Hourly history with bars at the given hours of 2024-01-01, newest first
+    fn hourly(hours: &[u32]) -> PriceHistory {
+        let mut data: Vec<_> = hours
+            .iter()
+            .map(|hour| PriceHistoryPoint {
+                timestamp: Utc.with_ymd_and_hms(2024, 1, 1, *hour, 0, 0).unwrap(),
+                price: decimal("100"),
+                open: None,
+                high: None,
+                low: None,
+                volume: None,
+            })
+            .collect();
+        data.reverse();
+
+        PriceHistory {
+            exchange: Exchange::Coinbase,
+            pair: pair("BTC", "USD"),
+            interval: PriceInterval::OneHour.into(),
+            data,
+        }
+    }
+
+    #[test]
+    fn stored_history_must_cover_the_request() {
+        let at = |hour| Some(Utc.with_ymd_and_hms(2024, 1, 1, hour, 0, 0).unwrap());
+        let covers = |hours: &[u32], start, end, limit| {
+            covers(&hourly(hours), PriceInterval::OneHour, start, end, limit)
+        };
+
+        assert!(covers(&[2, 3, 4, 5], at(2), at(6), None));
+        // The first bar may open after an unaligned start time
+        assert!(covers(
+            &[3, 4, 5],
+            Some(Utc.with_ymd_and_hms(2024, 1, 1, 2, 30, 0).unwrap()),
+            at(6),
+            None
+        ));
+        // Cached bars missing at either end of the range
+        assert!(!covers(&[4, 5], at(2), at(6), None));
+        assert!(!covers(&[2, 3], at(2), at(6), None));
+        assert!(!covers(&[], at(2), at(6), None));
+
+        // Without a start time the newest `limit` bars are needed
+        assert!(covers(&[3, 4, 5], None, at(6), Some(3)));
+        assert!(!covers(&[4, 5], None, at(6), Some(3)));
+        // A limit reached from the start time covers the request
+        assert!(covers(&[2, 3], at(2), at(10), Some(2)));
+        assert!(!covers(&[2, 3], at(2), at(10), Some(3)));
+        // Open-ended ranges need bars up to now
+        assert!(!covers(&[2, 3], at(2), None, None));
+    }
+
+    #[tokio::test]
+    async fn perpetual_market_values_open_interest_at_the_mark_price() {
+        let market = derivatives_service()
+            .get_perpetual_market("bitcoin", "usdt")
+            .await
+            .unwrap();
+
+        assert_eq!(market.mark.mark_price, decimal("61010.5"));
+        assert_eq!(market.open_interest.open_interest, decimal("80000.5"));
+        assert_eq!(
+            market.open_interest.open_interest_value,
+            Some(decimal("4880870505.25"))
+        );
+    }
+
+    #[tokio::test]
+    async fn funding_rates_of_the_perpetual_contract() {
+        let history = derivatives_service()
+            .get_funding_rates("bitcoin", "usdt", None, None, Some(2))
+            .await
+            .unwrap();
+
+        assert_eq!(history.pair, pair("BTC", "USDT"));
+        let rates: Vec<Decimal> = history.data.iter().map(|r| r.funding_rate).collect();
+        assert_eq!(rates, [decimal("-0.00005"), decimal("0.0001")]);
+    }
+
+    #[tokio::test]
+    async fn basis_against_the_spot_price() {
+        let basis = derivatives_service()
+            .get_basis("bitcoin", "usdt")
+            .await
+            .unwrap();
+
+        assert_eq!(basis.perp_price, decimal("61010.5"));
+        assert_eq!(basis.spot_price, decimal("61000.1"));
+        assert_eq!(basis.basis, decimal("10.4"));
+        assert!((basis.basis_percent - 10.4 / 61000.1 * 100.0).abs() < 1e-9);
+        assert_eq!(basis.funding_rate, Some(decimal("0.0001")));
+    }
+
+    #[tokio::test]
+    async fn basis_history_pairs_bars_present_on_both_markets() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 3, 1, 2, 0, 0).unwrap();
+        let history = derivatives_service()
+            .get_basis_history(
+                "bitcoin",
+                "usdt",
+                PriceInterval::OneHour.into(),
+                Some(start),
+                Some(end),
+                Some(3),
+            )
+            .await
+            .unwrap();
+
+        // The latest spot candle is incomplete, so its bar is left out
+        let basis: Vec<(i64, Decimal, Decimal)> = history
+            .data
+            .iter()
+            .map(|point| (point.timestamp.timestamp(), point.spot_price, point.basis))
+            .collect();
+        assert_eq!(
+            basis,
+            [
+                (1709254800, decimal("60950.5"), decimal("-10")),
+                (1709251200, decimal("60400"), decimal("30")),
+            ]
+        );
+    }
+}
@@ -11,12 +11,26 @@ pub enum Error {
     #[error("Exchange API error: {0}")]
     ExchangeError(String),
 
+    /// Error response of an exchange API, with its HTTP status
+    #[error("{api} API error: {status} - {body}")]
+    ExchangeStatus {
+        api: String,
+        status: u16,
+        body: String,
+    },
+
     #[error("Parsing error: {0}")]
     ParseError(String),
 
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
     #[error("Configuration error: {0}")]
     ConfigError(String),
 
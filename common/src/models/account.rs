@@ -0,0 +1,116 @@
+use crate::models::{Exchange, TradeSide, TradingPair};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Amount of an asset held in an exchange account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountBalance {
+    /// Asset held (e.g., BTC)
+    pub asset: String,
+    /// Amount available for trading or withdrawal
+    pub free: Decimal,
+    /// Amount locked in open orders or otherwise on hold
+    pub locked: Decimal,
+}
+
+impl AccountBalance {
+    /// Amount held, free or locked
+    pub fn total(&self) -> Decimal {
+        self.free + self.locked
+    }
+}
+
+/// A trade of one of the account's own orders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    /// The exchange the trade was executed on
+    pub exchange: Exchange,
+    /// Trading pair (e.g., BTC/USD)
+    pub pair: TradingPair,
+    /// Exchange-assigned trade identifier
+    pub trade_id: String,
+    /// Exchange-assigned identifier of the account's order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+    /// Whether the account bought or sold
+    pub side: TradeSide,
+    /// Execution price in quote currency
+    pub price: Decimal,
+    /// Executed quantity in base currency
+    pub size: Decimal,
+    /// Fee charged for the trade
+    pub fee: Option<Decimal>,
+    /// Asset the fee was charged in
+    pub fee_asset: Option<String>,
+    /// Whether the account's order provided liquidity
+    pub maker: Option<bool>,
+    /// Execution time as reported by the exchange
+    pub timestamp: DateTime<Utc>,
+}
+
+/// User-supplied API key of an exchange account. Read-only keys suffice.
+#[derive(Clone, Deserialize)]
+pub struct ExchangeAccountSpec {
+    /// Display name
+    pub name: String,
+    /// Exchange the key belongs to
+    pub exchange: Exchange,
+    pub api_key: String,
+    pub api_secret: String,
+    /// Passphrase chosen when creating the key (Coinbase only)
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+// Keep secrets out of logs
+impl std::fmt::Debug for ExchangeAccountSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExchangeAccountSpec")
+            .field("name", &self.name)
+            .field("exchange", &self.exchange)
+            .field("api_key", &self.api_key)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A connected exchange account. Its credentials are never returned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeAccount {
+    /// Unique account identifier
+    pub id: String,
+    /// Display name
+    pub name: String,
+    /// Exchange the account is on
+    pub exchange: Exchange,
+    /// Last characters of the API key, to tell keys apart
+    pub api_key_hint: String,
+    /// When the account was connected
+    pub created_at: DateTime<Utc>,
+    /// When balances and fills were last imported
+    pub last_import_at: Option<DateTime<Utc>>,
+}
+
+/// An exchange account as persisted, with its credentials encrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedAccount {
+    #[serde(flatten)]
+    pub account: ExchangeAccount,
+    /// Encrypted API key, secret and passphrase
+    pub credentials: String,
+}
+
+/// Balances and fills imported from an exchange account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountImport {
+    /// Account imported from
+    pub account_id: String,
+    /// Exchange the account is on
+    pub exchange: Exchange,
+    /// Non-zero balances
+    pub balances: Vec<AccountBalance>,
+    /// Fills on the pairs of the imported assets, newest first
+    pub fills: Vec<Fill>,
+    /// When the import ran
+    pub imported_at: DateTime<Utc>,
+}
@@ -0,0 +1,101 @@
+use crate::models::{Exchange, FxConversion, PriceRoute, TradingPair};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Method used to combine per-exchange prices into a single price
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum AggregationMethod {
+    /// Volume-weighted mean of the contributing prices
+    #[serde(rename = "mean")]
+    Mean,
+    /// Volume-weighted median of the contributing prices
+    #[serde(rename = "median")]
+    Median,
+}
+
+impl std::fmt::Display for AggregationMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AggregationMethod::Mean => write!(f, "mean"),
+            AggregationMethod::Median => write!(f, "median"),
+        }
+    }
+}
+
+impl std::str::FromStr for AggregationMethod {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mean" => Ok(AggregationMethod::Mean),
+            "median" => Ok(AggregationMethod::Median),
+            unknown => Err(crate::Error::ParseError(format!(
+                "Unknown aggregation method: {}. Supported methods: mean, median",
+                unknown
+            ))),
+        }
+    }
+}
+
+/// An exchange price that contributed to an aggregated price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceSource {
+    /// The exchange the price is from
+    pub exchange: Exchange,
+    /// The exchange's price
+    pub price: Decimal,
+    /// 24h volume in quote currency used for weighting
+    pub volume_24h: Option<Decimal>,
+    /// Normalized weight of this price in the aggregate (weights sum to 1)
+    pub weight: f64,
+}
+
+/// Why an exchange price was left out of an aggregated price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum ExclusionReason {
+    /// The price deviated too far from the cross-exchange median
+    Outlier { deviation_percent: f64 },
+    /// The exchange reported no volume while others did
+    NoVolume,
+    /// The exchange price was flagged as stale
+    Stale,
+}
+
+/// An exchange price that was excluded from an aggregated price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedSource {
+    /// The exchange the price is from
+    pub exchange: Exchange,
+    /// The exchange's price
+    pub price: Decimal,
+    /// Why the price was excluded
+    #[serde(flatten)]
+    pub reason: ExclusionReason,
+}
+
+/// A single price for a trading pair aggregated across exchanges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregatedPrice {
+    /// Trading pair (e.g., BTC/USD)
+    pub pair: TradingPair,
+    /// Aggregated price value
+    pub price: Decimal,
+    /// Method used to aggregate the prices
+    pub method: AggregationMethod,
+    /// Total 24h volume in quote currency of the contributing exchanges
+    pub volume_24h: Option<Decimal>,
+    /// Prices that contributed to the aggregate
+    pub sources: Vec<PriceSource>,
+    /// Prices that were excluded from the aggregate
+    pub excluded: Vec<ExcludedSource>,
+    /// Timestamp of the most recent contributing price
+    pub timestamp: DateTime<Utc>,
+    /// Route the price was derived through when no exchange lists the pair
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub route: Option<PriceRoute>,
+    /// Fiat conversion the price was derived with when no exchange lists the pair
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conversion: Option<FxConversion>,
+}
@@ -0,0 +1,92 @@
+use crate::models::Exchange;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Condition that triggers a price alert
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Price rises above a threshold
+    PriceAbove { threshold: f64 },
+    /// Price falls below a threshold
+    PriceBelow { threshold: f64 },
+    /// Price moves by at least `percent` within a window; negative values match drops
+    PercentChange { percent: f64, window_secs: u64 },
+    /// Prices on two exchanges differ by more than `threshold_percent`
+    Spread {
+        exchanges: [Exchange; 2],
+        threshold_percent: f64,
+    },
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// User-supplied definition of an alert rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRuleSpec {
+    /// Coin the alert watches (e.g., bitcoin)
+    pub coin_id: String,
+    /// Quote currency (default: USD)
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Restrict the alert to one exchange; any exchange matches if unset.
+    /// Ignored by spread conditions, which name their exchanges.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exchange: Option<Exchange>,
+    /// When the alert triggers
+    pub condition: AlertCondition,
+    /// URL notifications are POSTed to
+    pub webhook_url: String,
+    /// Minimum time between notifications; the service default applies if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooldown_secs: Option<u64>,
+    /// Disabled rules are kept but not evaluated
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+/// A registered alert rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Unique rule identifier
+    pub id: String,
+    #[serde(flatten)]
+    pub spec: AlertRuleSpec,
+    /// When the rule was created
+    pub created_at: DateTime<Utc>,
+    /// When the rule was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Notification sent when an alert triggers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertEvent {
+    /// Unique event identifier, also sent as the delivery ID for deduplication
+    pub id: String,
+    /// Rule that triggered
+    pub rule_id: String,
+    /// Coin the rule watches
+    pub coin_id: String,
+    /// Quote currency
+    pub currency: String,
+    /// The triggered condition
+    pub condition: AlertCondition,
+    /// Exchange whose price triggered the alert
+    pub exchange: Exchange,
+    /// Price that triggered the alert
+    pub price: Decimal,
+    /// Price the condition compared against (window start or other exchange), if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_price: Option<Decimal>,
+    /// Human-readable description
+    pub message: String,
+    /// When the alert triggered
+    pub triggered_at: DateTime<Utc>,
+}
@@ -0,0 +1,27 @@
+use crate::models::AggregatedPrice;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A single coin and currency of a batch price lookup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BatchPriceCell {
+    /// The aggregated price
+    Price(Box<AggregatedPrice>),
+    /// Why no price is available
+    Error { error: String },
+}
+
+/// Aggregated prices of several coins in several quote currencies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchPrices {
+    /// Coins looked up
+    pub ids: Vec<String>,
+    /// Quote currencies looked up
+    pub currencies: Vec<String>,
+    /// Prices by coin ID, then by quote currency
+    pub prices: BTreeMap<String, BTreeMap<String, BatchPriceCell>>,
+    /// When the lookup finished
+    pub timestamp: DateTime<Utc>,
+}
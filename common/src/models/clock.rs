@@ -0,0 +1,21 @@
+use crate::models::Exchange;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Offset of an exchange's clock from the local clock, from one sample of
+/// the exchange's server time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockSkew {
+    /// The exchange whose clock was sampled
+    pub exchange: Exchange,
+    /// Exchange time minus local time, in milliseconds, assuming the server
+    /// time was read halfway through the request
+    pub offset_ms: i64,
+    /// Duration of the time request, in milliseconds; the offset is accurate
+    /// to within half of it
+    pub round_trip_ms: i64,
+    /// Time reported by the exchange
+    pub server_time: DateTime<Utc>,
+    /// Local time the response was received
+    pub sampled_at: DateTime<Utc>,
+}
@@ -1,4 +1,7 @@
-use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Coin {
@@ -8,15 +11,96 @@ pub struct Coin {
     pub name: String,
     /// Ticker symbol (e.g., "BTC", "ETH")
     pub symbol: String,
+    /// Exchange-specific ticker symbols, where they differ from `symbol`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub exchange_symbols: BTreeMap<Exchange, String>,
+}
+
+impl Coin {
+    /// Ticker symbol of the coin on an exchange
+    pub fn symbol_on(&self, exchange: Exchange) -> &str {
+        self.exchange_symbols
+            .get(&exchange)
+            .map(String::as_str)
+            .unwrap_or(&self.symbol)
+    }
+
+    /// Trading pair of the coin under its catalog symbol. Labels prices
+    /// combined across exchanges; requests to an exchange use [`Coin::pair_on`].
+    pub fn pair(&self, quote: &str) -> TradingPair {
+        TradingPair {
+            base: self.symbol.clone(),
+            quote: quote.to_string(),
+        }
+    }
+
+    /// Trading pair of the coin as listed on an exchange
+    pub fn pair_on(&self, exchange: Exchange, quote: &str) -> TradingPair {
+        TradingPair {
+            base: self.symbol_on(exchange).to_string(),
+            quote: quote.to_string(),
+        }
+    }
 }
 
 /// Exchange identifiers
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Exchange {
-    #[serde(rename = "coinbase")]
     Coinbase,
-    #[serde(rename = "binance")]
     Binance,
+    /// Exchange added through configuration, identified by its registered name
+    Custom(&'static str),
+}
+
+// Names of the exchanges added through configuration. They are leaked once
+// when registered, so that `Exchange` stays `Copy`.
+static CUSTOM_EXCHANGES: RwLock<Vec<&'static str>> = RwLock::new(Vec::new());
+
+impl Exchange {
+    /// Exchanges with a built-in connector
+    pub const BUILT_IN: [Exchange; 2] = [Exchange::Coinbase, Exchange::Binance];
+
+    /// All supported exchanges, built-in ones first
+    pub fn all() -> Vec<Exchange> {
+        let custom = CUSTOM_EXCHANGES.read().unwrap_or_else(|e| e.into_inner());
+
+        Self::BUILT_IN
+            .into_iter()
+            .chain(custom.iter().map(|name| Exchange::Custom(name)))
+            .collect()
+    }
+
+    /// Register an exchange added through configuration. Names are lowercase
+    /// letters, digits, `-` and `_`; registering a name again returns the
+    /// same exchange.
+    pub fn register(name: &str) -> crate::Result<Exchange> {
+        let valid = name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if name.is_empty() || !valid {
+            return Err(crate::Error::ConfigError(format!(
+                "Invalid exchange name '{}': use lowercase letters, digits, '-' and '_'",
+                name
+            )));
+        }
+        if Self::BUILT_IN
+            .iter()
+            .any(|exchange| exchange.to_string() == name)
+        {
+            return Err(crate::Error::ConfigError(format!(
+                "Exchange '{}' is built in and cannot be registered",
+                name
+            )));
+        }
+
+        let mut custom = CUSTOM_EXCHANGES.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = custom.iter().find(|existing| **existing == name) {
+            return Ok(Exchange::Custom(existing));
+        }
+        let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+        custom.push(name);
+        Ok(Exchange::Custom(name))
+    }
 }
 
 impl std::fmt::Display for Exchange {
@@ -24,13 +108,97 @@ impl std::fmt::Display for Exchange {
         match self {
             Exchange::Coinbase => write!(f, "coinbase"),
             Exchange::Binance => write!(f, "binance"),
+            Exchange::Custom(name) => write!(f, "{}", name),
         }
     }
 }
 
+impl std::str::FromStr for Exchange {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "coinbase" => Ok(Exchange::Coinbase),
+            "binance" => Ok(Exchange::Binance),
+            name => Exchange::all()
+                .into_iter()
+                .find(|exchange| exchange.to_string() == name)
+                .ok_or_else(|| {
+                    let supported: Vec<String> =
+                        Exchange::all().iter().map(ToString::to_string).collect();
+                    crate::Error::ParseError(format!(
+                        "Unknown exchange: {}. Supported exchanges: {}",
+                        name,
+                        supported.join(", ")
+                    ))
+                }),
+        }
+    }
+}
+
+// Exchanges are (de)serialized by name, so registered exchanges can be used
+// wherever the built-in ones are
+impl Serialize for Exchange {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Exchange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// Represents a pair of coins being traded
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct TradingPair {
-    pub base: String,   // Base currency (e.g., BTC)
-    pub quote: String,  // Quote currency (e.g., USD)
-} 
\ No newline at end of file
+    pub base: String,  // Base currency (e.g., BTC)
+    pub quote: String, // Quote currency (e.g., USD)
+}
+/// Descriptive and supply metadata for a coin
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CoinMetadata {
+    /// Short description of the project
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Number of coins in circulation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circulating_supply: Option<Decimal>,
+    /// Number of coins in existence (circulating plus locked)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_supply: Option<Decimal>,
+    /// Maximum number of coins that can ever exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_supply: Option<Decimal>,
+    /// Categories the coin belongs to (e.g., "layer-1", "defi")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+    /// Project homepage URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+    /// Block explorer URLs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub explorers: Vec<String>,
+    /// Token contract addresses keyed by chain (e.g., "ethereum" => "0x...")
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub contracts: BTreeMap<String, String>,
+}
+
+/// Full profile of a coin with its market data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinProfile {
+    #[serde(flatten)]
+    pub coin: Coin,
+    #[serde(flatten)]
+    pub metadata: CoinMetadata,
+    /// Quote currency of the market data
+    pub currency: String,
+    /// Aggregated price in the quote currency
+    pub price: Option<Decimal>,
+    /// Circulating supply times price
+    pub market_cap: Option<Decimal>,
+    /// Maximum (or else total) supply times price
+    pub fully_diluted_valuation: Option<Decimal>,
+}
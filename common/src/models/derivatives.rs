@@ -0,0 +1,173 @@
+use crate::models::{Exchange, PriceHistoryPoint, Timeframe, TradingPair};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Derivatives exchange identifiers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DerivativesExchange {
+    /// Binance USDⓈ-margined futures
+    #[serde(rename = "binance_usdm")]
+    BinanceUsdm,
+}
+
+impl std::fmt::Display for DerivativesExchange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DerivativesExchange::BinanceUsdm => write!(f, "binance_usdm"),
+        }
+    }
+}
+
+impl std::str::FromStr for DerivativesExchange {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "binance_usdm" => Ok(DerivativesExchange::BinanceUsdm),
+            unknown => Err(crate::Error::ParseError(format!(
+                "Unknown derivatives exchange: {}. Supported exchanges: binance_usdm",
+                unknown
+            ))),
+        }
+    }
+}
+
+/// Mark and index price of a perpetual contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkPrice {
+    /// The exchange the contract trades on
+    pub exchange: DerivativesExchange,
+    /// Trading pair of the contract (e.g., BTC/USDT)
+    pub pair: TradingPair,
+    /// Price used to value positions and trigger liquidations
+    pub mark_price: Decimal,
+    /// Spot price index the contract tracks
+    pub index_price: Decimal,
+    /// Estimated settlement price, if the exchange reports one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimated_settle_price: Option<Decimal>,
+    /// Most recent funding rate
+    pub last_funding_rate: Option<Decimal>,
+    /// Time of the next funding payment
+    pub next_funding_time: Option<DateTime<Utc>>,
+    /// Interest rate component of the funding rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interest_rate: Option<Decimal>,
+    /// Timestamp when this price was recorded
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Open interest of a perpetual contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenInterest {
+    /// The exchange the contract trades on
+    pub exchange: DerivativesExchange,
+    /// Trading pair of the contract (e.g., BTC/USDT)
+    pub pair: TradingPair,
+    /// Open positions in base currency
+    pub open_interest: Decimal,
+    /// Open positions in quote currency, valued at the mark price
+    pub open_interest_value: Option<Decimal>,
+    /// Timestamp when the open interest was recorded
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Current state of a perpetual contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerpetualMarket {
+    /// Mark and index price
+    pub mark: MarkPrice,
+    /// Open interest
+    pub open_interest: OpenInterest,
+}
+
+/// A funding payment of a perpetual contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRate {
+    /// Time of the funding payment
+    pub funding_time: DateTime<Utc>,
+    /// Rate paid by longs to shorts (negative if shorts pay longs)
+    pub funding_rate: Decimal,
+    /// Mark price at the time of the payment
+    pub mark_price: Option<Decimal>,
+}
+
+/// Funding payments of a perpetual contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FundingRateHistory {
+    /// The exchange the contract trades on
+    pub exchange: DerivativesExchange,
+    /// Trading pair of the contract (e.g., BTC/USDT)
+    pub pair: TradingPair,
+    /// Funding payments, newest first
+    pub data: Vec<FundingRate>,
+}
+
+/// Price history of a perpetual contract
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerpetualHistory {
+    /// The exchange the contract trades on
+    pub exchange: DerivativesExchange,
+    /// Trading pair of the contract (e.g., BTC/USDT)
+    pub pair: TradingPair,
+    /// Time interval for this price history
+    pub interval: Timeframe,
+    /// Price data points, newest first
+    pub data: Vec<PriceHistoryPoint>,
+}
+
+/// Difference between a perpetual contract's price and the spot price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Basis {
+    /// The exchange the contract trades on
+    pub exchange: DerivativesExchange,
+    /// The exchange the spot price is from
+    pub spot_exchange: Exchange,
+    /// Trading pair (e.g., BTC/USDT)
+    pub pair: TradingPair,
+    /// Mark price of the perpetual contract
+    pub perp_price: Decimal,
+    /// Index price of the perpetual contract
+    pub index_price: Decimal,
+    /// Last traded spot price
+    pub spot_price: Decimal,
+    /// Perpetual price minus spot price
+    pub basis: Decimal,
+    /// Basis as a percentage of the spot price
+    pub basis_percent: f64,
+    /// Most recent funding rate
+    pub funding_rate: Option<Decimal>,
+    /// Timestamp of the older of the two prices
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Basis at a point in time, from closing prices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasisPoint {
+    /// Timestamp for this point
+    pub timestamp: DateTime<Utc>,
+    /// Closing price of the perpetual contract
+    pub perp_price: Decimal,
+    /// Closing spot price
+    pub spot_price: Decimal,
+    /// Perpetual price minus spot price
+    pub basis: Decimal,
+    /// Basis as a percentage of the spot price
+    pub basis_percent: f64,
+}
+
+/// Basis history of a perpetual contract against spot
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasisHistory {
+    /// The exchange the contract trades on
+    pub exchange: DerivativesExchange,
+    /// The exchange the spot prices are from
+    pub spot_exchange: Exchange,
+    /// Trading pair (e.g., BTC/USDT)
+    pub pair: TradingPair,
+    /// Time interval for this history
+    pub interval: Timeframe,
+    /// Basis at bars where both prices are known, newest first
+    pub data: Vec<BasisPoint>,
+}
@@ -0,0 +1,77 @@
+use crate::models::{AggregatedPrice, Exchange, PriceHistoryPoint, Timeframe, TradingPair};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Daily reference rates of fiat currencies against a base currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxRates {
+    /// Where the rates were published (e.g., ecb)
+    pub source: String,
+    /// Currency the rates are quoted against
+    pub base: String,
+    /// Day the rates apply to
+    pub date: NaiveDate,
+    /// Units of each currency per unit of the base currency
+    pub rates: BTreeMap<String, Decimal>,
+}
+
+/// Fiat exchange rate applied to convert a price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FxConversion {
+    /// Currency converted from
+    pub from: String,
+    /// Currency converted to
+    pub to: String,
+    /// Units of `to` per unit of `from`
+    pub rate: Decimal,
+    /// Where the rate was published (e.g., ecb)
+    pub source: String,
+    /// Start of the day the rate applies to
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A price converted from another fiat currency the coin trades in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvertedPrice {
+    /// Trading pair (e.g., BTC/JPY)
+    pub pair: TradingPair,
+    /// Converted price value
+    pub price: Decimal,
+    /// Aggregated price in the currency converted from
+    pub source_price: AggregatedPrice,
+    /// Rate the price was converted with
+    pub conversion: FxConversion,
+    /// Timestamp of the price converted from
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Price history point converted into another fiat currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvertedPricePoint {
+    /// Converted prices
+    #[serde(flatten)]
+    pub point: PriceHistoryPoint,
+    /// Units of the target currency per unit of the source currency
+    pub rate: Decimal,
+    /// Start of the day the rate applies to
+    pub rate_timestamp: DateTime<Utc>,
+}
+
+/// Price history converted from another fiat currency the coin trades in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConvertedPriceHistory {
+    /// The exchange the source history is from
+    pub exchange: Exchange,
+    /// Trading pair (e.g., BTC/JPY)
+    pub pair: TradingPair,
+    /// Trading pair of the history converted from (e.g., BTC/USD)
+    pub source_pair: TradingPair,
+    /// Where the rates were published (e.g., ecb)
+    pub source: String,
+    /// Bar size of the history
+    pub interval: Timeframe,
+    /// Converted prices at bars with a known rate, newest first
+    pub data: Vec<ConvertedPricePoint>,
+}
@@ -0,0 +1,69 @@
+use crate::models::Exchange;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// State of a circuit breaker guarding an exchange connector
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests pass through normally
+    #[serde(rename = "closed")]
+    Closed,
+    /// Requests fail immediately without reaching the exchange
+    #[serde(rename = "open")]
+    Open,
+    /// A limited number of trial requests are let through to probe recovery
+    #[serde(rename = "half_open")]
+    HalfOpen,
+}
+
+impl std::fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half_open"),
+        }
+    }
+}
+
+/// Latency percentiles of recent requests
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    /// Number of requests the percentiles are computed over
+    pub samples: usize,
+    /// Median latency in milliseconds
+    pub p50_ms: Option<u64>,
+    /// 90th percentile latency in milliseconds
+    pub p90_ms: Option<u64>,
+    /// 99th percentile latency in milliseconds
+    pub p99_ms: Option<u64>,
+}
+
+/// Health of an exchange connector as seen by its circuit breaker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectorHealth {
+    /// Current circuit state
+    pub state: CircuitState,
+    /// When the circuit entered its current state
+    pub state_since: DateTime<Utc>,
+    /// Fraction of failed requests in the recent window
+    pub failure_rate: f64,
+    /// Number of requests in the recent window
+    pub window_calls: usize,
+    /// Message of the most recent failure
+    pub last_error: Option<String>,
+    /// Time of the most recent failure
+    pub last_error_at: Option<DateTime<Utc>>,
+    /// Latency of recent requests
+    pub latency: LatencyPercentiles,
+}
+
+/// Health of an exchange's connector
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExchangeStatus {
+    /// The exchange
+    pub exchange: Exchange,
+    /// Circuit breaker state and recent request statistics
+    #[serde(flatten)]
+    pub health: ConnectorHealth,
+}
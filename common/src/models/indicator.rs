@@ -0,0 +1,29 @@
+use crate::models::{Exchange, Timeframe, TradingPair};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Indicator values at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorPoint {
+    /// Timestamp of the price bar the values were computed at
+    pub timestamp: DateTime<Utc>,
+    /// Indicator outputs by name (e.g., "value", or "macd", "signal", "histogram")
+    #[serde(flatten)]
+    pub values: BTreeMap<String, f64>,
+}
+
+/// A technical indicator computed over price history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorSeries {
+    /// The exchange the underlying prices are from
+    pub exchange: Exchange,
+    /// Trading pair (e.g., BTC/USD)
+    pub pair: TradingPair,
+    /// Bar size of the underlying prices
+    pub interval: Timeframe,
+    /// Indicator with its parameters (e.g., "rsi(14)")
+    pub indicator: String,
+    /// Indicator values, newest first
+    pub data: Vec<IndicatorPoint>,
+}
@@ -0,0 +1,65 @@
+use crate::models::{Exchange, TradingPair};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Trading status of a market on an exchange
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum MarketStatus {
+    /// Open for normal trading
+    #[serde(rename = "trading")]
+    Trading,
+    /// Only limit (or post-only) orders are accepted
+    #[serde(rename = "limit_only")]
+    LimitOnly,
+    /// Only order cancellations are accepted
+    #[serde(rename = "cancel_only")]
+    CancelOnly,
+    /// Temporarily not trading (break, halt, auction, maintenance)
+    #[serde(rename = "halted")]
+    Halted,
+    /// Permanently removed from the exchange
+    #[serde(rename = "delisted")]
+    Delisted,
+}
+
+impl MarketStatus {
+    /// Whether new trades can currently happen on the market
+    pub fn is_tradable(&self) -> bool {
+        matches!(self, MarketStatus::Trading | MarketStatus::LimitOnly)
+    }
+}
+
+impl std::fmt::Display for MarketStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MarketStatus::Trading => write!(f, "trading"),
+            MarketStatus::LimitOnly => write!(f, "limit_only"),
+            MarketStatus::CancelOnly => write!(f, "cancel_only"),
+            MarketStatus::Halted => write!(f, "halted"),
+            MarketStatus::Delisted => write!(f, "delisted"),
+        }
+    }
+}
+
+/// Metadata describing a market (trading pair) listed on an exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketInfo {
+    /// The exchange listing this market
+    pub exchange: Exchange,
+    /// Trading pair (e.g., BTC/USD)
+    pub pair: TradingPair,
+    /// Exchange-native market identifier (e.g., "BTCUSDT", "BTC-USD")
+    pub symbol: String,
+    /// Current trading status
+    pub status: MarketStatus,
+    /// Minimum price increment (tick size) in quote currency
+    pub price_increment: Option<Decimal>,
+    /// Minimum quantity increment (lot size) in base currency
+    pub size_increment: Option<Decimal>,
+    /// Minimum order size in base currency
+    pub min_size: Option<Decimal>,
+    /// Maximum order size in base currency
+    pub max_size: Option<Decimal>,
+    /// Minimum order value in quote currency
+    pub min_notional: Option<Decimal>,
+}
@@ -1,5 +1,33 @@
+mod account;
+mod aggregate;
+mod alert;
+mod batch;
+mod clock;
 mod coin;
+mod derivatives;
+mod fx;
+mod health;
+mod indicator;
+mod market;
+mod portfolio;
 mod price;
+mod quality;
+mod route;
+mod trade;
 
+pub use account::*;
+pub use aggregate::*;
+pub use alert::*;
+pub use batch::*;
+pub use clock::*;
 pub use coin::*;
-pub use price::*; 
\ No newline at end of file
+pub use derivatives::*;
+pub use fx::*;
+pub use health::*;
+pub use indicator::*;
+pub use market::*;
+pub use portfolio::*;
+pub use price::*;
+pub use quality::*;
+pub use route::*;
+pub use trade::*;
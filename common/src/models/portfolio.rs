@@ -0,0 +1,146 @@
+use crate::models::{Timeframe, TradeSide};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// A buy or sell of a holding, priced in the portfolio currency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioTransaction {
+    /// Whether the coin was bought or sold
+    pub side: TradeSide,
+    /// Amount of the coin traded
+    pub quantity: Decimal,
+    /// Price per coin
+    pub price: Decimal,
+    /// Fee paid, in the portfolio currency
+    #[serde(default)]
+    pub fee: Decimal,
+    /// When the transaction happened
+    pub timestamp: DateTime<Utc>,
+}
+
+/// A coin held in a portfolio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Holding {
+    /// Coin held (e.g., bitcoin)
+    pub coin_id: String,
+    /// Amount held; derived from the transactions if there are any
+    #[serde(default)]
+    pub quantity: Decimal,
+    /// Total cost of the amount held, in the portfolio currency; derived from
+    /// the transactions if there are any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_basis: Option<Decimal>,
+    /// Transactions the holding was built from, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transactions: Vec<PortfolioTransaction>,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// User-supplied definition of a portfolio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioSpec {
+    /// Display name
+    pub name: String,
+    /// Currency cost bases and transaction prices are given in (default: USD)
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    /// Coins held
+    #[serde(default)]
+    pub holdings: Vec<Holding>,
+}
+
+/// A registered portfolio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Portfolio {
+    /// Unique portfolio identifier
+    pub id: String,
+    #[serde(flatten)]
+    pub spec: PortfolioSpec,
+    /// When the portfolio was created
+    pub created_at: DateTime<Utc>,
+    /// When the portfolio was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Current value of a single holding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldingValuation {
+    /// Coin held
+    pub coin_id: String,
+    /// Amount held
+    pub quantity: Decimal,
+    /// Aggregated price of the coin
+    pub price: Decimal,
+    /// Value of the amount held
+    pub value: Decimal,
+    /// Share of the portfolio value, in percent
+    pub weight_percent: f64,
+    /// Total cost of the amount held
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_basis: Option<Decimal>,
+    /// Value minus cost basis
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unrealized_pnl: Option<Decimal>,
+    /// Unrealized P&L relative to the cost basis, in percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unrealized_pnl_percent: Option<f64>,
+    /// Gains locked in by sells, net of fees
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub realized_pnl: Option<Decimal>,
+}
+
+/// Current value and P&L of a portfolio.
+///
+/// P&L is only reported when valuing in the portfolio currency, since cost
+/// bases are given in it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioValuation {
+    /// Portfolio valued
+    pub portfolio_id: String,
+    /// Currency of the values
+    pub currency: String,
+    /// Total value of all holdings
+    pub value: Decimal,
+    /// Total cost of all holdings with a cost basis
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_basis: Option<Decimal>,
+    /// Value minus cost basis of all holdings with a cost basis
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unrealized_pnl: Option<Decimal>,
+    /// Unrealized P&L relative to the cost basis, in percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unrealized_pnl_percent: Option<f64>,
+    /// Realized P&L of all holdings
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub realized_pnl: Option<Decimal>,
+    /// Value of each holding
+    pub holdings: Vec<HoldingValuation>,
+    /// When the valuation was computed
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Portfolio value at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioValuePoint {
+    /// Start of the price bar the value was computed at
+    pub timestamp: DateTime<Utc>,
+    /// Total value of the holdings at the bar's closing prices
+    pub value: Decimal,
+}
+
+/// Historical value of a portfolio
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortfolioValueHistory {
+    /// Portfolio valued
+    pub portfolio_id: String,
+    /// Currency of the values
+    pub currency: String,
+    /// Bar size of the underlying prices
+    pub interval: Timeframe,
+    /// Values, newest first
+    pub data: Vec<PortfolioValuePoint>,
+}
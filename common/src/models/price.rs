@@ -1,5 +1,6 @@
-use crate::models::{Exchange, TradingPair};
-use chrono::{DateTime, Utc};
+use crate::models::{Exchange, QualityFlag, TradingPair};
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
 /// Current price data from an exchange
@@ -9,12 +10,31 @@ pub struct CurrentPrice {
     pub exchange: Exchange,
     /// Trading pair (e.g., BTC/USD)
     pub pair: TradingPair,
-    /// Current price value
-    pub price: f64,
-    /// 24h volume in quote currency
-    pub volume_24h: Option<f64>,
+    /// Current price value (last trade)
+    pub price: Decimal,
+    /// Best bid price
+    pub bid: Option<Decimal>,
+    /// Best ask price
+    pub ask: Option<Decimal>,
+    /// Price 24 hours ago
+    pub open_24h: Option<Decimal>,
+    /// Highest price in the last 24 hours
+    pub high_24h: Option<Decimal>,
+    /// Lowest price in the last 24 hours
+    pub low_24h: Option<Decimal>,
+    /// Absolute price change over the last 24 hours
+    pub change_24h: Option<Decimal>,
+    /// Percentage price change over the last 24 hours
+    pub change_percent_24h: Option<Decimal>,
+    /// 24h volume in base currency
+    pub base_volume_24h: Option<Decimal>,
+    /// 24h volume in quote currency, as reported by the exchange
+    pub volume_24h: Option<Decimal>,
     /// Timestamp when this price was recorded
     pub timestamp: DateTime<Utc>,
+    /// Data-quality problems detected on this price
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub flags: Vec<QualityFlag>,
 }
 
 /// Price history point
@@ -22,10 +42,19 @@ pub struct CurrentPrice {
 pub struct PriceHistoryPoint {
     /// Timestamp for this price point
     pub timestamp: DateTime<Utc>,
-    /// The price at this point in time
-    pub price: f64,
+    /// The price at this point in time (closing price of the period)
+    pub price: Decimal,
+    /// Opening price of the period
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open: Option<Decimal>,
+    /// Highest price during the period
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub high: Option<Decimal>,
+    /// Lowest price during the period
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low: Option<Decimal>,
     /// Trading volume for this time period
-    pub volume: Option<f64>,
+    pub volume: Option<Decimal>,
 }
 
 /// Historical price data
@@ -36,7 +65,7 @@ pub struct PriceHistory {
     /// Trading pair (e.g., BTC/USD)
     pub pair: TradingPair,
     /// Time interval for this price history
-    pub interval: PriceInterval,
+    pub interval: Timeframe,
     /// Price data points
     pub data: Vec<PriceHistoryPoint>,
 }
@@ -72,4 +101,274 @@ impl std::fmt::Display for PriceInterval {
             PriceInterval::OneWeek => write!(f, "1w"),
         }
     }
-} 
\ No newline at end of file
+}
+
+impl PriceInterval {
+    /// Length of the interval
+    pub fn duration(&self) -> Duration {
+        match self {
+            PriceInterval::OneMinute => Duration::minutes(1),
+            PriceInterval::FiveMinutes => Duration::minutes(5),
+            PriceInterval::FifteenMinutes => Duration::minutes(15),
+            PriceInterval::OneHour => Duration::hours(1),
+            PriceInterval::FourHours => Duration::hours(4),
+            PriceInterval::OneDay => Duration::days(1),
+            PriceInterval::OneWeek => Duration::weeks(1),
+        }
+    }
+}
+
+/// Unit of a timeframe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    Minute,
+    Hour,
+    Day,
+    Week,
+    /// Calendar month
+    Month,
+}
+
+/// Bar size of a price history: any positive multiple of a time unit.
+///
+/// Bars are aligned as follows: minute, hour and day multiples to the Unix
+/// epoch (1970-01-01T00:00Z), week multiples to Monday 00:00 UTC, and month
+/// multiples to the first day of a calendar month counted from January 1970.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Timeframe {
+    pub count: u32,
+    pub unit: TimeUnit,
+}
+
+// Native intervals that every connector can serve, finest last
+const SOURCE_INTERVALS: [PriceInterval; 5] = [
+    PriceInterval::OneDay,
+    PriceInterval::OneHour,
+    PriceInterval::FifteenMinutes,
+    PriceInterval::FiveMinutes,
+    PriceInterval::OneMinute,
+];
+
+impl Timeframe {
+    /// Largest bar count accepted when parsing a timeframe
+    pub const MAX_COUNT: u32 = 1_000;
+
+    pub fn new(count: u32, unit: TimeUnit) -> Self {
+        Self { count, unit }
+    }
+
+    /// The native interval this timeframe corresponds to, if any
+    pub fn native(&self) -> Option<PriceInterval> {
+        match (self.count, self.unit) {
+            (1, TimeUnit::Minute) => Some(PriceInterval::OneMinute),
+            (5, TimeUnit::Minute) => Some(PriceInterval::FiveMinutes),
+            (15, TimeUnit::Minute) => Some(PriceInterval::FifteenMinutes),
+            (1, TimeUnit::Hour) => Some(PriceInterval::OneHour),
+            (4, TimeUnit::Hour) => Some(PriceInterval::FourHours),
+            (1, TimeUnit::Day) => Some(PriceInterval::OneDay),
+            (1, TimeUnit::Week) => Some(PriceInterval::OneWeek),
+            _ => None,
+        }
+    }
+
+    /// The coarsest native interval whose bars fit exactly into this timeframe
+    pub fn source_interval(&self) -> PriceInterval {
+        match self.unit {
+            // Weeks and months are built from days, whose bars start at midnight UTC
+            TimeUnit::Day | TimeUnit::Week | TimeUnit::Month => PriceInterval::OneDay,
+            TimeUnit::Minute | TimeUnit::Hour => {
+                let seconds = self.max_seconds();
+                SOURCE_INTERVALS
+                    .into_iter()
+                    .find(|interval| seconds % interval.duration().num_seconds() == 0)
+                    .unwrap_or(PriceInterval::OneMinute)
+            }
+        }
+    }
+
+    /// Longest possible length of a single bar
+    pub fn max_duration(&self) -> Duration {
+        Duration::seconds(self.max_seconds())
+    }
+
+    /// Start of the bar containing a timestamp, or `None` if that falls
+    /// outside the representable date range
+    pub fn bucket_start(&self, timestamp: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let count = self.count.max(1) as i64;
+        match self.unit {
+            TimeUnit::Minute | TimeUnit::Hour | TimeUnit::Day => {
+                let size = self.max_seconds();
+                let secs = timestamp.timestamp().div_euclid(size).checked_mul(size)?;
+                Utc.timestamp_opt(secs, 0).single()
+            }
+            TimeUnit::Week => {
+                // 1970-01-05 was the first Monday after the epoch
+                let anchor = 4 * 86_400;
+                let size = self.max_seconds();
+                let offset = (timestamp.timestamp() - anchor).div_euclid(size);
+                let secs = offset.checked_mul(size)?.checked_add(anchor)?;
+                Utc.timestamp_opt(secs, 0).single()
+            }
+            TimeUnit::Month => {
+                let months = (timestamp.year() as i64 - 1970) * 12 + timestamp.month0() as i64;
+                let start = months.div_euclid(count) * count;
+                let year = i32::try_from(1970 + start.div_euclid(12)).ok()?;
+                let month = start.rem_euclid(12) as u32 + 1;
+                Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
+            }
+        }
+    }
+
+    /// Start of the bar `bars` bars before the one containing `end`, for
+    /// fetching a range of that many bars
+    pub fn bars_before(&self, end: DateTime<Utc>, bars: usize) -> crate::Result<DateTime<Utc>> {
+        let out_of_range = || {
+            crate::Error::InvalidRequest(format!(
+                "Cannot fetch {} bars of {} before {}",
+                bars, self, end
+            ))
+        };
+
+        let bars = i32::try_from(bars).map_err(|_| out_of_range())?;
+        let span = self
+            .max_duration()
+            .checked_mul(bars)
+            .ok_or_else(out_of_range)?;
+        let start = end.checked_sub_signed(span).ok_or_else(out_of_range)?;
+        self.bucket_start(start).ok_or_else(out_of_range)
+    }
+
+    // Bar length in seconds, exact for all units but months
+    fn max_seconds(&self) -> i64 {
+        let count = self.count.max(1) as i64;
+        match self.unit {
+            TimeUnit::Minute => count * 60,
+            TimeUnit::Hour => count * 3_600,
+            TimeUnit::Day => count * 86_400,
+            TimeUnit::Week => count * 604_800,
+            TimeUnit::Month => count * 31 * 86_400,
+        }
+    }
+}
+
+impl From<PriceInterval> for Timeframe {
+    fn from(interval: PriceInterval) -> Self {
+        match interval {
+            PriceInterval::OneMinute => Timeframe::new(1, TimeUnit::Minute),
+            PriceInterval::FiveMinutes => Timeframe::new(5, TimeUnit::Minute),
+            PriceInterval::FifteenMinutes => Timeframe::new(15, TimeUnit::Minute),
+            PriceInterval::OneHour => Timeframe::new(1, TimeUnit::Hour),
+            PriceInterval::FourHours => Timeframe::new(4, TimeUnit::Hour),
+            PriceInterval::OneDay => Timeframe::new(1, TimeUnit::Day),
+            PriceInterval::OneWeek => Timeframe::new(1, TimeUnit::Week),
+        }
+    }
+}
+
+impl std::fmt::Display for Timeframe {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = match self.unit {
+            TimeUnit::Minute => "m",
+            TimeUnit::Hour => "h",
+            TimeUnit::Day => "d",
+            TimeUnit::Week => "w",
+            TimeUnit::Month => "M",
+        };
+        write!(f, "{}{}", self.count, unit)
+    }
+}
+
+impl std::str::FromStr for Timeframe {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            crate::Error::ParseError(format!(
+                "Invalid interval: {}. Expected a count followed by m, h, d, w or M (e.g. 2h, 3d, 1M)",
+                s
+            ))
+        };
+
+        let split = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+        let (count, unit) = s.split_at(split);
+
+        let count: u32 = count.parse().map_err(|_| invalid())?;
+        if count == 0 {
+            return Err(invalid());
+        }
+        if count > Timeframe::MAX_COUNT {
+            return Err(crate::Error::InvalidRequest(format!(
+                "Invalid interval: {}. The count must be at most {}",
+                s,
+                Timeframe::MAX_COUNT
+            )));
+        }
+
+        let unit = match unit {
+            "m" => TimeUnit::Minute,
+            "h" => TimeUnit::Hour,
+            "d" => TimeUnit::Day,
+            "w" => TimeUnit::Week,
+            "M" => TimeUnit::Month,
+            _ => return Err(invalid()),
+        };
+
+        Ok(Timeframe::new(count, unit))
+    }
+}
+
+impl TryFrom<String> for Timeframe {
+    type Error = crate::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Timeframe> for String {
+    fn from(timeframe: Timeframe) -> Self {
+        timeframe.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_rejects_counts_over_the_maximum() {
+        assert_eq!(
+            "1000M".parse::<Timeframe>().unwrap(),
+            Timeframe::new(1_000, TimeUnit::Month)
+        );
+        assert!(matches!(
+            "1001h".parse::<Timeframe>(),
+            Err(crate::Error::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            "4294967296m".parse::<Timeframe>(),
+            Err(crate::Error::ParseError(_))
+        ));
+    }
+
+    #[test]
+    fn bars_before_rejects_ranges_out_of_range() {
+        let timeframe = Timeframe::new(1_000, TimeUnit::Month);
+        let end = Utc.with_ymd_and_hms(2024, 5, 17, 12, 0, 0).unwrap();
+
+        // 1000 months before 1970 is September 1886
+        assert_eq!(
+            timeframe.bars_before(end, 1).unwrap(),
+            Utc.with_ymd_and_hms(1886, 9, 1, 0, 0, 0).unwrap()
+        );
+        assert!(matches!(
+            timeframe.bars_before(end, usize::MAX),
+            Err(crate::Error::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            timeframe.bars_before(end, 1_000_000),
+            Err(crate::Error::InvalidRequest(_))
+        ));
+    }
+}
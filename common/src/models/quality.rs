@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A data-quality problem detected on an exchange price
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "flag", rename_all = "snake_case")]
+pub enum QualityFlag {
+    /// The price deviates from the cross-exchange consensus (median)
+    ConsensusDeviation {
+        consensus: f64,
+        deviation_percent: f64,
+    },
+    /// The price deviates from the exchange's own recent history
+    HistoryDeviation {
+        reference: f64,
+        deviation_percent: f64,
+    },
+    /// The exchange timestamp has not advanced or is too old
+    Stale {
+        last_update: DateTime<Utc>,
+        age_secs: i64,
+    },
+}
+
+impl QualityFlag {
+    /// Whether the flag indicates the price should not be relied upon at all
+    pub fn is_stale(&self) -> bool {
+        matches!(self, QualityFlag::Stale { .. })
+    }
+}
@@ -0,0 +1,61 @@
+use crate::models::{Exchange, PriceHistoryPoint, Timeframe, TradingPair};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// One conversion step of a price route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteLeg {
+    /// Exchange the market is listed on
+    pub exchange: Exchange,
+    /// The market as listed on the exchange
+    pub pair: TradingPair,
+    /// Asset converted from
+    pub from: String,
+    /// Asset converted to
+    pub to: String,
+    /// Whether the leg converts from the market's quote to its base
+    pub inverted: bool,
+    /// Units of `to` per unit of `from`, if priced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Decimal>,
+    /// 24h volume of the market in units of `from`, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume_24h: Option<Decimal>,
+}
+
+/// Path through the markets used to derive a synthetic price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceRoute {
+    /// Conversion steps, from the coin to the quote currency
+    pub legs: Vec<RouteLeg>,
+    /// Volume of the least liquid leg in units of the coin, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub liquidity: Option<Decimal>,
+}
+
+/// A price derived by converting through intermediate markets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutedPrice {
+    /// Trading pair (e.g., ADA/EUR)
+    pub pair: TradingPair,
+    /// Synthetic price, the product of the leg rates
+    pub price: Decimal,
+    /// Route the price was derived through
+    pub route: PriceRoute,
+    /// Timestamp of the oldest leg price
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Price history derived by converting through intermediate markets
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutedPriceHistory {
+    /// Trading pair (e.g., ADA/EUR)
+    pub pair: TradingPair,
+    /// Bar size of the history
+    pub interval: Timeframe,
+    /// Route the history was derived through
+    pub route: PriceRoute,
+    /// Synthetic prices at bars where every leg has a price, newest first
+    pub data: Vec<PriceHistoryPoint>,
+}
@@ -0,0 +1,41 @@
+use crate::models::{Exchange, TradingPair};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+/// Side of the taker (aggressor) in a trade
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum TradeSide {
+    #[serde(rename = "buy")]
+    Buy,
+    #[serde(rename = "sell")]
+    Sell,
+}
+
+impl std::fmt::Display for TradeSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TradeSide::Buy => write!(f, "buy"),
+            TradeSide::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+/// A single executed trade reported by an exchange
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    /// The exchange this trade was executed on
+    pub exchange: Exchange,
+    /// Trading pair (e.g., BTC/USD)
+    pub pair: TradingPair,
+    /// Exchange-assigned trade identifier
+    pub trade_id: String,
+    /// Execution price in quote currency
+    pub price: Decimal,
+    /// Executed quantity in base currency
+    pub size: Decimal,
+    /// Side of the taker
+    pub side: TradeSide,
+    /// Execution time as reported by the exchange
+    pub timestamp: DateTime<Utc>,
+}
@@ -1,48 +1,84 @@
+use crate::http::HttpClient;
 use crate::ExchangeConnector;
 use async_trait::async_trait;
-use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use common::{
-    models::{CurrentPrice, Exchange, PriceHistory, PriceHistoryPoint, PriceInterval, TradingPair},
+    models::{
+        CurrentPrice, Exchange, MarketInfo, MarketStatus, PriceHistory, PriceHistoryPoint,
+        PriceInterval, Trade, TradeSide, TradingPair,
+    },
     Error, Result,
 };
-use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use tracing::debug;
 
-const BINANCE_API_URL: &str = "https://api.binance.com/api/v3";
+pub(crate) const BINANCE_API_URL: &str = "https://api.binance.com/api/v3";
 
 pub struct BinanceConnector {
-    client: reqwest::Client,
+    http: HttpClient,
+    base_url: String,
+}
+
+impl Default for BinanceConnector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BinanceConnector {
     pub fn new() -> Self {
+        Self::with_base_url(BINANCE_API_URL)
+    }
+
+    /// Create a connector for another host serving the spot API, such as the
+    /// testnet
+    pub fn with_base_url(base_url: &str) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            http: HttpClient::default(),
+            base_url: base_url.trim_end_matches('/').to_string(),
         }
     }
 
+    /// Send requests through a client, e.g. one recording or replaying them
+    pub fn with_http(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
     fn format_symbol(&self, pair: &TradingPair) -> String {
         format!("{}{}", pair.base, pair.quote)
     }
 }
 
 #[derive(Debug, Deserialize)]
-struct BinanceTickerPrice {
-    symbol: String,
-    price: String,
-}
-
-#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct Binance24hTicker {
-    symbol: String,
-    #[serde(rename = "lastPrice")]
     last_price: String,
-    #[serde(rename = "volume")]
+    bid_price: String,
+    ask_price: String,
+    open_price: String,
+    high_price: String,
+    low_price: String,
+    price_change: String,
+    price_change_percent: String,
     volume: String,
+    quote_volume: String,
+    close_time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceTrade {
+    id: u64,
+    price: String,
+    qty: String,
+    time: i64,
+    is_buyer_maker: bool,
 }
 
 // Convert PriceInterval to Binance interval string
-fn binance_interval(interval: PriceInterval) -> &'static str {
+pub(crate) fn binance_interval(interval: PriceInterval) -> &'static str {
     match interval {
         PriceInterval::OneMinute => "1m",
         PriceInterval::FiveMinutes => "5m",
@@ -54,53 +90,88 @@ fn binance_interval(interval: PriceInterval) -> &'static str {
     }
 }
 
+// Convert Binance candles into price history points, newest first.
+// Spot and futures klines share the same format.
+pub(crate) fn parse_klines(candles: Vec<Vec<serde_json::Value>>) -> Vec<PriceHistoryPoint> {
+    let mut data_points = Vec::with_capacity(candles.len());
+
+    for candle in candles {
+        if candle.len() < 6 {
+            continue; // Skip malformed candles
+        }
+
+        let timestamp = match candle[0].as_i64() {
+            Some(ts) => Utc.timestamp_millis_opt(ts).unwrap(),
+            None => continue,
+        };
+
+        let close_price = match candle[4].as_str() {
+            Some(price_str) => match price_str.parse::<Decimal>() {
+                Ok(price) => price,
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+
+        let parse = |value: &serde_json::Value| value.as_str().and_then(|v| v.parse().ok());
+
+        data_points.push(PriceHistoryPoint {
+            timestamp,
+            price: close_price,
+            open: parse(&candle[1]),
+            high: parse(&candle[2]),
+            low: parse(&candle[3]),
+            volume: parse(&candle[5]),
+        });
+    }
+
+    // Sort by timestamp (newest first)
+    data_points.sort_by_key(|point| std::cmp::Reverse(point.timestamp));
+    data_points
+}
+
 #[async_trait]
 impl ExchangeConnector for BinanceConnector {
     async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
         let symbol = self.format_symbol(pair);
-        let url = format!("{}/ticker/24hr", BINANCE_API_URL);
+        let url = format!("{}/ticker/24hr", self.base_url);
 
         debug!("Fetching 24hr ticker from Binance for {}", symbol);
 
         let response = self
-            .client
-            .get(&url)
-            .query(&[("symbol", &symbol)])
-            .send()
-            .await
-            .map_err(Error::HttpError)?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            error!("Binance API error: {} - {}", status, error_text);
-            return Err(Error::ExchangeError(format!(
-                "Binance API error: {} - {}",
-                status, error_text
-            )));
-        }
+            .http
+            .get(&url, &[("symbol", symbol.clone())])
+            .await?
+            .error_for_status("Binance")?;
 
-        let ticker: Binance24hTicker = response.json().await.map_err(|e| {
-            Error::ParseError(format!("Failed to parse Binance response: {}", e))
-        })?;
+        let ticker: Binance24hTicker = response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse Binance response: {}", e)))?;
 
         let price = ticker
             .last_price
-            .parse::<f64>()
+            .parse::<Decimal>()
             .map_err(|e| Error::ParseError(format!("Failed to parse price: {}", e)))?;
 
-        let volume = ticker
-            .volume
-            .parse::<f64>()
-            .ok()
-            .map(|v| v * price); // Convert to quote currency volume
-
         Ok(CurrentPrice {
             exchange: Exchange::Binance,
             pair: pair.clone(),
             price,
-            volume_24h: volume,
-            timestamp: Utc::now(),
+            bid: ticker.bid_price.parse().ok(),
+            ask: ticker.ask_price.parse().ok(),
+            open_24h: ticker.open_price.parse().ok(),
+            high_24h: ticker.high_price.parse().ok(),
+            low_24h: ticker.low_price.parse().ok(),
+            change_24h: ticker.price_change.parse().ok(),
+            change_percent_24h: ticker.price_change_percent.parse().ok(),
+            base_volume_24h: ticker.volume.parse().ok(),
+            volume_24h: ticker.quote_volume.parse().ok(),
+            // The ticker is as of the end of its rolling 24h window
+            timestamp: Utc
+                .timestamp_millis_opt(ticker.close_time)
+                .single()
+                .unwrap_or_else(Utc::now),
+            flags: Vec::new(),
         })
     }
 
@@ -113,11 +184,8 @@ impl ExchangeConnector for BinanceConnector {
         limit: Option<usize>,
     ) -> Result<PriceHistory> {
         let symbol = self.format_symbol(pair);
-        let url = format!("{}/klines", BINANCE_API_URL);
+        let url = format!("{}/klines", self.base_url);
 
-        let now = Utc::now();
-        let end = end_time.unwrap_or(now);
-        
         // Default to 1000 candles (Binance limit) if start time not provided
         let binance_limit = limit.unwrap_or(1000).min(1000); // Binance max limit is 1000
         
@@ -143,22 +211,10 @@ impl ExchangeConnector for BinanceConnector {
         );
 
         let response = self
-            .client
-            .get(&url)
-            .query(&params)
-            .send()
-            .await
-            .map_err(Error::HttpError)?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            error!("Binance API error: {} - {}", status, error_text);
-            return Err(Error::ExchangeError(format!(
-                "Binance API error: {} - {}",
-                status, error_text
-            )));
-        }
+            .http
+            .get(&url, &params)
+            .await?
+            .error_for_status("Binance")?;
 
         // Binance returns an array of arrays:
         // [
@@ -172,83 +228,143 @@ impl ExchangeConnector for BinanceConnector {
         //     ...                 // (more fields we don't need)
         //   ]
         // ]
-        let candles: Vec<Vec<serde_json::Value>> = response.json().await.map_err(|e| {
-            Error::ParseError(format!("Failed to parse Binance candles: {}", e))
-        })?;
+        let candles: Vec<Vec<serde_json::Value>> = response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse Binance candles: {}", e)))?;
 
-        let mut data_points = Vec::with_capacity(candles.len());
+        let data_points = parse_klines(candles);
 
-        for candle in candles {
-            if candle.len() < 6 {
-                continue; // Skip malformed candles
-            }
+        Ok(PriceHistory {
+            exchange: Exchange::Binance,
+            pair: pair.clone(),
+            interval: interval.into(),
+            data: data_points,
+        })
+    }
 
-            let timestamp = match candle[0].as_i64() {
-                Some(ts) => Utc.timestamp_millis_opt(ts).unwrap(),
-                None => continue,
-            };
+    async fn get_recent_trades(
+        &self,
+        pair: &TradingPair,
+        limit: Option<usize>,
+    ) -> Result<Vec<Trade>> {
+        let symbol = self.format_symbol(pair);
+        let url = format!("{}/trades", self.base_url);
 
-            let close_price = match candle[4].as_str() {
-                Some(price_str) => match price_str.parse::<f64>() {
-                    Ok(price) => price,
-                    Err(_) => continue,
-                },
-                None => continue,
-            };
+        // Binance defaults to 500 trades and allows at most 1000
+        let binance_limit = limit.unwrap_or(500).min(1000);
 
-            let volume = match candle[5].as_str() {
-                Some(vol_str) => match vol_str.parse::<f64>() {
-                    Ok(vol) => Some(vol),
-                    Err(_) => None,
-                },
-                None => None,
+        debug!(
+            "Fetching recent trades from Binance for {} (limit: {})",
+            symbol, binance_limit
+        );
+
+        let response = self
+            .http
+            .get(
+                &url,
+                &[("symbol", symbol), ("limit", binance_limit.to_string())],
+            )
+            .await?
+            .error_for_status("Binance")?;
+
+        let raw_trades: Vec<BinanceTrade> = response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse Binance trades: {}", e)))?;
+
+        let mut trades = Vec::with_capacity(raw_trades.len());
+
+        for raw in raw_trades {
+            let price = raw
+                .price
+                .parse::<Decimal>()
+                .map_err(|e| Error::ParseError(format!("Failed to parse trade price: {}", e)))?;
+            let size = raw
+                .qty
+                .parse::<Decimal>()
+                .map_err(|e| Error::ParseError(format!("Failed to parse trade size: {}", e)))?;
+            let timestamp = Utc.timestamp_millis_opt(raw.time).single().ok_or_else(|| {
+                Error::ParseError(format!("Invalid trade timestamp: {}", raw.time))
+            })?;
+
+            // A buyer acting as maker means the seller took liquidity
+            let side = if raw.is_buyer_maker {
+                TradeSide::Sell
+            } else {
+                TradeSide::Buy
             };
 
-            data_points.push(PriceHistoryPoint {
+            trades.push(Trade {
+                exchange: Exchange::Binance,
+                pair: pair.clone(),
+                trade_id: raw.id.to_string(),
+                price,
+                size,
+                side,
                 timestamp,
-                price: close_price,
-                volume,
             });
         }
 
-        // Sort by timestamp (newest first)
-        data_points.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        // Binance returns oldest first
+        trades.sort_by_key(|trade| std::cmp::Reverse(trade.timestamp));
 
-        Ok(PriceHistory {
-            exchange: Exchange::Binance,
-            pair: pair.clone(),
-            interval,
-            data: data_points,
-        })
+        Ok(trades)
     }
 
-    async fn list_trading_pairs(&self) -> Result<Vec<TradingPair>> {
-        let url = format!("{}/exchangeInfo", BINANCE_API_URL);
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let url = format!("{}/time", self.base_url);
+
+        let response = self
+            .http
+            .get(&url, &[])
+            .await?
+            .error_for_status("Binance")?;
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ServerTime {
+            server_time: i64,
+        }
+
+        let time: ServerTime = response.json().map_err(|e| {
+            Error::ParseError(format!("Failed to parse Binance server time: {}", e))
+        })?;
+
+        Utc.timestamp_millis_opt(time.server_time)
+            .single()
+            .ok_or_else(|| Error::ParseError(format!("Invalid server time: {}", time.server_time)))
+    }
+
+    async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+        let url = format!("{}/exchangeInfo", self.base_url);
 
         debug!("Fetching exchange info from Binance: {}", url);
 
         let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(Error::HttpError)?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            error!("Binance API error: {} - {}", status, error_text);
-            return Err(Error::ExchangeError(format!(
-                "Binance API error: {} - {}",
-                status, error_text
-            )));
+            .http
+            .get(&url, &[])
+            .await?
+            .error_for_status("Binance")?;
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Filter {
+            filter_type: String,
+            tick_size: Option<String>,
+            step_size: Option<String>,
+            min_qty: Option<String>,
+            max_qty: Option<String>,
+            min_notional: Option<String>,
         }
 
         #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
         struct Symbol {
             symbol: String,
-            baseAsset: String,
-            quoteAsset: String,
+            status: String,
+            base_asset: String,
+            quote_asset: String,
+            #[serde(default)]
+            filters: Vec<Filter>,
         }
 
         #[derive(Deserialize)]
@@ -256,19 +372,161 @@ impl ExchangeConnector for BinanceConnector {
             symbols: Vec<Symbol>,
         }
 
-        let info: ExchangeInfo = response.json().await.map_err(|e| {
+        let info: ExchangeInfo = response.json().map_err(|e| {
             Error::ParseError(format!("Failed to parse Binance exchange info: {}", e))
         })?;
 
-        let pairs = info
+        let parse = |value: &Option<String>| value.as_deref().and_then(|v| v.parse().ok());
+
+        let markets = info
             .symbols
             .into_iter()
-            .map(|symbol| TradingPair {
-                base: symbol.baseAsset,
-                quote: symbol.quoteAsset,
+            .map(|symbol| {
+                let mut market = MarketInfo {
+                    exchange: Exchange::Binance,
+                    pair: TradingPair {
+                        base: symbol.base_asset,
+                        quote: symbol.quote_asset,
+                    },
+                    symbol: symbol.symbol,
+                    // Anything but TRADING (BREAK, HALT, AUCTION_MATCH, ...) is not tradable
+                    status: match symbol.status.as_str() {
+                        "TRADING" => MarketStatus::Trading,
+                        _ => MarketStatus::Halted,
+                    },
+                    price_increment: None,
+                    size_increment: None,
+                    min_size: None,
+                    max_size: None,
+                    min_notional: None,
+                };
+
+                for filter in &symbol.filters {
+                    match filter.filter_type.as_str() {
+                        "PRICE_FILTER" => market.price_increment = parse(&filter.tick_size),
+                        "LOT_SIZE" => {
+                            market.size_increment = parse(&filter.step_size);
+                            market.min_size = parse(&filter.min_qty);
+                            market.max_size = parse(&filter.max_qty);
+                        }
+                        "MIN_NOTIONAL" | "NOTIONAL" => {
+                            market.min_notional = parse(&filter.min_notional)
+                        }
+                        _ => {}
+                    }
+                }
+
+                market
             })
             .collect();
 
-        Ok(pairs)
+        Ok(markets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::replay_fixtures;
+
+    fn connector() -> BinanceConnector {
+        BinanceConnector::with_base_url("http://binance.test/api/v3/")
+            .with_http(replay_fixtures("binance"))
+    }
+
+    fn btc_usdt() -> TradingPair {
+        TradingPair {
+            base: "BTC".to_string(),
+            quote: "USDT".to_string(),
+        }
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn current_price_from_24h_ticker() {
+        let price = connector().get_current_price(&btc_usdt()).await.unwrap();
+
+        assert_eq!(price.exchange, Exchange::Binance);
+        assert_eq!(price.price, decimal("61000.1"));
+        assert_eq!(price.bid, Some(decimal("61000")));
+        assert_eq!(price.ask, Some(decimal("61000.2")));
+        assert_eq!(price.open_24h, Some(decimal("60000")));
+        assert_eq!(price.high_24h, Some(decimal("62000")));
+        assert_eq!(price.low_24h, Some(decimal("59500.5")));
+        assert_eq!(price.change_24h, Some(decimal("1000.1")));
+        assert_eq!(price.change_percent_24h, Some(decimal("1.667")));
+        assert_eq!(price.base_volume_24h, Some(decimal("1234.5678")));
+        assert_eq!(price.volume_24h, Some(decimal("75308641.98")));
+        assert_eq!(price.timestamp.timestamp_millis(), 1709294400123);
+    }
+
+    #[tokio::test]
+    async fn error_responses_carry_their_status() {
+        let pair = TradingPair {
+            base: "NOPE".to_string(),
+            quote: "USDT".to_string(),
+        };
+
+        match connector().get_current_price(&pair).await {
+            Err(Error::ExchangeStatus { status, body, .. }) => {
+                assert_eq!(status, 400);
+                assert!(body.contains("Invalid symbol"));
+            }
+            other => panic!("Expected an error status, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn klines_newest_first() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 3, 1, 2, 0, 0).unwrap();
+        let history = connector()
+            .get_price_history(
+                &btc_usdt(),
+                PriceInterval::OneHour,
+                Some(start),
+                Some(end),
+                Some(3),
+            )
+            .await
+            .unwrap();
+
+        // The truncated candle is skipped
+        let timestamps: Vec<i64> = history
+            .data
+            .iter()
+            .map(|p| p.timestamp.timestamp())
+            .collect();
+        assert_eq!(timestamps, [1709254800, 1709251200]);
+
+        let latest = &history.data[0];
+        assert_eq!(latest.price, decimal("60950.5"));
+        assert_eq!(latest.open, Some(decimal("60400")));
+        assert_eq!(latest.high, Some(decimal("61000")));
+        assert_eq!(latest.low, Some(decimal("60300")));
+        assert_eq!(latest.volume, Some(decimal("12.25")));
+    }
+
+    #[tokio::test]
+    async fn trades_newest_first_with_taker_side() {
+        let trades = connector()
+            .get_recent_trades(&btc_usdt(), Some(2))
+            .await
+            .unwrap();
+
+        let summary: Vec<(&str, TradeSide)> = trades
+            .iter()
+            .map(|trade| (trade.trade_id.as_str(), trade.side))
+            .collect();
+        // A buyer acting as maker means the seller took liquidity
+        assert_eq!(
+            summary,
+            [("3500000", TradeSide::Buy), ("3499999", TradeSide::Sell)]
+        );
+        assert_eq!(trades[0].price, decimal("61000.1"));
+        assert_eq!(trades[0].size, decimal("0.012"));
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,362 @@
+use crate::binance::{binance_interval, parse_klines};
+use crate::http::HttpClient;
+use crate::DerivativesConnector;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use common::{
+    models::{
+        DerivativesExchange, FundingRate, FundingRateHistory, MarkPrice, OpenInterest,
+        PerpetualHistory, PriceInterval, TradingPair,
+    },
+    Error, Result,
+};
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tracing::debug;
+
+const BINANCE_FUTURES_API_URL: &str = "https://fapi.binance.com/fapi/v1";
+
+/// Connector for Binance USDⓈ-M perpetual futures
+pub struct BinanceFuturesConnector {
+    http: HttpClient,
+    base_url: String,
+}
+
+impl Default for BinanceFuturesConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinanceFuturesConnector {
+    pub fn new() -> Self {
+        Self::with_base_url(BINANCE_FUTURES_API_URL)
+    }
+
+    /// Create a connector for another host serving the futures API, such as
+    /// the testnet
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            http: HttpClient::default(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Send requests through a client, e.g. one recording or replaying them
+    pub fn with_http(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    fn format_symbol(&self, pair: &TradingPair) -> String {
+        format!("{}{}", pair.base, pair.quote)
+    }
+
+    // Send a GET request to an endpoint and parse the JSON response
+    async fn get<T: DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        params: &[(&str, String)],
+    ) -> Result<T> {
+        let url = format!("{}/{}", self.base_url, endpoint);
+
+        let response = self
+            .http
+            .get(&url, params)
+            .await?
+            .error_for_status("Binance futures")?;
+
+        response.json().map_err(|e| {
+            Error::ParseError(format!(
+                "Failed to parse Binance futures {} response: {}",
+                endpoint, e
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinancePremiumIndex {
+    mark_price: String,
+    index_price: String,
+    estimated_settle_price: Option<String>,
+    last_funding_rate: Option<String>,
+    interest_rate: Option<String>,
+    next_funding_time: Option<i64>,
+    time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceOpenInterest {
+    open_interest: String,
+    time: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BinanceFundingRate {
+    funding_rate: String,
+    funding_time: i64,
+    #[serde(default)]
+    mark_price: Option<String>,
+}
+
+fn parse_price(value: &str, field: &str) -> Result<Decimal> {
+    value
+        .parse::<Decimal>()
+        .map_err(|e| Error::ParseError(format!("Failed to parse {}: {}", field, e)))
+}
+
+fn timestamp(millis: i64) -> Result<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(millis)
+        .single()
+        .ok_or_else(|| Error::ParseError(format!("Invalid timestamp: {}", millis)))
+}
+
+#[async_trait]
+impl DerivativesConnector for BinanceFuturesConnector {
+    async fn get_mark_price(&self, pair: &TradingPair) -> Result<MarkPrice> {
+        let symbol = self.format_symbol(pair);
+
+        debug!("Fetching premium index from Binance futures for {}", symbol);
+
+        let index: BinancePremiumIndex = self.get("premiumIndex", &[("symbol", symbol)]).await?;
+
+        // Optional fields are reported as empty strings for some contracts
+        let optional = |value: &Option<String>| value.as_deref().and_then(|v| v.parse().ok());
+
+        Ok(MarkPrice {
+            exchange: DerivativesExchange::BinanceUsdm,
+            pair: pair.clone(),
+            mark_price: parse_price(&index.mark_price, "mark price")?,
+            index_price: parse_price(&index.index_price, "index price")?,
+            estimated_settle_price: optional(&index.estimated_settle_price),
+            last_funding_rate: optional(&index.last_funding_rate),
+            next_funding_time: index
+                .next_funding_time
+                .filter(|t| *t > 0)
+                .and_then(|t| timestamp(t).ok()),
+            interest_rate: optional(&index.interest_rate),
+            timestamp: timestamp(index.time)?,
+        })
+    }
+
+    async fn get_open_interest(&self, pair: &TradingPair) -> Result<OpenInterest> {
+        let symbol = self.format_symbol(pair);
+
+        debug!("Fetching open interest from Binance futures for {}", symbol);
+
+        let interest: BinanceOpenInterest = self.get("openInterest", &[("symbol", symbol)]).await?;
+
+        Ok(OpenInterest {
+            exchange: DerivativesExchange::BinanceUsdm,
+            pair: pair.clone(),
+            open_interest: parse_price(&interest.open_interest, "open interest")?,
+            open_interest_value: None,
+            timestamp: timestamp(interest.time)?,
+        })
+    }
+
+    async fn get_funding_rates(
+        &self,
+        pair: &TradingPair,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<FundingRateHistory> {
+        let symbol = self.format_symbol(pair);
+
+        // Binance returns at most 1000 funding payments
+        let mut params = vec![
+            ("symbol", symbol),
+            ("limit", limit.unwrap_or(100).min(1000).to_string()),
+        ];
+        if let Some(start) = start_time {
+            params.push(("startTime", start.timestamp_millis().to_string()));
+        }
+        if let Some(end) = end_time {
+            params.push(("endTime", end.timestamp_millis().to_string()));
+        }
+
+        debug!("Fetching funding rates from Binance futures: {:?}", params);
+
+        let rates: Vec<BinanceFundingRate> = self.get("fundingRate", &params).await?;
+
+        let mut data = Vec::with_capacity(rates.len());
+        for rate in rates {
+            data.push(FundingRate {
+                funding_time: timestamp(rate.funding_time)?,
+                funding_rate: parse_price(&rate.funding_rate, "funding rate")?,
+                mark_price: rate.mark_price.and_then(|p| p.parse().ok()),
+            });
+        }
+
+        // Sort by funding time (newest first)
+        data.sort_by_key(|rate| std::cmp::Reverse(rate.funding_time));
+
+        Ok(FundingRateHistory {
+            exchange: DerivativesExchange::BinanceUsdm,
+            pair: pair.clone(),
+            data,
+        })
+    }
+
+    async fn get_perpetual_history(
+        &self,
+        pair: &TradingPair,
+        interval: PriceInterval,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PerpetualHistory> {
+        let symbol = self.format_symbol(pair);
+
+        // Binance returns at most 1500 futures candles
+        let mut params = vec![
+            ("symbol", symbol),
+            ("interval", binance_interval(interval).to_string()),
+            ("limit", limit.unwrap_or(500).min(1500).to_string()),
+        ];
+        if let Some(start) = start_time {
+            params.push(("startTime", start.timestamp_millis().to_string()));
+        }
+        if let Some(end) = end_time {
+            params.push(("endTime", end.timestamp_millis().to_string()));
+        }
+
+        debug!(
+            "Fetching perpetual klines from Binance futures: {:?}",
+            params
+        );
+
+        let candles: Vec<Vec<serde_json::Value>> = self.get("klines", &params).await?;
+
+        Ok(PerpetualHistory {
+            exchange: DerivativesExchange::BinanceUsdm,
+            pair: pair.clone(),
+            interval: interval.into(),
+            data: parse_klines(candles),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::replay_fixtures;
+
+    fn connector() -> BinanceFuturesConnector {
+        BinanceFuturesConnector::with_base_url("http://binance.test/fapi/v1/")
+            .with_http(replay_fixtures("binance_futures"))
+    }
+
+    fn usdt(base: &str) -> TradingPair {
+        TradingPair {
+            base: base.to_string(),
+            quote: "USDT".to_string(),
+        }
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn mark_price_from_premium_index() {
+        let mark = connector().get_mark_price(&usdt("BTC")).await.unwrap();
+
+        assert_eq!(mark.exchange, DerivativesExchange::BinanceUsdm);
+        assert_eq!(mark.mark_price, decimal("61010.5"));
+        assert_eq!(mark.index_price, decimal("61000.25"));
+        assert_eq!(mark.estimated_settle_price, Some(decimal("61005")));
+        assert_eq!(mark.last_funding_rate, Some(decimal("0.0001")));
+        assert_eq!(mark.interest_rate, Some(decimal("0.0001")));
+        assert_eq!(
+            mark.next_funding_time.map(|t| t.timestamp_millis()),
+            Some(1709308800000)
+        );
+        assert_eq!(mark.timestamp.timestamp_millis(), 1709294400000);
+    }
+
+    #[tokio::test]
+    async fn empty_premium_index_fields_are_missing() {
+        let mark = connector().get_mark_price(&usdt("ETH")).await.unwrap();
+
+        assert_eq!(mark.mark_price, decimal("3400.1"));
+        assert_eq!(mark.estimated_settle_price, None);
+        assert_eq!(mark.last_funding_rate, None);
+        assert_eq!(mark.interest_rate, None);
+        assert_eq!(mark.next_funding_time, None);
+    }
+
+    #[tokio::test]
+    async fn open_interest_in_contracts() {
+        let interest = connector().get_open_interest(&usdt("BTC")).await.unwrap();
+
+        assert_eq!(interest.open_interest, decimal("80000.5"));
+        // Valued by the service, which knows the mark price
+        assert_eq!(interest.open_interest_value, None);
+        assert_eq!(interest.timestamp.timestamp_millis(), 1709294400000);
+    }
+
+    #[tokio::test]
+    async fn funding_rates_newest_first() {
+        let history = connector()
+            .get_funding_rates(&usdt("BTC"), None, None, Some(2))
+            .await
+            .unwrap();
+
+        let rates: Vec<(i64, Decimal, Option<Decimal>)> = history
+            .data
+            .iter()
+            .map(|rate| {
+                (
+                    rate.funding_time.timestamp_millis(),
+                    rate.funding_rate,
+                    rate.mark_price,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rates,
+            [
+                (1709280000000, decimal("-0.00005"), None),
+                (1709251200000, decimal("0.0001"), Some(decimal("60000.1"))),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn perpetual_klines_newest_first() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 3, 1, 2, 0, 0).unwrap();
+        let history = connector()
+            .get_perpetual_history(
+                &usdt("BTC"),
+                PriceInterval::OneHour,
+                Some(start),
+                Some(end),
+                Some(3),
+            )
+            .await
+            .unwrap();
+
+        let closes: Vec<(i64, Decimal)> = history
+            .data
+            .iter()
+            .map(|point| (point.timestamp.timestamp(), point.price))
+            .collect();
+        assert_eq!(
+            closes,
+            [
+                (1709258400, decimal("61050")),
+                (1709254800, decimal("60940.5")),
+                (1709251200, decimal("60430")),
+            ]
+        );
+        assert_eq!(history.data[1].volume, Some(decimal("250.25")));
+    }
+}
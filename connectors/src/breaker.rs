@@ -0,0 +1,623 @@
+use crate::ExchangeConnector;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use common::{
+    models::{
+        CircuitState, ConnectorHealth, CurrentPrice, LatencyPercentiles, MarketInfo, PriceHistory,
+        PriceInterval, Trade, TradingPair,
+    },
+    Error, Result,
+};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+// Number of recent request latencies kept for percentiles
+const LATENCY_SAMPLES: usize = 100;
+
+/// Settings of a circuit breaker
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitBreakerConfig {
+    /// Fraction of failed requests in the window that opens the circuit
+    pub failure_rate: f64,
+    /// Number of recent requests the failure rate is computed over
+    pub window: usize,
+    /// Minimum number of requests in the window before the circuit can open
+    pub min_calls: usize,
+    /// Requests slower than this count as failures, in milliseconds
+    pub slow_call_ms: u64,
+    /// Requests are abandoned, and count as failures, after this many milliseconds
+    pub timeout_ms: u64,
+    /// How long the circuit stays open before probing the exchange again
+    pub open_secs: u64,
+    /// Number of trial requests let through while half-open
+    pub half_open_calls: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate: 0.5,
+            window: 20,
+            min_calls: 5,
+            slow_call_ms: 5_000,
+            timeout_ms: 10_000,
+            open_secs: 30,
+            half_open_calls: 1,
+        }
+    }
+}
+
+impl CircuitBreakerConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let env_count = |name: &str| {
+            env(name)
+                .and_then(|v| v.parse::<usize>().ok())
+                .filter(|v| *v > 0)
+        };
+        let env_u64 = |name: &str| {
+            env(name)
+                .and_then(|v| v.parse::<u64>().ok())
+                .filter(|v| *v > 0)
+        };
+
+        Self {
+            failure_rate: env("BREAKER_FAILURE_RATE")
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v > 0.0 && *v <= 1.0)
+                .unwrap_or(default.failure_rate),
+            window: env_count("BREAKER_WINDOW").unwrap_or(default.window),
+            min_calls: env_count("BREAKER_MIN_CALLS").unwrap_or(default.min_calls),
+            slow_call_ms: env_u64("BREAKER_SLOW_CALL_MS").unwrap_or(default.slow_call_ms),
+            timeout_ms: env_u64("BREAKER_TIMEOUT_MS").unwrap_or(default.timeout_ms),
+            open_secs: env_u64("BREAKER_OPEN_SECS").unwrap_or(default.open_secs),
+            half_open_calls: env_count("BREAKER_HALF_OPEN_CALLS")
+                .unwrap_or(default.half_open_calls),
+        }
+    }
+}
+
+// Mutable state of a circuit breaker
+struct BreakerState {
+    state: CircuitState,
+    state_since: DateTime<Utc>,
+    opened_at: Option<Instant>,
+    // Recent request outcomes, true for failures
+    outcomes: VecDeque<bool>,
+    // Recent request latencies in milliseconds
+    latencies: VecDeque<u64>,
+    // Trial requests in flight while half-open
+    trials: usize,
+    // Incremented on every state change, so requests admitted in an earlier
+    // state can be told apart
+    generation: u64,
+    last_error: Option<String>,
+    last_error_at: Option<DateTime<Utc>>,
+}
+
+impl BreakerState {
+    fn transition(&mut self, state: CircuitState) {
+        self.state = state;
+        self.state_since = Utc::now();
+        self.opened_at = (state == CircuitState::Open).then(Instant::now);
+        self.trials = 0;
+        self.generation += 1;
+        if state == CircuitState::Closed {
+            self.outcomes.clear();
+        }
+    }
+
+    fn failure_rate(&self) -> f64 {
+        if self.outcomes.is_empty() {
+            return 0.0;
+        }
+        let failures = self.outcomes.iter().filter(|failed| **failed).count();
+        failures as f64 / self.outcomes.len() as f64
+    }
+}
+
+// Whether an error means the exchange is unhealthy, as opposed to a request
+// it rightly rejected (e.g. an unlisted pair)
+fn is_failure(error: &Error) -> bool {
+    match error {
+        Error::HttpError(_) | Error::ExchangeError(_) => true,
+        Error::ExchangeStatus { status, .. } => *status >= 500 || *status == 429 || *status == 418,
+        _ => false,
+    }
+}
+
+// A request let through the breaker. Its outcome is recorded by `finish`; if
+// it is dropped first, e.g. because the caller went away, a half-open trial
+// is given back so it does not hold the circuit half-open for good.
+struct Admission<'a> {
+    breaker: &'a CircuitBreakerConnector,
+    // Generation of the half-open state the request is a trial of
+    trial: Option<u64>,
+    started: Instant,
+    finished: bool,
+}
+
+impl Admission<'_> {
+    fn finish(mut self, error: Option<String>) {
+        self.finished = true;
+        self.breaker
+            .record(self.trial, self.started.elapsed(), error);
+    }
+}
+
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        if let Some(generation) = self.trial {
+            self.breaker.release(generation);
+        }
+    }
+}
+
+// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Exchange connector that fails fast while the exchange it wraps is unhealthy.
+///
+/// The circuit opens when the failure rate over recent requests, counting
+/// timeouts and slow responses as failures, reaches the configured threshold.
+/// While open, requests fail immediately. Once the open period has passed,
+/// the circuit lets a few trial requests through: it closes if they succeed
+/// and opens again if they fail.
+pub struct CircuitBreakerConnector {
+    name: String,
+    inner: Arc<dyn ExchangeConnector>,
+    config: CircuitBreakerConfig,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreakerConnector {
+    pub fn new(
+        name: impl Into<String>,
+        inner: Arc<dyn ExchangeConnector>,
+        config: CircuitBreakerConfig,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+            config,
+            state: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                state_since: Utc::now(),
+                opened_at: None,
+                outcomes: VecDeque::new(),
+                latencies: VecDeque::new(),
+                trials: 0,
+                generation: 0,
+                last_error: None,
+                last_error_at: None,
+            }),
+        }
+    }
+
+    // Admit a request, tracking whether it is a half-open trial
+    fn admit(&self) -> Result<Admission<'_>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if state.state == CircuitState::Open {
+            let open_for = Duration::from_secs(self.config.open_secs);
+            let elapsed = state.opened_at.map_or(open_for, |opened| opened.elapsed());
+            if elapsed < open_for {
+                return Err(Error::ExchangeError(format!(
+                    "{} circuit breaker is open, retrying in {}s",
+                    self.name,
+                    (open_for - elapsed).as_secs().max(1)
+                )));
+            }
+            info!(
+                "{} circuit breaker is half-open, probing the exchange",
+                self.name
+            );
+            state.transition(CircuitState::HalfOpen);
+        }
+
+        if state.state == CircuitState::HalfOpen {
+            if state.trials >= self.config.half_open_calls.max(1) {
+                return Err(Error::ExchangeError(format!(
+                    "{} circuit breaker is half-open, waiting for trial requests",
+                    self.name
+                )));
+            }
+            state.trials += 1;
+        }
+
+        Ok(Admission {
+            breaker: self,
+            trial: (state.state == CircuitState::HalfOpen).then_some(state.generation),
+            started: Instant::now(),
+            finished: false,
+        })
+    }
+
+    // Give back the slot of a half-open trial that ended without an outcome
+    fn release(&self, generation: u64) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.generation == generation {
+            state.trials = state.trials.saturating_sub(1);
+        }
+    }
+
+    // Record the outcome of an admitted request
+    fn record(&self, trial: Option<u64>, latency: Duration, error: Option<String>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let latency_ms = latency.as_millis() as u64;
+
+        state.latencies.push_back(latency_ms);
+        if state.latencies.len() > LATENCY_SAMPLES {
+            state.latencies.pop_front();
+        }
+
+        let error = error.or_else(|| {
+            (latency_ms > self.config.slow_call_ms)
+                .then(|| format!("Slow response: {} ms", latency_ms))
+        });
+        let failed = error.is_some();
+        if let Some(error) = error {
+            state.last_error = Some(error);
+            state.last_error_at = Some(Utc::now());
+        }
+
+        match state.state {
+            CircuitState::HalfOpen if trial == Some(state.generation) => {
+                if failed {
+                    warn!("{} circuit breaker trial failed, opening again", self.name);
+                    state.transition(CircuitState::Open);
+                } else {
+                    info!("{} circuit breaker trial succeeded, closing", self.name);
+                    state.transition(CircuitState::Closed);
+                }
+            }
+            CircuitState::Closed => {
+                state.outcomes.push_back(failed);
+                if state.outcomes.len() > self.config.window.max(1) {
+                    state.outcomes.pop_front();
+                }
+
+                let failure_rate = state.failure_rate();
+                if state.outcomes.len() >= self.config.min_calls
+                    && failure_rate >= self.config.failure_rate
+                {
+                    warn!(
+                        "{} circuit breaker opening: {:.0}% of the last {} requests failed",
+                        self.name,
+                        failure_rate * 100.0,
+                        state.outcomes.len()
+                    );
+                    state.transition(CircuitState::Open);
+                }
+            }
+            // Requests admitted before the state changed don't affect it
+            _ => {}
+        }
+    }
+
+    // Run a request through the breaker
+    async fn call<T>(&self, request: impl Future<Output = Result<T>>) -> Result<T> {
+        let admission = self.admit()?;
+
+        let timeout = Duration::from_millis(self.config.timeout_ms);
+        let result = match tokio::time::timeout(timeout, request).await {
+            Ok(result) => result,
+            Err(_) => Err(Error::ExchangeError(format!(
+                "{} request timed out after {} ms",
+                self.name, self.config.timeout_ms
+            ))),
+        };
+
+        let error = match &result {
+            Err(e) if is_failure(e) => Some(e.to_string()),
+            _ => None,
+        };
+        admission.finish(error);
+
+        result
+    }
+}
+
+#[async_trait]
+impl ExchangeConnector for CircuitBreakerConnector {
+    async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
+        self.call(self.inner.get_current_price(pair)).await
+    }
+
+    async fn get_price_history(
+        &self,
+        pair: &TradingPair,
+        interval: PriceInterval,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PriceHistory> {
+        self.call(
+            self.inner
+                .get_price_history(pair, interval, start_time, end_time, limit),
+        )
+        .await
+    }
+
+    async fn get_recent_trades(
+        &self,
+        pair: &TradingPair,
+        limit: Option<usize>,
+    ) -> Result<Vec<Trade>> {
+        self.call(self.inner.get_recent_trades(pair, limit)).await
+    }
+
+    async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+        self.call(self.inner.list_markets()).await
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        self.call(self.inner.get_server_time()).await
+    }
+
+    fn health(&self) -> Option<ConnectorHealth> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut latencies: Vec<u64> = state.latencies.iter().copied().collect();
+        latencies.sort_unstable();
+
+        Some(ConnectorHealth {
+            state: state.state,
+            state_since: state.state_since,
+            failure_rate: state.failure_rate(),
+            window_calls: state.outcomes.len(),
+            last_error: state.last_error.clone(),
+            last_error_at: state.last_error_at,
+            latency: LatencyPercentiles {
+                samples: latencies.len(),
+                p50_ms: percentile(&latencies, 0.5),
+                p90_ms: percentile(&latencies, 0.9),
+                p99_ms: percentile(&latencies, 0.99),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::models::Exchange;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // How the stub exchange answers price requests
+    #[derive(Clone, Copy)]
+    enum Response {
+        Ok,
+        Status(u16),
+        Slow,
+        Hang,
+    }
+
+    struct StubExchange {
+        response: Mutex<Response>,
+        calls: AtomicUsize,
+    }
+
+    impl StubExchange {
+        fn respond(&self, response: Response) {
+            *self.response.lock().unwrap() = response;
+        }
+    }
+
+    #[async_trait]
+    impl ExchangeConnector for StubExchange {
+        async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let response = *self.response.lock().unwrap();
+            match response {
+                Response::Ok => {}
+                Response::Status(status) => {
+                    return Err(Error::ExchangeStatus {
+                        api: "Stub".to_string(),
+                        status,
+                        body: String::new(),
+                    })
+                }
+                Response::Slow => tokio::time::sleep(Duration::from_millis(30)).await,
+                Response::Hang => std::future::pending::<()>().await,
+            }
+
+            Ok(CurrentPrice {
+                exchange: Exchange::Coinbase,
+                pair: pair.clone(),
+                price: "100".parse().unwrap(),
+                bid: None,
+                ask: None,
+                open_24h: None,
+                high_24h: None,
+                low_24h: None,
+                change_24h: None,
+                change_percent_24h: None,
+                base_volume_24h: None,
+                volume_24h: None,
+                timestamp: Utc::now(),
+                flags: Vec::new(),
+            })
+        }
+
+        async fn get_price_history(
+            &self,
+            _pair: &TradingPair,
+            _interval: PriceInterval,
+            _start_time: Option<DateTime<Utc>>,
+            _end_time: Option<DateTime<Utc>>,
+            _limit: Option<usize>,
+        ) -> Result<PriceHistory> {
+            Err(Error::NotFound("history".to_string()))
+        }
+
+        async fn get_recent_trades(
+            &self,
+            _pair: &TradingPair,
+            _limit: Option<usize>,
+        ) -> Result<Vec<Trade>> {
+            Err(Error::NotFound("trades".to_string()))
+        }
+
+        async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+            Ok(Vec::new())
+        }
+
+        async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+            Ok(Utc::now())
+        }
+    }
+
+    fn btc_usd() -> TradingPair {
+        TradingPair {
+            base: "BTC".to_string(),
+            quote: "USD".to_string(),
+        }
+    }
+
+    // Breaker opening once half of the last four requests failed
+    fn breaker(open_secs: u64) -> (Arc<StubExchange>, CircuitBreakerConnector) {
+        let stub = Arc::new(StubExchange {
+            response: Mutex::new(Response::Ok),
+            calls: AtomicUsize::new(0),
+        });
+        let config = CircuitBreakerConfig {
+            failure_rate: 0.5,
+            window: 4,
+            min_calls: 4,
+            slow_call_ms: 10,
+            timeout_ms: 1_000,
+            open_secs,
+            half_open_calls: 1,
+        };
+        let breaker = CircuitBreakerConnector::new("Stub", stub.clone(), config);
+        (stub, breaker)
+    }
+
+    fn state(breaker: &CircuitBreakerConnector) -> CircuitState {
+        breaker.health().unwrap().state
+    }
+
+    async fn call(breaker: &CircuitBreakerConnector) -> Result<CurrentPrice> {
+        breaker.get_current_price(&btc_usd()).await
+    }
+
+    // Open the circuit with failing requests
+    async fn open(stub: &StubExchange, breaker: &CircuitBreakerConnector) {
+        stub.respond(Response::Status(500));
+        for _ in 0..4 {
+            assert!(call(breaker).await.is_err());
+        }
+        assert_eq!(state(breaker), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn opens_on_failure_rate() {
+        let (stub, breaker) = breaker(60);
+
+        // Rejected requests say nothing about the exchange's health
+        stub.respond(Response::Status(404));
+        for _ in 0..4 {
+            assert!(call(&breaker).await.is_err());
+        }
+        assert_eq!(state(&breaker), CircuitState::Closed);
+
+        stub.respond(Response::Ok);
+        call(&breaker).await.unwrap();
+        call(&breaker).await.unwrap();
+        stub.respond(Response::Status(503));
+        assert!(call(&breaker).await.is_err());
+        assert_eq!(state(&breaker), CircuitState::Closed);
+        assert!(call(&breaker).await.is_err());
+        assert_eq!(state(&breaker), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn opens_on_slow_call_rate() {
+        let (stub, breaker) = breaker(60);
+
+        stub.respond(Response::Slow);
+        for _ in 0..4 {
+            call(&breaker).await.unwrap();
+        }
+        assert_eq!(state(&breaker), CircuitState::Open);
+        assert!(breaker
+            .health()
+            .unwrap()
+            .last_error
+            .unwrap()
+            .starts_with("Slow response"));
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_while_open() {
+        let (stub, breaker) = breaker(60);
+        open(&stub, &breaker).await;
+
+        stub.respond(Response::Ok);
+        let calls = stub.calls.load(Ordering::SeqCst);
+        let error = call(&breaker).await.unwrap_err().to_string();
+        assert!(error.contains("circuit breaker is open"), "{}", error);
+        assert_eq!(stub.calls.load(Ordering::SeqCst), calls);
+        assert_eq!(state(&breaker), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn half_open_trial_success_closes() {
+        let (stub, breaker) = breaker(0);
+        open(&stub, &breaker).await;
+
+        // Once the open period has passed, the next request is a trial
+        stub.respond(Response::Ok);
+        call(&breaker).await.unwrap();
+        assert_eq!(state(&breaker), CircuitState::Closed);
+        assert_eq!(breaker.health().unwrap().window_calls, 0);
+    }
+
+    #[tokio::test]
+    async fn half_open_trial_failure_opens_again() {
+        let (stub, breaker) = breaker(0);
+        open(&stub, &breaker).await;
+
+        stub.respond(Response::Status(502));
+        assert!(call(&breaker).await.is_err());
+        assert_eq!(state(&breaker), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn cancelled_trial_is_released() {
+        let (stub, breaker) = breaker(0);
+        open(&stub, &breaker).await;
+
+        // Start a trial that never completes, as if the client went away
+        stub.respond(Response::Hang);
+        let pair = btc_usd();
+        let mut trial = Box::pin(breaker.get_current_price(&pair));
+        assert!(tokio::time::timeout(Duration::from_millis(10), &mut trial)
+            .await
+            .is_err());
+        assert_eq!(state(&breaker), CircuitState::HalfOpen);
+
+        // Only one trial is let through at a time
+        stub.respond(Response::Ok);
+        let error = call(&breaker).await.unwrap_err().to_string();
+        assert!(error.contains("waiting for trial requests"), "{}", error);
+
+        drop(trial);
+        call(&breaker).await.unwrap();
+        assert_eq!(state(&breaker), CircuitState::Closed);
+    }
+}
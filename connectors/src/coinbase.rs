@@ -1,59 +1,115 @@
+use crate::http::HttpClient;
 use crate::ExchangeConnector;
 use async_trait::async_trait;
 use chrono::{DateTime, Duration, TimeZone, Utc};
 use common::{
-    models::{CurrentPrice, Exchange, PriceHistory, PriceHistoryPoint, PriceInterval, TradingPair},
+    models::{
+        CurrentPrice, Exchange, MarketInfo, MarketStatus, PriceHistory, PriceHistoryPoint,
+        PriceInterval, Trade, TradeSide, TradingPair,
+    },
     Error, Result,
 };
-use serde::{Deserialize, Serialize};
-use tracing::{debug, error, info};
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::debug;
 
-const COINBASE_API_URL: &str = "https://api.coinbase.com/v2";
-const COINBASE_PRO_API_URL: &str = "https://api.exchange.coinbase.com";
+pub(crate) const COINBASE_PRO_API_URL: &str = "https://api.exchange.coinbase.com";
 
 pub struct CoinbaseConnector {
-    client: reqwest::Client,
+    http: HttpClient,
+    base_url: String,
+}
+
+impl Default for CoinbaseConnector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl CoinbaseConnector {
     pub fn new() -> Self {
+        Self::with_base_url(COINBASE_PRO_API_URL)
+    }
+
+    /// Create a connector for another host serving the Exchange API, such as
+    /// the sandbox
+    pub fn with_base_url(base_url: &str) -> Self {
         Self {
-            client: reqwest::Client::new(),
+            http: HttpClient::default(),
+            base_url: base_url.trim_end_matches('/').to_string(),
         }
     }
 
+    /// Send requests through a client, e.g. one recording or replaying them
+    pub fn with_http(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
     fn format_product_id(&self, pair: &TradingPair) -> String {
         format!("{}-{}", pair.base, pair.quote)
     }
-}
 
-#[derive(Debug, Deserialize)]
-struct CoinbaseResponse<T> {
-    data: T,
+    // Fetch and decode a JSON document from the Exchange API
+    async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T> {
+        let response = self
+            .http
+            .get(url, &[])
+            .await?
+            .error_for_status("Coinbase")?;
+
+        response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse Coinbase response: {}", e)))
+    }
 }
 
 #[derive(Debug, Deserialize)]
-struct CoinbaseSpotPrice {
-    base: String,
-    currency: String,
-    amount: String,
+struct CoinbaseTicker {
+    price: String,
+    bid: String,
+    ask: String,
+    #[serde(default)]
+    time: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Deserialize)]
-struct CoinbaseCandle {
-    time: i64,
-    low: String,
-    high: String,
+struct CoinbaseStats {
     open: String,
-    close: String,
+    high: String,
+    low: String,
     volume: String,
 }
 
-#[derive(Debug, Serialize)]
-struct CoinbaseHistoricalParams {
-    start: Option<String>,
-    end: Option<String>,
-    granularity: Option<u32>,
+#[derive(Debug, Deserialize)]
+struct CoinbaseTime {
+    iso: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CoinbaseTrade {
+    trade_id: u64,
+    price: String,
+    size: String,
+    time: DateTime<Utc>,
+    side: String,
+}
+
+// Read a candle value reported as a JSON number or string. Numbers are
+// decoded by serde_json as f64, whose shortest representation is what
+// Coinbase sent for the precisions it uses.
+fn candle_value(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::String(value) => value.parse().ok(),
+        serde_json::Value::Number(number) => {
+            let value = number.to_string();
+            value
+                .parse()
+                .or_else(|_| Decimal::from_scientific(&value))
+                .ok()
+        }
+        _ => None,
+    }
 }
 
 // Convert PriceInterval to Coinbase granularity (seconds)
@@ -72,47 +128,49 @@ fn coinbase_granularity(interval: PriceInterval) -> u32 {
 #[async_trait]
 impl ExchangeConnector for CoinbaseConnector {
     async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
-        let url = format!(
-            "{}/prices/{}-{}/spot",
-            COINBASE_API_URL, pair.base, pair.quote
-        );
-
-        debug!("Fetching current price from Coinbase: {}", url);
+        let product_id = self.format_product_id(pair);
+        let ticker_url = format!("{}/products/{}/ticker", self.base_url, product_id);
+        let stats_url = format!("{}/products/{}/stats", self.base_url, product_id);
 
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(Error::HttpError)?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            error!("Coinbase API error: {} - {}", status, error_text);
-            return Err(Error::ExchangeError(format!(
-                "Coinbase API error: {} - {}",
-                status, error_text
-            )));
-        }
+        debug!(
+            "Fetching ticker and 24h stats from Coinbase for {}",
+            product_id
+        );
 
-        let price_data: CoinbaseResponse<CoinbaseSpotPrice> =
-            response.json().await.map_err(|e| {
-                Error::ParseError(format!("Failed to parse Coinbase response: {}", e))
-            })?;
+        let (ticker, stats) = tokio::join!(
+            self.get_json::<CoinbaseTicker>(&ticker_url),
+            self.get_json::<CoinbaseStats>(&stats_url)
+        );
+        let ticker = ticker?;
+        let stats = stats?;
 
-        let price = price_data
-            .data
-            .amount
-            .parse::<f64>()
+        let price = ticker
+            .price
+            .parse::<Decimal>()
             .map_err(|e| Error::ParseError(format!("Failed to parse price: {}", e)))?;
 
+        let open_24h = stats.open.parse::<Decimal>().ok();
+        let change_24h = open_24h.map(|open| price - open);
+        // Percentages are rounded to three places, as Binance reports them
+        let change_percent_24h = open_24h
+            .filter(|open| !open.is_zero())
+            .map(|open| ((price - open) / open * Decimal::ONE_HUNDRED).round_dp(3));
+
         Ok(CurrentPrice {
             exchange: Exchange::Coinbase,
             pair: pair.clone(),
             price,
-            volume_24h: None, // Coinbase spot API doesn't provide volume
-            timestamp: Utc::now(),
+            bid: ticker.bid.parse().ok(),
+            ask: ticker.ask.parse().ok(),
+            open_24h,
+            high_24h: stats.high.parse().ok(),
+            low_24h: stats.low.parse().ok(),
+            change_24h,
+            change_percent_24h,
+            base_volume_24h: stats.volume.parse().ok(),
+            volume_24h: None, // Coinbase only reports volume in base currency
+            timestamp: ticker.time.unwrap_or_else(Utc::now),
+            flags: Vec::new(),
         })
     }
 
@@ -126,7 +184,7 @@ impl ExchangeConnector for CoinbaseConnector {
     ) -> Result<PriceHistory> {
         // Use Coinbase Pro/Exchange API for historical data
         let product_id = self.format_product_id(pair);
-        let url = format!("{}/products/{}/candles", COINBASE_PRO_API_URL, product_id);
+        let url = format!("{}/products/{}/candles", self.base_url, product_id);
 
         // Set default time range if not provided
         let end = end_time.unwrap_or_else(Utc::now);
@@ -141,32 +199,22 @@ impl ExchangeConnector for CoinbaseConnector {
             url, interval, start, end
         );
 
+        let params = [
+            ("start", start.to_rfc3339()),
+            ("end", end.to_rfc3339()),
+            ("granularity", granularity.to_string()),
+        ];
+
         let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("start", start.to_rfc3339()),
-                ("end", end.to_rfc3339()),
-                ("granularity", granularity.to_string()),
-            ])
-            .send()
-            .await
-            .map_err(Error::HttpError)?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            error!("Coinbase API error: {} - {}", status, error_text);
-            return Err(Error::ExchangeError(format!(
-                "Coinbase API error: {} - {}",
-                status, error_text
-            )));
-        }
+            .http
+            .get(&url, &params)
+            .await?
+            .error_for_status("Coinbase")?;
 
         // Coinbase returns an array of arrays: [time, low, high, open, close, volume]
-        let candles: Vec<Vec<serde_json::Value>> = response.json().await.map_err(|e| {
-            Error::ParseError(format!("Failed to parse Coinbase candles: {}", e))
-        })?;
+        let candles: Vec<Vec<serde_json::Value>> = response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse Coinbase candles: {}", e)))?;
 
         let mut data_points = Vec::with_capacity(candles.len());
 
@@ -180,34 +228,24 @@ impl ExchangeConnector for CoinbaseConnector {
                 None => continue,
             };
 
-            let close_price = match candle[4].as_str() {
-                Some(price_str) => match price_str.parse::<f64>() {
-                    Ok(price) => price,
-                    Err(_) => continue,
-                },
-                None => match candle[4].as_f64() {
-                    Some(price) => price,
-                    None => continue,
-                },
-            };
-
-            let volume = match candle[5].as_str() {
-                Some(vol_str) => match vol_str.parse::<f64>() {
-                    Ok(vol) => Some(vol),
-                    Err(_) => None,
-                },
-                None => candle[5].as_f64(),
+            // Values are numbers, though strings are accepted too
+            let close_price = match candle_value(&candle[4]) {
+                Some(price) => price,
+                None => continue,
             };
 
             data_points.push(PriceHistoryPoint {
                 timestamp,
                 price: close_price,
-                volume,
+                open: candle_value(&candle[3]),
+                high: candle_value(&candle[2]),
+                low: candle_value(&candle[1]),
+                volume: candle_value(&candle[5]),
             });
         }
 
         // Sort by timestamp (newest first)
-        data_points.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        data_points.sort_by_key(|point| std::cmp::Reverse(point.timestamp));
 
         // Limit results if requested
         if let Some(limit_val) = limit {
@@ -217,52 +255,282 @@ impl ExchangeConnector for CoinbaseConnector {
         Ok(PriceHistory {
             exchange: Exchange::Coinbase,
             pair: pair.clone(),
-            interval,
+            interval: interval.into(),
             data: data_points,
         })
     }
 
-    async fn list_trading_pairs(&self) -> Result<Vec<TradingPair>> {
-        let url = format!("{}/products", COINBASE_PRO_API_URL);
+    async fn get_recent_trades(
+        &self,
+        pair: &TradingPair,
+        limit: Option<usize>,
+    ) -> Result<Vec<Trade>> {
+        let product_id = self.format_product_id(pair);
+        let url = format!("{}/products/{}/trades", self.base_url, product_id);
+
+        // Coinbase defaults to 100 trades and allows at most 1000
+        let coinbase_limit = limit.unwrap_or(100).min(1000);
 
-        debug!("Fetching trading pairs from Coinbase: {}", url);
+        debug!(
+            "Fetching recent trades from Coinbase: {} (limit: {})",
+            url, coinbase_limit
+        );
 
         let response = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .map_err(Error::HttpError)?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            error!("Coinbase API error: {} - {}", status, error_text);
-            return Err(Error::ExchangeError(format!(
-                "Coinbase API error: {} - {}",
-                status, error_text
-            )));
+            .http
+            .get(&url, &[("limit", coinbase_limit.to_string())])
+            .await?
+            .error_for_status("Coinbase")?;
+
+        let raw_trades: Vec<CoinbaseTrade> = response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse Coinbase trades: {}", e)))?;
+
+        let mut trades = Vec::with_capacity(raw_trades.len());
+
+        for raw in raw_trades {
+            let price = raw
+                .price
+                .parse::<Decimal>()
+                .map_err(|e| Error::ParseError(format!("Failed to parse trade price: {}", e)))?;
+            let size = raw
+                .size
+                .parse::<Decimal>()
+                .map_err(|e| Error::ParseError(format!("Failed to parse trade size: {}", e)))?;
+
+            // Coinbase reports the maker's side, so the taker is on the other side
+            let side = match raw.side.as_str() {
+                "buy" => TradeSide::Sell,
+                "sell" => TradeSide::Buy,
+                other => {
+                    return Err(Error::ParseError(format!(
+                        "Unknown Coinbase trade side: {}",
+                        other
+                    )))
+                }
+            };
+
+            trades.push(Trade {
+                exchange: Exchange::Coinbase,
+                pair: pair.clone(),
+                trade_id: raw.trade_id.to_string(),
+                price,
+                size,
+                side,
+                timestamp: raw.time,
+            });
         }
 
+        // Sort by timestamp (newest first)
+        trades.sort_by_key(|trade| std::cmp::Reverse(trade.timestamp));
+
+        Ok(trades)
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let url = format!("{}/time", self.base_url);
+        let time: CoinbaseTime = self.get_json(&url).await?;
+        Ok(time.iso)
+    }
+
+    async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+        let url = format!("{}/products", self.base_url);
+
+        debug!("Fetching products from Coinbase: {}", url);
+
+        let response = self
+            .http
+            .get(&url, &[])
+            .await?
+            .error_for_status("Coinbase")?;
+
         #[derive(Deserialize)]
         struct Product {
             id: String,
             base_currency: String,
             quote_currency: String,
+            status: String,
+            #[serde(default)]
+            trading_disabled: bool,
+            #[serde(default)]
+            cancel_only: bool,
+            #[serde(default)]
+            limit_only: bool,
+            #[serde(default)]
+            post_only: bool,
+            quote_increment: Option<String>,
+            base_increment: Option<String>,
+            base_min_size: Option<String>,
+            base_max_size: Option<String>,
+            min_market_funds: Option<String>,
         }
 
-        let products: Vec<Product> = response.json().await.map_err(|e| {
-            Error::ParseError(format!("Failed to parse Coinbase products: {}", e))
-        })?;
+        let products: Vec<Product> = response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse Coinbase products: {}", e)))?;
+
+        let parse = |value: &Option<String>| value.as_deref().and_then(|v| v.parse().ok());
 
-        let pairs = products
+        let markets = products
             .into_iter()
-            .map(|product| TradingPair {
-                base: product.base_currency,
-                quote: product.quote_currency,
+            .map(|product| {
+                let status = if product.status == "delisted" {
+                    MarketStatus::Delisted
+                } else if product.trading_disabled || product.status != "online" {
+                    MarketStatus::Halted
+                } else if product.cancel_only {
+                    MarketStatus::CancelOnly
+                } else if product.limit_only || product.post_only {
+                    MarketStatus::LimitOnly
+                } else {
+                    MarketStatus::Trading
+                };
+
+                MarketInfo {
+                    exchange: Exchange::Coinbase,
+                    pair: TradingPair {
+                        base: product.base_currency.clone(),
+                        quote: product.quote_currency.clone(),
+                    },
+                    status,
+                    price_increment: parse(&product.quote_increment),
+                    size_increment: parse(&product.base_increment),
+                    min_size: parse(&product.base_min_size),
+                    max_size: parse(&product.base_max_size),
+                    min_notional: parse(&product.min_market_funds),
+                    symbol: product.id,
+                }
             })
             .collect();
 
-        Ok(pairs)
+        Ok(markets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::replay_fixtures;
+    use std::str::FromStr;
+
+    fn connector() -> CoinbaseConnector {
+        CoinbaseConnector::with_base_url("http://coinbase.test/")
+            .with_http(replay_fixtures("coinbase"))
+    }
+
+    fn btc_usd() -> TradingPair {
+        TradingPair {
+            base: "BTC".to_string(),
+            quote: "USD".to_string(),
+        }
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn current_price_combines_ticker_and_stats() {
+        let price = connector().get_current_price(&btc_usd()).await.unwrap();
+
+        assert_eq!(price.price, decimal("61000.50"));
+        assert_eq!(price.bid, Some(decimal("61000.49")));
+        assert_eq!(price.ask, Some(decimal("61000.51")));
+        assert_eq!(price.open_24h, Some(decimal("60000")));
+        assert_eq!(price.high_24h, Some(decimal("62000")));
+        assert_eq!(price.low_24h, Some(decimal("59000")));
+        assert_eq!(price.change_24h, Some(decimal("1000.50")));
+        assert_eq!(price.change_percent_24h, Some(decimal("1.668")));
+        assert_eq!(price.base_volume_24h, Some(decimal("12345.6789")));
+        assert_eq!(price.volume_24h, None);
+        assert_eq!(
+            price.timestamp,
+            DateTime::parse_from_rfc3339("2024-03-01T12:00:00.123456Z").unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn candles_accept_numbers_and_strings() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 3, 1, 2, 0, 0).unwrap();
+        let history = connector()
+            .get_price_history(
+                &btc_usd(),
+                PriceInterval::OneHour,
+                Some(start),
+                Some(end),
+                None,
+            )
+            .await
+            .unwrap();
+
+        // The malformed candle is skipped
+        let timestamps: Vec<i64> = history
+            .data
+            .iter()
+            .map(|p| p.timestamp.timestamp())
+            .collect();
+        assert_eq!(timestamps, [1709258400, 1709254800, 1709251200]);
+
+        let numbers = &history.data[0];
+        assert_eq!(numbers.price, decimal("61000.25"));
+        assert_eq!(numbers.open, Some(decimal("60900.5")));
+        assert_eq!(numbers.high, Some(decimal("61200")));
+        assert_eq!(numbers.low, Some(decimal("60800.01")));
+        assert_eq!(numbers.volume, Some(decimal("12.5")));
+
+        let strings = &history.data[1];
+        assert_eq!(strings.price, decimal("60900.5"));
+        assert_eq!(strings.open, Some(decimal("60100")));
+        assert_eq!(strings.low, Some(decimal("60000.1")));
+        assert_eq!(strings.volume, Some(decimal("10.25")));
+
+        // Tiny numbers are printed in scientific notation
+        assert_eq!(history.data[2].volume, Some(decimal("0.00000012")));
+    }
+
+    #[tokio::test]
+    async fn trades_report_the_taker_side() {
+        let trades = connector()
+            .get_recent_trades(&btc_usd(), Some(2))
+            .await
+            .unwrap();
+
+        let summary: Vec<(&str, TradeSide)> = trades
+            .iter()
+            .map(|trade| (trade.trade_id.as_str(), trade.side))
+            .collect();
+        // Coinbase reports the maker's side
+        assert_eq!(summary, [("102", TradeSide::Sell), ("101", TradeSide::Buy)]);
+        assert_eq!(trades[1].size, decimal("0.25"));
     }
-} 
\ No newline at end of file
+
+    #[tokio::test]
+    async fn products_map_to_market_statuses() {
+        let markets = connector().list_markets().await.unwrap();
+
+        let statuses: Vec<(&str, MarketStatus)> = markets
+            .iter()
+            .map(|market| (market.symbol.as_str(), market.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("BTC-USD", MarketStatus::Trading),
+                ("LUNA-USD", MarketStatus::Delisted),
+                ("ETH-EUR", MarketStatus::Halted),
+                ("SOL-USD", MarketStatus::CancelOnly),
+                ("ADA-USD", MarketStatus::LimitOnly),
+                ("XRP-USD", MarketStatus::Halted),
+            ]
+        );
+        assert_eq!(markets[0].price_increment, Some(decimal("0.01")));
+        assert_eq!(markets[0].min_notional, Some(decimal("1")));
+    }
+
+    #[tokio::test]
+    async fn server_time_uses_the_base_url() {
+        let time = connector().get_server_time().await.unwrap();
+        assert_eq!(time.timestamp_millis(), 1709294400500);
+    }
+}
@@ -0,0 +1,555 @@
+use crate::http::HttpClient;
+use crate::ExchangeConnector;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use common::{
+    models::{
+        CurrentPrice, Exchange, MarketInfo, MarketStatus, PriceHistory, PriceHistoryPoint,
+        PriceInterval, Trade, TradeSide, TradingPair,
+    },
+    Error, Result,
+};
+use rust_decimal::Decimal;
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::debug;
+
+pub(crate) const COINBASE_ADVANCED_API_URL: &str = "https://api.coinbase.com/api/v3/brokerage";
+
+// Most candles the Advanced Trade API returns per request
+const MAX_CANDLES: usize = 350;
+
+/// Connector for the public market endpoints of the Coinbase Advanced Trade
+/// API, an alternative to the Exchange API of `CoinbaseConnector`. Products
+/// report 24h volume alongside the price, and the order book its best bid
+/// and ask.
+pub struct CoinbaseAdvancedConnector {
+    http: HttpClient,
+    base_url: String,
+}
+
+impl Default for CoinbaseAdvancedConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CoinbaseAdvancedConnector {
+    pub fn new() -> Self {
+        Self::with_base_url(COINBASE_ADVANCED_API_URL)
+    }
+
+    /// Create a connector for another host serving the Advanced Trade API,
+    /// given the URL its `/market` and `/time` paths are relative to
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            http: HttpClient::default(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Send requests through a client, e.g. one recording or replaying them
+    pub fn with_http(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    fn format_product_id(&self, pair: &TradingPair) -> String {
+        format!("{}-{}", pair.base, pair.quote)
+    }
+
+    // Fetch and decode a JSON document from the Advanced Trade API
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+    ) -> Result<T> {
+        let response = self
+            .http
+            .get(url, query)
+            .await?
+            .error_for_status("Coinbase")?;
+
+        response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse Coinbase response: {}", e)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedProduct {
+    product_id: String,
+    #[serde(default)]
+    price: String,
+    #[serde(default)]
+    price_percentage_change_24h: String,
+    #[serde(default)]
+    volume_24h: String,
+    #[serde(default)]
+    approximate_quote_24h_volume: Option<String>,
+    #[serde(default)]
+    base_currency_id: String,
+    #[serde(default)]
+    quote_currency_id: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    trading_disabled: bool,
+    #[serde(default)]
+    is_disabled: bool,
+    #[serde(default)]
+    cancel_only: bool,
+    #[serde(default)]
+    limit_only: bool,
+    #[serde(default)]
+    post_only: bool,
+    quote_increment: Option<String>,
+    base_increment: Option<String>,
+    base_min_size: Option<String>,
+    base_max_size: Option<String>,
+    quote_min_size: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedProducts {
+    products: Vec<AdvancedProduct>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedBookLevel {
+    price: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedPriceBook {
+    #[serde(default)]
+    bids: Vec<AdvancedBookLevel>,
+    #[serde(default)]
+    asks: Vec<AdvancedBookLevel>,
+    #[serde(default)]
+    time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedProductBook {
+    pricebook: AdvancedPriceBook,
+}
+
+// Candle values are strings, and their start a string of Unix seconds
+#[derive(Debug, Deserialize)]
+struct AdvancedCandle {
+    start: String,
+    low: String,
+    high: String,
+    open: String,
+    close: String,
+    volume: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedCandles {
+    candles: Vec<AdvancedCandle>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedTrade {
+    trade_id: String,
+    price: String,
+    size: String,
+    time: DateTime<Utc>,
+    side: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedTrades {
+    trades: Vec<AdvancedTrade>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvancedTime {
+    iso: DateTime<Utc>,
+}
+
+// Convert PriceInterval to an Advanced Trade granularity name; there is no
+// weekly granularity
+fn advanced_granularity(interval: PriceInterval) -> Option<&'static str> {
+    match interval {
+        PriceInterval::OneMinute => Some("ONE_MINUTE"),
+        PriceInterval::FiveMinutes => Some("FIVE_MINUTE"),
+        PriceInterval::FifteenMinutes => Some("FIFTEEN_MINUTE"),
+        PriceInterval::OneHour => Some("ONE_HOUR"),
+        PriceInterval::FourHours => Some("FOUR_HOUR"),
+        PriceInterval::OneDay => Some("ONE_DAY"),
+        PriceInterval::OneWeek => None,
+    }
+}
+
+#[async_trait]
+impl ExchangeConnector for CoinbaseAdvancedConnector {
+    async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
+        let product_id = self.format_product_id(pair);
+        let product_url = format!("{}/market/products/{}", self.base_url, product_id);
+        let book_url = format!("{}/market/product_book", self.base_url);
+        let book_query = [
+            ("product_id", product_id.clone()),
+            ("limit", "1".to_string()),
+        ];
+
+        debug!(
+            "Fetching product and best bid/ask from Coinbase for {}",
+            product_id
+        );
+
+        let (product, book) = tokio::join!(
+            self.get_json::<AdvancedProduct>(&product_url, &[]),
+            self.get_json::<AdvancedProductBook>(&book_url, &book_query)
+        );
+        let product = product?;
+        let book = book?.pricebook;
+
+        let price = product
+            .price
+            .parse::<Decimal>()
+            .map_err(|e| Error::ParseError(format!("Failed to parse price: {}", e)))?;
+
+        // Only the relative change is reported, so the open is derived from it
+        let change_percent_24h = product
+            .price_percentage_change_24h
+            .parse::<Decimal>()
+            .ok()
+            .map(|percent| percent.round_dp(3));
+        let open_24h = product
+            .price_percentage_change_24h
+            .parse::<Decimal>()
+            .ok()
+            .map(|percent| Decimal::ONE_HUNDRED + percent)
+            .filter(|ratio| !ratio.is_zero())
+            .map(|ratio| (price * Decimal::ONE_HUNDRED / ratio).round_dp(price.scale()));
+        let change_24h = open_24h.map(|open| price - open);
+
+        let best = |levels: &[AdvancedBookLevel]| {
+            levels.first().and_then(|level| level.price.parse().ok())
+        };
+
+        Ok(CurrentPrice {
+            exchange: Exchange::Coinbase,
+            pair: pair.clone(),
+            price,
+            bid: best(&book.bids),
+            ask: best(&book.asks),
+            open_24h,
+            high_24h: None,
+            low_24h: None,
+            change_24h,
+            change_percent_24h,
+            base_volume_24h: product.volume_24h.parse().ok(),
+            volume_24h: product
+                .approximate_quote_24h_volume
+                .and_then(|volume| volume.parse().ok()),
+            timestamp: book.time.unwrap_or_else(Utc::now),
+            flags: Vec::new(),
+        })
+    }
+
+    async fn get_price_history(
+        &self,
+        pair: &TradingPair,
+        interval: PriceInterval,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PriceHistory> {
+        let granularity = advanced_granularity(interval).ok_or_else(|| {
+            Error::ParseError(format!(
+                "Coinbase Advanced Trade does not support the {} interval",
+                interval
+            ))
+        })?;
+        let product_id = self.format_product_id(pair);
+        let url = format!("{}/market/products/{}/candles", self.base_url, product_id);
+
+        // Both ends of the range are required; default to the latest candles
+        let end = end_time.unwrap_or_else(Utc::now);
+        let count = limit.unwrap_or(300).min(MAX_CANDLES);
+        let start = start_time.unwrap_or_else(|| end - interval.duration() * count as i32);
+
+        debug!(
+            "Fetching price history from Coinbase: {} (granularity: {}, start: {}, end: {})",
+            url, granularity, start, end
+        );
+
+        let params = [
+            ("start", start.timestamp().to_string()),
+            ("end", end.timestamp().to_string()),
+            ("granularity", granularity.to_string()),
+            ("limit", count.to_string()),
+        ];
+        let candles: AdvancedCandles = self.get_json(&url, &params).await?;
+
+        let mut data_points = Vec::with_capacity(candles.candles.len());
+
+        for candle in candles.candles {
+            let timestamp = match candle.start.parse::<i64>() {
+                Ok(ts) => Utc.timestamp_opt(ts, 0).unwrap(),
+                Err(_) => continue,
+            };
+            let close_price = match candle.close.parse::<Decimal>() {
+                Ok(price) => price,
+                Err(_) => continue,
+            };
+
+            data_points.push(PriceHistoryPoint {
+                timestamp,
+                price: close_price,
+                open: candle.open.parse().ok(),
+                high: candle.high.parse().ok(),
+                low: candle.low.parse().ok(),
+                volume: candle.volume.parse().ok(),
+            });
+        }
+
+        // Sort by timestamp (newest first)
+        data_points.sort_by_key(|point| std::cmp::Reverse(point.timestamp));
+
+        if let Some(limit_val) = limit {
+            data_points.truncate(limit_val);
+        }
+
+        Ok(PriceHistory {
+            exchange: Exchange::Coinbase,
+            pair: pair.clone(),
+            interval: interval.into(),
+            data: data_points,
+        })
+    }
+
+    async fn get_recent_trades(
+        &self,
+        pair: &TradingPair,
+        limit: Option<usize>,
+    ) -> Result<Vec<Trade>> {
+        let product_id = self.format_product_id(pair);
+        let url = format!("{}/market/products/{}/ticker", self.base_url, product_id);
+
+        // Advanced Trade requires a limit and allows at most 1000
+        let coinbase_limit = limit.unwrap_or(100).min(1000);
+
+        debug!(
+            "Fetching market trades from Coinbase Advanced Trade: {} (limit: {})",
+            url, coinbase_limit
+        );
+
+        let raw_trades: AdvancedTrades = self
+            .get_json(&url, &[("limit", coinbase_limit.to_string())])
+            .await?;
+
+        let mut trades = Vec::with_capacity(raw_trades.trades.len());
+
+        for raw in raw_trades.trades {
+            let price = raw
+                .price
+                .parse::<Decimal>()
+                .map_err(|e| Error::ParseError(format!("Failed to parse trade price: {}", e)))?;
+            let size = raw
+                .size
+                .parse::<Decimal>()
+                .map_err(|e| Error::ParseError(format!("Failed to parse trade size: {}", e)))?;
+
+            // As on the Exchange API, the side is the maker's, so the taker is
+            // on the other side
+            let side = match raw.side.as_str() {
+                "BUY" => TradeSide::Sell,
+                "SELL" => TradeSide::Buy,
+                other => {
+                    return Err(Error::ParseError(format!(
+                        "Unknown Coinbase trade side: {}",
+                        other
+                    )))
+                }
+            };
+
+            trades.push(Trade {
+                exchange: Exchange::Coinbase,
+                pair: pair.clone(),
+                trade_id: raw.trade_id,
+                price,
+                size,
+                side,
+                timestamp: raw.time,
+            });
+        }
+
+        // Sort by timestamp (newest first)
+        trades.sort_by_key(|trade| std::cmp::Reverse(trade.timestamp));
+
+        Ok(trades)
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let url = format!("{}/time", self.base_url);
+        let time: AdvancedTime = self.get_json(&url, &[]).await?;
+        Ok(time.iso)
+    }
+
+    async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+        let url = format!("{}/market/products", self.base_url);
+
+        debug!("Fetching products from Coinbase Advanced Trade: {}", url);
+
+        let products: AdvancedProducts = self
+            .get_json(&url, &[("product_type", "SPOT".to_string())])
+            .await?;
+
+        let parse = |value: &Option<String>| value.as_deref().and_then(|v| v.parse().ok());
+
+        let markets = products
+            .products
+            .into_iter()
+            .map(|product| {
+                let status = if product.status == "delisted" {
+                    MarketStatus::Delisted
+                } else if product.trading_disabled
+                    || product.is_disabled
+                    || product.status != "online"
+                {
+                    MarketStatus::Halted
+                } else if product.cancel_only {
+                    MarketStatus::CancelOnly
+                } else if product.limit_only || product.post_only {
+                    MarketStatus::LimitOnly
+                } else {
+                    MarketStatus::Trading
+                };
+
+                MarketInfo {
+                    exchange: Exchange::Coinbase,
+                    pair: TradingPair {
+                        base: product.base_currency_id.clone(),
+                        quote: product.quote_currency_id.clone(),
+                    },
+                    status,
+                    price_increment: parse(&product.quote_increment),
+                    size_increment: parse(&product.base_increment),
+                    min_size: parse(&product.base_min_size),
+                    max_size: parse(&product.base_max_size),
+                    min_notional: parse(&product.quote_min_size),
+                    symbol: product.product_id,
+                }
+            })
+            .collect();
+
+        Ok(markets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::replay_fixtures;
+
+    fn connector() -> CoinbaseAdvancedConnector {
+        CoinbaseAdvancedConnector::with_base_url("http://coinbase-advanced.test/api/v3/brokerage/")
+            .with_http(replay_fixtures("coinbase_advanced"))
+    }
+
+    fn btc_usd() -> TradingPair {
+        TradingPair {
+            base: "BTC".to_string(),
+            quote: "USD".to_string(),
+        }
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn current_price_from_product_and_book() {
+        let price = connector().get_current_price(&btc_usd()).await.unwrap();
+
+        assert_eq!(price.exchange, Exchange::Coinbase);
+        assert_eq!(price.price, decimal("61000.50"));
+        assert_eq!(price.bid, Some(decimal("61000.49")));
+        assert_eq!(price.ask, Some(decimal("61000.51")));
+        // The open is derived from the relative change
+        assert_eq!(price.open_24h, Some(decimal("60000.00")));
+        assert_eq!(price.change_24h, Some(decimal("1000.50")));
+        assert_eq!(price.change_percent_24h, Some(decimal("1.668")));
+        assert_eq!(price.base_volume_24h, Some(decimal("12345.678")));
+        assert_eq!(price.volume_24h, Some(decimal("753086419.75")));
+        assert_eq!(price.timestamp.timestamp_micros(), 1709294400123456);
+    }
+
+    #[tokio::test]
+    async fn candles_newest_first() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 3, 1, 3, 0, 0).unwrap();
+        let history = connector()
+            .get_price_history(
+                &btc_usd(),
+                PriceInterval::OneHour,
+                Some(start),
+                Some(end),
+                Some(3),
+            )
+            .await
+            .unwrap();
+
+        // The candle without a start time is skipped
+        let closes: Vec<(i64, Decimal)> = history
+            .data
+            .iter()
+            .map(|point| (point.timestamp.timestamp(), point.price))
+            .collect();
+        assert_eq!(
+            closes,
+            [
+                (1709258400, decimal("61050")),
+                (1709254800, decimal("60950.5")),
+                (1709251200, decimal("60400")),
+            ]
+        );
+
+        let bar = &history.data[1];
+        assert_eq!(bar.open, Some(decimal("60400")));
+        assert_eq!(bar.high, Some(decimal("61000")));
+        assert_eq!(bar.low, Some(decimal("60300")));
+        assert_eq!(bar.volume, Some(decimal("250.25")));
+    }
+
+    #[tokio::test]
+    async fn weekly_candles_are_unsupported() {
+        let result = connector()
+            .get_price_history(&btc_usd(), PriceInterval::OneWeek, None, None, None)
+            .await;
+
+        assert!(matches!(result, Err(Error::ParseError(_))));
+    }
+
+    #[tokio::test]
+    async fn products_listed_with_trading_status() {
+        let markets = connector().list_markets().await.unwrap();
+
+        let statuses: Vec<(&str, MarketStatus)> = markets
+            .iter()
+            .map(|market| (market.symbol.as_str(), market.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                ("BTC-USD", MarketStatus::Trading),
+                ("ETH-USD", MarketStatus::CancelOnly),
+                ("SOL-USD", MarketStatus::LimitOnly),
+                ("ADA-USD", MarketStatus::Halted),
+                ("XYZ-USD", MarketStatus::Delisted),
+            ]
+        );
+
+        let btc = &markets[0];
+        assert_eq!(btc.pair, btc_usd());
+        assert_eq!(btc.price_increment, Some(decimal("0.01")));
+        assert_eq!(btc.size_increment, Some(decimal("0.00000001")));
+        assert_eq!(btc.min_size, Some(decimal("0.00000001")));
+        assert_eq!(btc.max_size, Some(decimal("3400")));
+        assert_eq!(btc.min_notional, Some(decimal("1")));
+    }
+}
@@ -0,0 +1,734 @@
+use crate::http::HttpClient;
+use crate::ExchangeConnector;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use common::{
+    models::{
+        CurrentPrice, Exchange, MarketInfo, MarketStatus, PriceHistory, PriceInterval, Trade,
+        TradingPair,
+    },
+    Error, Result,
+};
+use num_bigint::BigUint;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use tokio::sync::OnceCell;
+use tracing::{debug, warn};
+
+// Function selectors of the contract calls made
+const GET_RESERVES: &str = "0x0902f1ac";
+const SLOT0: &str = "0x3850c7bd";
+const TOKEN0: &str = "0x0dfe1681";
+const TOKEN1: &str = "0xd21220a7";
+const DECIMALS: &str = "0x313ce567";
+
+// Significant digits kept in prices computed from pool state
+const SIGNIFICANT_DIGITS: u32 = 18;
+
+/// Description of the tokens and pools of a decentralized exchange, read
+/// through an Ethereum JSON-RPC endpoint, from which a [`DexConnector`] is
+/// built.
+///
+/// Pools are Uniswap v2-style pairs, priced from `getReserves`, or Uniswap
+/// v3-style pools, priced from the square root price of `slot0`. The tokens
+/// of each pool are read from the pool itself and must be listed in
+/// `tokens`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DexSpec {
+    /// Exchange name, used to select the exchange in the API
+    pub name: String,
+    /// URL of the JSON-RPC endpoint of a node of the chain
+    pub rpc_url: String,
+    /// Tokens standing in for other symbols, such as WETH for ETH
+    #[serde(default = "default_aliases")]
+    pub aliases: HashMap<String, String>,
+    /// Tokens that prices are routed through when no pool pairs a token with
+    /// the requested quote, in order of preference
+    #[serde(default = "default_route_via")]
+    pub route_via: Vec<String>,
+    /// Tokens by symbol
+    pub tokens: BTreeMap<String, TokenSpec>,
+    /// Pools prices are read from; the first pool of a pair is used
+    pub pools: Vec<PoolSpec>,
+}
+
+fn default_aliases() -> HashMap<String, String> {
+    HashMap::from([
+        ("ETH".to_string(), "WETH".to_string()),
+        ("USD".to_string(), "USDC".to_string()),
+    ])
+}
+
+fn default_route_via() -> Vec<String> {
+    vec!["WETH".to_string(), "USDC".to_string()]
+}
+
+/// An ERC-20 token
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenSpec {
+    /// Contract address
+    pub address: String,
+    /// Decimals of token amounts; read from the contract if not given
+    pub decimals: Option<u32>,
+}
+
+/// A liquidity pool
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PoolSpec {
+    /// Contract address
+    pub address: String,
+    /// Interface of the pool
+    pub version: PoolVersion,
+}
+
+/// Interface of a liquidity pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolVersion {
+    /// Constant-product pair exposing `getReserves`
+    V2,
+    /// Concentrated-liquidity pool exposing `slot0`
+    V3,
+}
+
+fn is_address(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+impl DexSpec {
+    /// Load a spec from a TOML file, or a JSON file if its extension is .json
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            Error::ConfigError(format!("Failed to read DEX spec {}: {}", path.display(), e))
+        })?;
+
+        let spec: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&contents).map_err(|e| {
+                Error::ConfigError(format!("Invalid DEX spec JSON {}: {}", path.display(), e))
+            })?,
+            _ => toml::from_str(&contents).map_err(|e| {
+                Error::ConfigError(format!("Invalid DEX spec TOML {}: {}", path.display(), e))
+            })?,
+        };
+
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Check the parts of the spec that its format does not enforce
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| {
+            Err(Error::ConfigError(format!(
+                "Invalid DEX spec '{}': {}",
+                self.name, message
+            )))
+        };
+        let listed = |symbol: &str| self.tokens.contains_key(&symbol.to_uppercase());
+
+        for (symbol, token) in &self.tokens {
+            if symbol != &symbol.to_uppercase() {
+                return invalid(format!("token symbol {} is not upper case", symbol));
+            }
+            if !is_address(&token.address) {
+                return invalid(format!("invalid address of {}: {}", symbol, token.address));
+            }
+            if token.decimals.is_some_and(|decimals| decimals > 36) {
+                return invalid(format!("too many decimals for {}", symbol));
+            }
+        }
+        if let Some(pool) = self.pools.iter().find(|pool| !is_address(&pool.address)) {
+            return invalid(format!("invalid pool address: {}", pool.address));
+        }
+        if self.pools.is_empty() {
+            return invalid("no pools".to_string());
+        }
+        if let Some((alias, _)) = self.aliases.iter().find(|(_, token)| !listed(token)) {
+            return invalid(format!("alias {} names an unlisted token", alias));
+        }
+        if let Some(token) = self.route_via.iter().find(|token| !listed(token)) {
+            return invalid(format!("route_via names an unlisted token: {}", token));
+        }
+
+        Ok(())
+    }
+}
+
+// A pool with its tokens
+#[derive(Debug, Clone)]
+struct Pool {
+    address: String,
+    version: PoolVersion,
+    token0: String,
+    token1: String,
+    decimals0: u32,
+    decimals1: u32,
+}
+
+// A price as an exact fraction
+struct Ratio {
+    num: BigUint,
+    den: BigUint,
+}
+
+impl Ratio {
+    fn inverse(self) -> Self {
+        Ratio {
+            num: self.den,
+            den: self.num,
+        }
+    }
+
+    fn times(self, other: Ratio) -> Self {
+        Ratio {
+            num: self.num * other.num,
+            den: self.den * other.den,
+        }
+    }
+
+    // Round to a decimal with at most SIGNIFICANT_DIGITS significant digits
+    fn to_decimal(&self) -> Option<Decimal> {
+        if self.den == BigUint::default() {
+            return None;
+        }
+        let integer_digits = (&self.num / &self.den).to_string().len() as u32;
+        if integer_digits > 28 {
+            return None;
+        }
+
+        let mut scale = 28 - integer_digits;
+        let mut scaled = &self.num * pow10(scale) / &self.den;
+        let digits = scaled.to_string().len() as u32;
+        if digits > SIGNIFICANT_DIGITS {
+            let dropped = (digits - SIGNIFICANT_DIGITS).min(scale);
+            if dropped > 0 {
+                scaled = (scaled + pow10(dropped) / 2u32) / pow10(dropped);
+                scale -= dropped;
+            }
+        }
+
+        let mantissa = i128::try_from(&scaled).ok()?;
+        Some(Decimal::from_i128_with_scale(mantissa, scale).normalize())
+    }
+}
+
+fn pow10(exponent: u32) -> BigUint {
+    BigUint::from(10u32).pow(exponent)
+}
+
+// 32-byte word of ABI-encoded call output
+fn word(data: &[u8], index: usize) -> Result<&[u8]> {
+    data.get(index * 32..(index + 1) * 32).ok_or_else(|| {
+        Error::ParseError(format!(
+            "Contract call returned {} bytes, expected at least {}",
+            data.len(),
+            (index + 1) * 32
+        ))
+    })
+}
+
+fn uint(word: &[u8]) -> BigUint {
+    BigUint::from_bytes_be(word)
+}
+
+fn address(word: &[u8]) -> String {
+    format!("0x{}", hex::encode(&word[12..]))
+}
+
+fn hex_quantity(value: &Value) -> Option<u64> {
+    value
+        .as_str()
+        .and_then(|v| v.strip_prefix("0x"))
+        .and_then(|v| u64::from_str_radix(v, 16).ok())
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<RpcError>,
+}
+
+/// Connector pricing tokens from the state of decentralized exchange pools,
+/// read with `eth_call` through a JSON-RPC endpoint.
+///
+/// A pair is priced from a pool of its two tokens, or else routed through
+/// the `route_via` tokens, e.g. from a token/WETH pool and a WETH/USDC pool.
+/// All pools of a price are read at the same block, whose time stamps the
+/// price. Pools have no order book, candles or trade list, so only prices,
+/// markets and the chain's clock are provided.
+pub struct DexConnector {
+    exchange: Exchange,
+    spec: DexSpec,
+    http: HttpClient,
+    pools: OnceCell<Vec<Pool>>,
+}
+
+impl DexConnector {
+    /// Create a connector for the DEX of a spec, registering the exchange
+    pub fn new(mut spec: DexSpec) -> Result<Self> {
+        spec.validate()?;
+        let exchange = Exchange::register(&spec.name)?;
+        spec.aliases = spec
+            .aliases
+            .into_iter()
+            .map(|(alias, token)| (alias.to_uppercase(), token.to_uppercase()))
+            .collect();
+        spec.route_via = spec.route_via.iter().map(|t| t.to_uppercase()).collect();
+
+        Ok(Self {
+            exchange,
+            spec,
+            http: HttpClient::default(),
+            pools: OnceCell::new(),
+        })
+    }
+
+    /// Send requests through a client, e.g. one recording or replaying them
+    pub fn with_http(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Exchange served by the connector
+    pub fn exchange(&self) -> Exchange {
+        self.exchange
+    }
+
+    fn not_supported(&self, what: &str) -> Error {
+        Error::NotFound(format!("{} does not provide {}", self.spec.name, what))
+    }
+
+    // Token standing for a symbol
+    fn token(&self, symbol: &str) -> Result<String> {
+        let symbol = symbol.to_uppercase();
+        let token = self.spec.aliases.get(&symbol).cloned().unwrap_or(symbol);
+
+        if self.spec.tokens.contains_key(&token) {
+            Ok(token)
+        } else {
+            Err(Error::NotFound(format!(
+                "{} does not list {}",
+                self.spec.name, token
+            )))
+        }
+    }
+
+    async fn rpc(&self, method: &str, params: Value) -> Result<Value> {
+        // A fixed id keeps requests, and so recorded responses, reproducible
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+        let response = self
+            .http
+            .post_json(&self.spec.rpc_url, &request)
+            .await?
+            .error_for_status(&self.spec.name)?;
+        let response: RpcResponse = response
+            .json()
+            .map_err(|e| Error::ParseError(format!("Failed to parse JSON-RPC response: {}", e)))?;
+
+        if let Some(error) = response.error {
+            return Err(Error::ExchangeError(format!(
+                "{} JSON-RPC error {} on {}: {}",
+                self.spec.name, error.code, method, error.message
+            )));
+        }
+        response.result.ok_or_else(|| {
+            Error::ParseError(format!("JSON-RPC response to {} has no result", method))
+        })
+    }
+
+    // Call a view function of a contract at a block, returning its output
+    async fn call(&self, to: &str, selector: &str, block: &str) -> Result<Vec<u8>> {
+        let result = self
+            .rpc("eth_call", json!([{ "to": to, "data": selector }, block]))
+            .await?;
+
+        result
+            .as_str()
+            .and_then(|output| output.strip_prefix("0x"))
+            .and_then(|output| hex::decode(output).ok())
+            .ok_or_else(|| Error::ParseError(format!("Invalid eth_call output: {}", result)))
+    }
+
+    // Number and time of the latest block
+    async fn latest_block(&self) -> Result<(String, DateTime<Utc>)> {
+        let block = self
+            .rpc("eth_getBlockByNumber", json!(["latest", false]))
+            .await?;
+        let number = block.get("number").and_then(hex_quantity);
+        let timestamp = block.get("timestamp").and_then(hex_quantity);
+
+        match (number, timestamp) {
+            (Some(number), Some(timestamp)) => {
+                let time = Utc
+                    .timestamp_opt(timestamp as i64, 0)
+                    .single()
+                    .ok_or_else(|| {
+                        Error::ParseError(format!("Invalid block time: {}", timestamp))
+                    })?;
+                Ok((format!("0x{:x}", number), time))
+            }
+            _ => Err(Error::ParseError(format!("Invalid block: {}", block))),
+        }
+    }
+
+    async fn decimals(&self, symbol: &str) -> Result<u32> {
+        let token = &self.spec.tokens[symbol];
+        if let Some(decimals) = token.decimals {
+            return Ok(decimals);
+        }
+
+        let output = self.call(&token.address, DECIMALS, "latest").await?;
+        u32::try_from(uint(word(&output, 0)?))
+            .ok()
+            .filter(|decimals| *decimals <= 36)
+            .ok_or_else(|| Error::ParseError(format!("Invalid decimals of {}", symbol)))
+    }
+
+    // Pools of the spec with their tokens, read from the chain once
+    async fn pools(&self) -> Result<&[Pool]> {
+        let pools = self
+            .pools
+            .get_or_try_init(|| async {
+                let symbols: HashMap<String, &String> = self
+                    .spec
+                    .tokens
+                    .iter()
+                    .map(|(symbol, token)| (token.address.to_lowercase(), symbol))
+                    .collect();
+                let mut decimals: HashMap<String, u32> = HashMap::new();
+                let mut pools = Vec::new();
+
+                for spec in &self.spec.pools {
+                    let token0 =
+                        address(word(&self.call(&spec.address, TOKEN0, "latest").await?, 0)?);
+                    let token1 =
+                        address(word(&self.call(&spec.address, TOKEN1, "latest").await?, 0)?);
+                    let (Some(&token0), Some(&token1)) =
+                        (symbols.get(&token0), symbols.get(&token1))
+                    else {
+                        warn!(
+                            "Skipping {} pool {}: its tokens {} and {} are not both listed",
+                            self.spec.name, spec.address, token0, token1
+                        );
+                        continue;
+                    };
+
+                    for symbol in [token0, token1] {
+                        if !decimals.contains_key(symbol) {
+                            decimals.insert(symbol.clone(), self.decimals(symbol).await?);
+                        }
+                    }
+                    pools.push(Pool {
+                        address: spec.address.clone(),
+                        version: spec.version,
+                        token0: token0.clone(),
+                        token1: token1.clone(),
+                        decimals0: decimals[token0],
+                        decimals1: decimals[token1],
+                    });
+                }
+
+                debug!("Resolved {} pools of {}", pools.len(), self.spec.name);
+                Ok::<_, Error>(pools)
+            })
+            .await?;
+
+        Ok(pools)
+    }
+
+    // Pools leading from one token to another, and whether each is crossed
+    // from its first token to its second: directly, or through one or two
+    // of the route_via tokens
+    fn route<'a>(&self, pools: &'a [Pool], from: &str, to: &str) -> Option<Vec<(&'a Pool, bool)>> {
+        let hop = |a: &str, b: &str| {
+            pools.iter().find_map(|pool| {
+                if pool.token0 == a && pool.token1 == b {
+                    Some((pool, true))
+                } else if pool.token0 == b && pool.token1 == a {
+                    Some((pool, false))
+                } else {
+                    None
+                }
+            })
+        };
+        let via: Vec<&str> = self
+            .spec
+            .route_via
+            .iter()
+            .map(String::as_str)
+            .filter(|token| *token != from && *token != to)
+            .collect();
+
+        let mut paths = vec![vec![from, to]];
+        paths.extend(via.iter().map(|&v| vec![from, v, to]));
+        for &first in &via {
+            paths.extend(
+                via.iter()
+                    .filter(|&&second| second != first)
+                    .map(|&second| vec![from, first, second, to]),
+            );
+        }
+
+        paths.into_iter().find_map(|path| {
+            path.windows(2)
+                .map(|tokens| hop(tokens[0], tokens[1]))
+                .collect()
+        })
+    }
+
+    // Price of a pool's first token in its second token at a block
+    async fn pool_price(&self, pool: &Pool, block: &str) -> Result<Ratio> {
+        let output = match pool.version {
+            PoolVersion::V2 => self.call(&pool.address, GET_RESERVES, block).await?,
+            PoolVersion::V3 => self.call(&pool.address, SLOT0, block).await?,
+        };
+        // Scale from smallest units of each token to whole tokens
+        let (scale0, scale1) = (pow10(pool.decimals0), pow10(pool.decimals1));
+
+        let price = match pool.version {
+            PoolVersion::V2 => {
+                let reserve0 = uint(word(&output, 0)?);
+                let reserve1 = uint(word(&output, 1)?);
+                Ratio {
+                    num: reserve1 * scale0,
+                    den: reserve0 * scale1,
+                }
+            }
+            PoolVersion::V3 => {
+                // The square root of the price in smallest units, as Q64.96
+                let sqrt_price = uint(word(&output, 0)?);
+                Ratio {
+                    num: &sqrt_price * &sqrt_price * scale0,
+                    den: (BigUint::from(1u32) << 192u32) * scale1,
+                }
+            }
+        };
+
+        if price.num == BigUint::default() || price.den == BigUint::default() {
+            return Err(Error::ExchangeError(format!(
+                "{} pool {} has no liquidity",
+                self.spec.name, pool.address
+            )));
+        }
+        Ok(price)
+    }
+}
+
+#[async_trait]
+impl ExchangeConnector for DexConnector {
+    async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
+        let base = self.token(&pair.base)?;
+        let quote = self.token(&pair.quote)?;
+        let pools = self.pools().await?;
+        let route = self.route(pools, &base, &quote).ok_or_else(|| {
+            Error::NotFound(format!(
+                "{} has no pools pricing {} in {}",
+                self.spec.name, base, quote
+            ))
+        })?;
+
+        debug!(
+            "Pricing {}/{} on {} through pools {:?}",
+            base,
+            quote,
+            self.spec.name,
+            route
+                .iter()
+                .map(|(pool, _)| &pool.address)
+                .collect::<Vec<_>>()
+        );
+
+        // Read every pool at the same block
+        let (block, timestamp) = self.latest_block().await?;
+        let mut price = Ratio {
+            num: BigUint::from(1u32),
+            den: BigUint::from(1u32),
+        };
+        for (pool, forward) in route {
+            let pool_price = self.pool_price(pool, &block).await?;
+            price = price.times(if forward {
+                pool_price
+            } else {
+                pool_price.inverse()
+            });
+        }
+
+        let price = price.to_decimal().ok_or_else(|| {
+            Error::ParseError(format!(
+                "Price of {}/{} on {} is out of range",
+                base, quote, self.spec.name
+            ))
+        })?;
+
+        Ok(CurrentPrice {
+            exchange: self.exchange,
+            pair: pair.clone(),
+            price,
+            bid: None,
+            ask: None,
+            open_24h: None,
+            high_24h: None,
+            low_24h: None,
+            change_24h: None,
+            change_percent_24h: None,
+            base_volume_24h: None,
+            volume_24h: None,
+            timestamp,
+            flags: Vec::new(),
+        })
+    }
+
+    async fn get_price_history(
+        &self,
+        _pair: &TradingPair,
+        _interval: PriceInterval,
+        _start_time: Option<DateTime<Utc>>,
+        _end_time: Option<DateTime<Utc>>,
+        _limit: Option<usize>,
+    ) -> Result<PriceHistory> {
+        Err(self.not_supported("price history"))
+    }
+
+    async fn get_recent_trades(
+        &self,
+        _pair: &TradingPair,
+        _limit: Option<usize>,
+    ) -> Result<Vec<Trade>> {
+        Err(self.not_supported("recent trades"))
+    }
+
+    async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+        let pools = self.pools().await?;
+
+        Ok(pools
+            .iter()
+            .map(|pool| MarketInfo {
+                exchange: self.exchange,
+                pair: TradingPair {
+                    base: pool.token0.clone(),
+                    quote: pool.token1.clone(),
+                },
+                symbol: pool.address.clone(),
+                status: MarketStatus::Trading,
+                price_increment: None,
+                size_increment: None,
+                min_size: None,
+                max_size: None,
+                min_notional: None,
+            })
+            .collect())
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let (_, timestamp) = self.latest_block().await?;
+        Ok(timestamp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::replay_fixtures;
+    use std::str::FromStr;
+
+    // Replays a USDC/WETH v3 pool at 2500 USDC per WETH, a PEPE/WETH v2 pair
+    // at 4e-9 WETH per PEPE and a WBTC/WETH v3 pool at 40 WETH per WBTC
+    fn connector() -> DexConnector {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/specs/uniswap.toml");
+        DexConnector::new(DexSpec::load(&path).unwrap())
+            .unwrap()
+            .with_http(replay_fixtures("dex"))
+    }
+
+    fn pair(base: &str, quote: &str) -> TradingPair {
+        TradingPair {
+            base: base.to_string(),
+            quote: quote.to_string(),
+        }
+    }
+
+    async fn price(connector: &DexConnector, base: &str, quote: &str) -> Decimal {
+        connector
+            .get_current_price(&pair(base, quote))
+            .await
+            .unwrap()
+            .price
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn v3_prices_scale_by_token_decimals() {
+        let connector = connector();
+
+        // USDC has 6 decimals and WETH 18
+        assert_eq!(price(&connector, "ETH", "USD").await, decimal("2500"));
+        assert_eq!(price(&connector, "USDC", "WETH").await, decimal("0.0004"));
+
+        let current = connector
+            .get_current_price(&pair("ETH", "USD"))
+            .await
+            .unwrap();
+        assert_eq!(current.timestamp.timestamp(), 1760000012);
+    }
+
+    #[tokio::test]
+    async fn v2_prices_come_from_reserves() {
+        let connector = connector();
+
+        // PEPE decimals are read from the token contract
+        assert_eq!(
+            price(&connector, "PEPE", "ETH").await,
+            decimal("0.000000004")
+        );
+        // Routed through WETH
+        assert_eq!(price(&connector, "PEPE", "USD").await, decimal("0.00001"));
+    }
+
+    #[tokio::test]
+    async fn routes_cross_several_pools() {
+        let connector = connector();
+
+        // WBTC has 8 decimals; the square root price was rounded down
+        let btc = price(&connector, "BTC", "USD").await;
+        assert!((btc - decimal("100000")).abs() < decimal("0.000001"));
+        assert!(matches!(
+            connector.get_current_price(&pair("DOGE", "USD")).await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn markets_list_pools_with_listed_tokens() {
+        let markets = connector().list_markets().await.unwrap();
+
+        let pairs: Vec<(&str, &str)> = markets
+            .iter()
+            .map(|market| (market.pair.base.as_str(), market.pair.quote.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            [("USDC", "WETH"), ("PEPE", "WETH"), ("WBTC", "WETH")]
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use crate::http::HttpClient;
+use crate::FxRateConnector;
+use async_trait::async_trait;
+use chrono::{Duration, NaiveDate, Utc};
+use common::{models::FxRates, Error, Result};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use tracing::debug;
+
+const ECB_RATES_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref";
+
+/// Rates published in the last 90 days
+const HISTORY_90D_FILE: &str = "eurofxref-hist-90d.xml";
+
+/// Rates published since 1999
+const HISTORY_FILE: &str = "eurofxref-hist.xml";
+
+/// Latest rates
+const DAILY_FILE: &str = "eurofxref-daily.xml";
+
+/// Connector for the euro foreign exchange reference rates of the European
+/// Central Bank, or any source serving files in the same XML format.
+///
+/// The base URL may be a `file://` URL of a directory holding the files.
+pub struct EcbConnector {
+    http: HttpClient,
+    base_url: String,
+}
+
+impl Default for EcbConnector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EcbConnector {
+    pub fn new() -> Self {
+        Self::with_base_url(ECB_RATES_URL)
+    }
+
+    /// Create a connector reading the rate files from another location
+    pub fn with_base_url(base_url: &str) -> Self {
+        Self {
+            http: HttpClient::default(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    // Read a rate file from the base URL
+    async fn fetch(&self, file: &str) -> Result<String> {
+        if let Some(dir) = self.base_url.strip_prefix("file://") {
+            let path = std::path::Path::new(dir).join(file);
+            debug!("Reading ECB rates from {}", path.display());
+            return tokio::fs::read_to_string(&path).await.map_err(|e| {
+                Error::ExchangeError(format!("Failed to read {}: {}", path.display(), e))
+            });
+        }
+
+        let url = format!("{}/{}", self.base_url, file);
+        debug!("Fetching ECB rates from {}", url);
+
+        let response = self.http.get(&url, &[]).await?.error_for_status("ECB")?;
+
+        Ok(response.body)
+    }
+
+    /// Send requests through a client, e.g. one recording or replaying them
+    pub fn with_http(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+}
+
+// Parse the daily rates of an ECB reference rate file, oldest first.
+//
+// Rates are in `Cube` elements: one per day with a `time` attribute, holding
+// one per currency with `currency` and `rate` attributes.
+fn parse_rates(xml: &str) -> Result<Vec<FxRates>> {
+    let document = roxmltree::Document::parse(xml)
+        .map_err(|e| Error::ParseError(format!("Failed to parse ECB rates: {}", e)))?;
+
+    let mut days = Vec::new();
+
+    for day in document
+        .descendants()
+        .filter(|node| node.has_tag_name("Cube") && node.has_attribute("time"))
+    {
+        let time = day.attribute("time").unwrap_or_default();
+        let date = NaiveDate::parse_from_str(time, "%Y-%m-%d")
+            .map_err(|e| Error::ParseError(format!("Invalid ECB rate date {}: {}", time, e)))?;
+
+        let mut rates = BTreeMap::new();
+        for cube in day.children().filter(|node| node.has_tag_name("Cube")) {
+            let (Some(currency), Some(rate)) = (cube.attribute("currency"), cube.attribute("rate"))
+            else {
+                continue;
+            };
+            let rate = rate.parse::<Decimal>().map_err(|e| {
+                Error::ParseError(format!("Invalid ECB rate for {}: {}", currency, e))
+            })?;
+            if rate > Decimal::ZERO {
+                rates.insert(currency.to_uppercase(), rate);
+            }
+        }
+
+        days.push(FxRates {
+            source: "ecb".to_string(),
+            base: "EUR".to_string(),
+            date,
+            rates,
+        });
+    }
+
+    days.sort_by_key(|day| day.date);
+    Ok(days)
+}
+
+#[async_trait]
+impl FxRateConnector for EcbConnector {
+    fn source(&self) -> &str {
+        "ecb"
+    }
+
+    async fn get_latest_rates(&self) -> Result<FxRates> {
+        let xml = self.fetch(DAILY_FILE).await?;
+
+        parse_rates(&xml)?
+            .pop()
+            .ok_or_else(|| Error::NotFound("No rates in ECB daily rates".to_string()))
+    }
+
+    async fn get_rate_history(&self, start: NaiveDate) -> Result<Vec<FxRates>> {
+        // The 90-day file is far smaller; leave a margin for its publication lag
+        let file = if start >= Utc::now().date_naive() - Duration::days(85) {
+            HISTORY_90D_FILE
+        } else {
+            HISTORY_FILE
+        };
+
+        let xml = self.fetch(file).await?;
+        let mut days = parse_rates(&xml)?;
+        days.retain(|day| day.date >= start);
+
+        Ok(days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads the sample rate files checked in with the tests
+    fn connector() -> EcbConnector {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/ecb");
+        EcbConnector::with_base_url(&format!("file://{}", dir))
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        value.parse().unwrap()
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn history_is_parsed_oldest_first() {
+        let days = connector()
+            .get_rate_history(date("2024-03-07"))
+            .await
+            .unwrap();
+
+        let dates: Vec<NaiveDate> = days.iter().map(|day| day.date).collect();
+        assert_eq!(dates, [date("2024-03-07"), date("2024-03-08")]);
+
+        let day = &days[0];
+        assert_eq!((day.source.as_str(), day.base.as_str()), ("ecb", "EUR"));
+        assert_eq!(
+            day.rates.keys().collect::<Vec<_>>(),
+            ["CHF", "GBP", "JPY", "USD"]
+        );
+        assert_eq!(day.rates["USD"], decimal("1.0895"));
+        assert_eq!(day.rates["JPY"], decimal("161.02"));
+    }
+
+    #[tokio::test]
+    async fn latest_rates_come_from_the_daily_file() {
+        let day = connector().get_latest_rates().await.unwrap();
+
+        assert_eq!(day.date, date("2024-03-08"));
+        assert_eq!(day.rates["GBP"], decimal("0.85098"));
+    }
+
+    #[test]
+    fn invalid_rates_are_rejected() {
+        let xml = |rate: &str| {
+            format!(
+                r#"<Envelope><Cube><Cube time="2024-03-08"><Cube currency="USD" rate="{}"/></Cube></Cube></Envelope>"#,
+                rate
+            )
+        };
+
+        assert!(parse_rates(&xml("1.0938")).is_ok());
+        assert!(matches!(
+            parse_rates(&xml("n/a")),
+            Err(Error::ParseError(_))
+        ));
+        // Non-positive rates are left out
+        assert!(parse_rates(&xml("0")).unwrap()[0].rates.is_empty());
+        assert!(parse_rates("<Envelope>").is_err());
+    }
+}
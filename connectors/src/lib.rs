@@ -1,10 +1,25 @@
 pub mod binance;
+pub mod binance_account;
+pub mod binance_futures;
+pub mod breaker;
 pub mod coinbase;
+pub mod coinbase_account;
+pub mod coinbase_advanced;
+pub mod dex;
+pub mod ecb;
+pub mod http;
+pub mod rest;
+pub mod signing;
+pub mod simulated;
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use common::{
-    models::{CurrentPrice, PriceHistory, PriceInterval, TradingPair},
+    models::{
+        AccountBalance, ConnectorHealth, CurrentPrice, Fill, FundingRateHistory, FxRates,
+        MarkPrice, MarketInfo, OpenInterest, PerpetualHistory, PriceHistory, PriceInterval, Trade,
+        TradingPair,
+    },
     Result,
 };
 
@@ -24,6 +39,91 @@ pub trait ExchangeConnector: Send + Sync {
         limit: Option<usize>,
     ) -> Result<PriceHistory>;
 
-    /// List supported trading pairs
-    async fn list_trading_pairs(&self) -> Result<Vec<TradingPair>>;
-} 
\ No newline at end of file
+    /// Get the most recent trades for a trading pair, newest first
+    async fn get_recent_trades(
+        &self,
+        pair: &TradingPair,
+        limit: Option<usize>,
+    ) -> Result<Vec<Trade>>;
+
+    /// List all markets on the exchange with their trading rules and status
+    async fn list_markets(&self) -> Result<Vec<MarketInfo>>;
+
+    /// Get the current time on the exchange's clock
+    async fn get_server_time(&self) -> Result<DateTime<Utc>>;
+
+    /// Health of the connection to the exchange, if the connector tracks it
+    fn health(&self) -> Option<ConnectorHealth> {
+        None
+    }
+
+    /// List trading pairs that are currently tradable
+    async fn list_trading_pairs(&self) -> Result<Vec<TradingPair>> {
+        let markets = self.list_markets().await?;
+
+        Ok(markets
+            .into_iter()
+            .filter(|market| market.status.is_tradable())
+            .map(|market| market.pair)
+            .collect())
+    }
+}
+
+/// Trait defining the interface for derivatives exchange API clients
+#[async_trait]
+pub trait DerivativesConnector: Send + Sync {
+    /// Get the mark and index price of a perpetual contract
+    async fn get_mark_price(&self, pair: &TradingPair) -> Result<MarkPrice>;
+
+    /// Get the open interest of a perpetual contract
+    async fn get_open_interest(&self, pair: &TradingPair) -> Result<OpenInterest>;
+
+    /// Get the funding payments of a perpetual contract within a specified
+    /// time range, newest first
+    async fn get_funding_rates(
+        &self,
+        pair: &TradingPair,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<FundingRateHistory>;
+
+    /// Get price history of a perpetual contract within a specified time range
+    async fn get_perpetual_history(
+        &self,
+        pair: &TradingPair,
+        interval: PriceInterval,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PerpetualHistory>;
+}
+
+/// Trait defining the interface for authenticated exchange account clients
+#[async_trait]
+pub trait AccountConnector: Send + Sync {
+    /// Get the non-zero balances of the account
+    async fn get_balances(&self) -> Result<Vec<AccountBalance>>;
+
+    /// Get the account's fills on a trading pair since a time, or its most
+    /// recent fills, newest first
+    async fn get_fills(
+        &self,
+        pair: &TradingPair,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Fill>>;
+}
+
+/// Trait defining the interface for fiat exchange rate sources
+#[async_trait]
+pub trait FxRateConnector: Send + Sync {
+    /// Name of the rate source, reported with converted prices
+    fn source(&self) -> &str;
+
+    /// Get the most recently published daily rates
+    async fn get_latest_rates(&self) -> Result<FxRates>;
+
+    /// Get the daily rates of every day since `start` that rates were
+    /// published for, oldest first
+    async fn get_rate_history(&self, start: NaiveDate) -> Result<Vec<FxRates>>;
+}
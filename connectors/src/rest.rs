@@ -0,0 +1,931 @@
+use crate::http::HttpClient;
+use crate::ExchangeConnector;
+use async_trait::async_trait;
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
+use common::{
+    models::{
+        CurrentPrice, Exchange, MarketInfo, MarketStatus, PriceHistory, PriceHistoryPoint,
+        PriceInterval, Trade, TradeSide, TradingPair,
+    },
+    Error, Result,
+};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::str::FromStr;
+use tracing::debug;
+
+/// Description of an exchange's public REST API, from which a
+/// [`RestConnector`] is built.
+///
+/// Endpoint paths are appended to `base_url`. Paths and query values are
+/// templates whose `{name}` placeholders are filled in per request; a query
+/// parameter whose placeholder has no value (e.g. `{start}` when no start
+/// time is requested) is left out. Fields are located in responses with
+/// dot-separated JSON paths of object keys and array indices, such as
+/// `data.ohlc` or `result.0.price`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RestSpec {
+    /// Exchange name, used to select the exchange in the API
+    pub name: String,
+    /// URL that endpoint paths are appended to
+    pub base_url: String,
+    /// How a trading pair is written, from `{base}` and `{quote}`
+    #[serde(default = "default_symbol")]
+    pub symbol: String,
+    /// Letter case of symbols
+    #[serde(default)]
+    pub symbol_case: SymbolCase,
+    /// Endpoint serving the current price of a pair
+    pub ticker: TickerSpec,
+    /// Endpoint serving candles, if the exchange has one
+    pub candles: Option<CandleSpec>,
+    /// Endpoint serving recent trades, if the exchange has one
+    pub trades: Option<TradeSpec>,
+    /// Endpoint listing markets, if the exchange has one
+    pub markets: Option<MarketSpec>,
+    /// Endpoint serving the exchange's clock, if the exchange has one
+    pub time: Option<TimeSpec>,
+}
+
+fn default_symbol() -> String {
+    "{base}{quote}".to_string()
+}
+
+/// Letter case of exchange symbols
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SymbolCase {
+    #[default]
+    Upper,
+    Lower,
+}
+
+/// Unit of timestamps in requests and responses
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum TimestampUnit {
+    #[serde(rename = "s")]
+    Seconds,
+    #[default]
+    #[serde(rename = "ms")]
+    Milliseconds,
+    #[serde(rename = "us")]
+    Microseconds,
+    #[serde(rename = "ns")]
+    Nanoseconds,
+    /// RFC 3339 date and time
+    #[serde(rename = "iso")]
+    Iso,
+}
+
+/// Ticker endpoint. Placeholders: `{symbol}`. Field paths may contain
+/// `{symbol}` too, for responses keyed by market.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TickerSpec {
+    pub path: String,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    /// Last trade price
+    pub price: String,
+    pub bid: Option<String>,
+    pub ask: Option<String>,
+    pub open_24h: Option<String>,
+    pub high_24h: Option<String>,
+    pub low_24h: Option<String>,
+    /// Derived from `open_24h` if not given
+    pub change_24h: Option<String>,
+    /// Derived from `open_24h` if not given
+    pub change_percent_24h: Option<String>,
+    pub base_volume_24h: Option<String>,
+    pub volume_24h: Option<String>,
+    /// Time of the ticker, the time of the request if not given
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub time_unit: TimestampUnit,
+}
+
+/// Candle endpoint. Placeholders: `{symbol}`, `{interval}`, `{limit}`,
+/// `{start}` and `{end}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CandleSpec {
+    pub path: String,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    /// Exchange name of each supported interval
+    pub intervals: HashMap<PriceInterval, String>,
+    /// Path of the candle array, the response itself if empty
+    #[serde(default)]
+    pub items: String,
+    /// Open time of a candle
+    pub time: String,
+    /// Close price of a candle
+    pub close: String,
+    pub open: Option<String>,
+    pub high: Option<String>,
+    pub low: Option<String>,
+    pub volume: Option<String>,
+    /// Unit of candle times in the response
+    #[serde(default)]
+    pub time_unit: TimestampUnit,
+    /// Unit of `{start}` and `{end}`, the one of responses if not given
+    pub query_time_unit: Option<TimestampUnit>,
+    /// Number of candles requested when no limit is given
+    pub default_limit: Option<usize>,
+    /// Most candles the exchange returns at once
+    pub max_limit: Option<usize>,
+}
+
+/// Recent trade endpoint. Placeholders: `{symbol}` and `{limit}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TradeSpec {
+    pub path: String,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    /// Path of the trade array, the response itself if empty
+    #[serde(default)]
+    pub items: String,
+    pub id: String,
+    pub price: String,
+    pub size: String,
+    pub time: String,
+    #[serde(default)]
+    pub time_unit: TimestampUnit,
+    /// Field telling the taker side
+    pub side: String,
+    /// Value of `side` for trades where the taker bought
+    #[serde(default = "default_buy")]
+    pub buy: String,
+    /// Number of trades requested when no limit is given
+    pub default_limit: Option<usize>,
+    /// Most trades the exchange returns at once
+    pub max_limit: Option<usize>,
+}
+
+fn default_buy() -> String {
+    "buy".to_string()
+}
+
+/// Market listing endpoint, without placeholders. Pairs are read either
+/// from `base` and `quote`, or from `pair` split at `pair_separator`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MarketSpec {
+    pub path: String,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    /// Path of the market array, the response itself if empty
+    #[serde(default)]
+    pub items: String,
+    /// Exchange-native market identifier
+    pub symbol: String,
+    pub base: Option<String>,
+    pub quote: Option<String>,
+    pub pair: Option<String>,
+    pub pair_separator: Option<String>,
+    /// Field telling whether the market trades; all markets trade if not given
+    pub status: Option<String>,
+    /// Values of `status` for markets open for trading
+    #[serde(default)]
+    pub trading: Vec<String>,
+    pub price_increment: Option<String>,
+    pub size_increment: Option<String>,
+    /// Number of price decimals, used if `price_increment` is not given
+    pub price_decimals: Option<String>,
+    /// Number of size decimals, used if `size_increment` is not given
+    pub size_decimals: Option<String>,
+    pub min_size: Option<String>,
+    pub max_size: Option<String>,
+    pub min_notional: Option<String>,
+}
+
+/// Server time endpoint, without placeholders
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimeSpec {
+    pub path: String,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+    pub time: String,
+    #[serde(default)]
+    pub time_unit: TimestampUnit,
+}
+
+impl RestSpec {
+    /// Read a spec from a TOML or JSON file, chosen by file extension
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).map_err(|e| {
+            Error::ConfigError(format!(
+                "Failed to read connector spec {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        let spec: Self = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&contents).map_err(|e| {
+                Error::ConfigError(format!(
+                    "Invalid connector spec JSON {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?,
+            _ => toml::from_str(&contents).map_err(|e| {
+                Error::ConfigError(format!(
+                    "Invalid connector spec TOML {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?,
+        };
+
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Check the parts of the spec that its format does not enforce
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: String| {
+            Err(Error::ConfigError(format!(
+                "Invalid connector spec '{}': {}",
+                self.name, message
+            )))
+        };
+
+        if !self.symbol.contains("{base}") || !self.symbol.contains("{quote}") {
+            return invalid(format!(
+                "symbol '{}' needs both {{base}} and {{quote}}",
+                self.symbol
+            ));
+        }
+        if let Some(candles) = &self.candles {
+            if candles.intervals.is_empty() {
+                return invalid("candles.intervals is empty".to_string());
+            }
+        }
+        if let Some(markets) = &self.markets {
+            let split = markets.pair.is_some() && markets.pair_separator.is_some();
+            if !split && (markets.base.is_none() || markets.quote.is_none()) {
+                return invalid(
+                    "markets needs base and quote, or pair and pair_separator".to_string(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Connector for an exchange described by a [`RestSpec`], so that exchanges
+/// with a plain public REST API can be added through configuration
+pub struct RestConnector {
+    exchange: Exchange,
+    spec: RestSpec,
+    http: HttpClient,
+}
+
+impl RestConnector {
+    /// Create a connector from a spec, registering its exchange
+    pub fn new(mut spec: RestSpec) -> Result<Self> {
+        spec.validate()?;
+        let exchange = Exchange::register(&spec.name)?;
+        spec.base_url = spec.base_url.trim_end_matches('/').to_string();
+
+        Ok(Self {
+            exchange,
+            spec,
+            http: HttpClient::default(),
+        })
+    }
+
+    /// Send requests through a client, e.g. one recording or replaying them
+    pub fn with_http(mut self, http: HttpClient) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Exchange served by the connector
+    pub fn exchange(&self) -> Exchange {
+        self.exchange
+    }
+
+    fn format_symbol(&self, pair: &TradingPair) -> String {
+        let symbol = self
+            .spec
+            .symbol
+            .replace("{base}", &pair.base)
+            .replace("{quote}", &pair.quote);
+
+        match self.spec.symbol_case {
+            SymbolCase::Upper => symbol.to_uppercase(),
+            SymbolCase::Lower => symbol.to_lowercase(),
+        }
+    }
+
+    // Fill in an endpoint's path and query, then fetch it as JSON
+    async fn fetch(
+        &self,
+        path: &str,
+        query: &BTreeMap<String, String>,
+        vars: &[(&str, Option<String>)],
+    ) -> Result<Value> {
+        let path = fill(path, vars).ok_or_else(|| {
+            Error::ConfigError(format!(
+                "Missing value for a placeholder in {} path {}",
+                self.spec.name, path
+            ))
+        })?;
+        let url = format!("{}{}", self.spec.base_url, path);
+        let params: Vec<(&str, String)> = query
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str(), fill(value, vars)?)))
+            .collect();
+
+        let response = self
+            .http
+            .get(&url, &params)
+            .await?
+            .error_for_status(&self.spec.name)?;
+
+        response.json().map_err(|e| {
+            Error::ParseError(format!(
+                "Failed to parse {} response: {}",
+                self.spec.name, e
+            ))
+        })
+    }
+
+    fn not_supported(&self, what: &str) -> Error {
+        Error::NotFound(format!("{} does not provide {}", self.spec.name, what))
+    }
+}
+
+// Replace the `{name}` placeholders of a template, or None if one has no value
+fn fill(template: &str, vars: &[(&str, Option<String>)]) -> Option<String> {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        let close = open + rest[open..].find('}')?;
+        let name = &rest[open + 1..close];
+        let value = vars.iter().find(|(var, _)| *var == name)?.1.as_deref()?;
+        filled.push_str(&rest[..open]);
+        filled.push_str(value);
+        rest = &rest[close + 1..];
+    }
+
+    filled.push_str(rest);
+    Some(filled)
+}
+
+// Value at a dot-separated path of object keys and array indices
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.')
+        .filter(|key| !key.is_empty())
+        .try_fold(value, |value, key| match value {
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => value.get(key),
+        })
+}
+
+// Array at a path, the empty path being the value itself
+fn items<'a>(value: &'a Value, path: &str, name: &str) -> Result<&'a Vec<Value>> {
+    lookup(value, path)
+        .and_then(Value::as_array)
+        .ok_or_else(|| Error::ParseError(format!("No array at '{}' in {} response", path, name)))
+}
+
+// Exchanges send numbers both as JSON numbers and as strings
+fn decimal(value: &Value) -> Option<Decimal> {
+    let text = match value {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .ok()
+}
+
+fn decimal_at(value: &Value, path: &Option<String>) -> Option<Decimal> {
+    path.as_deref()
+        .and_then(|p| lookup(value, p))
+        .and_then(decimal)
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Null | Value::Array(_) | Value::Object(_) => None,
+        other => Some(other.to_string()),
+    }
+}
+
+// Increment of a number of decimals, e.g. 0.01 for 2
+fn decimals_increment(decimals: Decimal) -> Option<Decimal> {
+    decimals
+        .to_u32()
+        .filter(|&d| d <= 28)
+        .map(|d| Decimal::new(1, d))
+}
+
+fn parse_time(value: &Value, unit: TimestampUnit) -> Option<DateTime<Utc>> {
+    let millis = match unit {
+        TimestampUnit::Iso => {
+            return DateTime::parse_from_rfc3339(value.as_str()?)
+                .ok()
+                .map(|time| time.with_timezone(&Utc))
+        }
+        TimestampUnit::Seconds => decimal(value)? * Decimal::from(1000),
+        TimestampUnit::Milliseconds => decimal(value)?,
+        TimestampUnit::Microseconds => decimal(value)? / Decimal::from(1000),
+        TimestampUnit::Nanoseconds => decimal(value)? / Decimal::from(1_000_000),
+    };
+
+    Utc.timestamp_millis_opt(millis.trunc().to_i64()?).single()
+}
+
+fn format_time(time: DateTime<Utc>, unit: TimestampUnit) -> String {
+    match unit {
+        TimestampUnit::Seconds => time.timestamp().to_string(),
+        TimestampUnit::Milliseconds => time.timestamp_millis().to_string(),
+        TimestampUnit::Microseconds => time.timestamp_micros().to_string(),
+        TimestampUnit::Nanoseconds => (time.timestamp_micros() * 1000).to_string(),
+        TimestampUnit::Iso => time.to_rfc3339_opts(SecondsFormat::Secs, true),
+    }
+}
+
+// Requested number of items, capped at what the exchange allows
+fn request_limit(
+    limit: Option<usize>,
+    default_limit: Option<usize>,
+    max_limit: Option<usize>,
+) -> Option<String> {
+    limit
+        .or(default_limit)
+        .map(|l| max_limit.map_or(l, |max| l.min(max)).to_string())
+}
+
+#[async_trait]
+impl ExchangeConnector for RestConnector {
+    async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
+        let spec = &self.spec.ticker;
+        let symbol = self.format_symbol(pair);
+
+        debug!("Fetching ticker from {} for {}", self.spec.name, symbol);
+
+        let vars = [("symbol", Some(symbol.clone()))];
+        let ticker = self.fetch(&spec.path, &spec.query, &vars).await?;
+        let field = |path: Option<&str>| {
+            path.and_then(|p| lookup(&ticker, &p.replace("{symbol}", &symbol)))
+        };
+
+        let price = field(Some(&spec.price)).and_then(decimal).ok_or_else(|| {
+            Error::ParseError(format!(
+                "No price at '{}' in {} ticker",
+                spec.price, self.spec.name
+            ))
+        })?;
+        let open_24h = field(spec.open_24h.as_deref()).and_then(decimal);
+        let change_24h = field(spec.change_24h.as_deref())
+            .and_then(decimal)
+            .or_else(|| open_24h.map(|open| price - open));
+        let change_percent_24h = field(spec.change_percent_24h.as_deref())
+            .and_then(decimal)
+            .or_else(|| {
+                let open = open_24h.filter(|open| !open.is_zero())?;
+                Some((price - open) / open * Decimal::ONE_HUNDRED)
+            });
+        let timestamp = field(spec.timestamp.as_deref())
+            .and_then(|value| parse_time(value, spec.time_unit))
+            .unwrap_or_else(Utc::now);
+
+        Ok(CurrentPrice {
+            exchange: self.exchange,
+            pair: pair.clone(),
+            price,
+            bid: field(spec.bid.as_deref()).and_then(decimal),
+            ask: field(spec.ask.as_deref()).and_then(decimal),
+            open_24h,
+            high_24h: field(spec.high_24h.as_deref()).and_then(decimal),
+            low_24h: field(spec.low_24h.as_deref()).and_then(decimal),
+            change_24h,
+            change_percent_24h,
+            base_volume_24h: field(spec.base_volume_24h.as_deref()).and_then(decimal),
+            volume_24h: field(spec.volume_24h.as_deref()).and_then(decimal),
+            timestamp,
+            flags: Vec::new(),
+        })
+    }
+
+    async fn get_price_history(
+        &self,
+        pair: &TradingPair,
+        interval: PriceInterval,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PriceHistory> {
+        let spec = self
+            .spec
+            .candles
+            .as_ref()
+            .ok_or_else(|| self.not_supported("candles"))?;
+        let exchange_interval = spec.intervals.get(&interval).ok_or_else(|| {
+            Error::ParseError(format!(
+                "{} does not support the {} interval",
+                self.spec.name, interval
+            ))
+        })?;
+        let query_unit = spec.query_time_unit.unwrap_or(spec.time_unit);
+        let symbol = self.format_symbol(pair);
+
+        debug!(
+            "Fetching price history from {} for {} (interval: {:?})",
+            self.spec.name, symbol, interval
+        );
+
+        let vars = [
+            ("symbol", Some(symbol)),
+            ("interval", Some(exchange_interval.clone())),
+            (
+                "limit",
+                request_limit(limit, spec.default_limit, spec.max_limit),
+            ),
+            ("start", start_time.map(|t| format_time(t, query_unit))),
+            ("end", end_time.map(|t| format_time(t, query_unit))),
+        ];
+        let response = self.fetch(&spec.path, &spec.query, &vars).await?;
+
+        let mut data_points = Vec::new();
+
+        for candle in items(&response, &spec.items, &self.spec.name)? {
+            let timestamp = match lookup(candle, &spec.time)
+                .and_then(|value| parse_time(value, spec.time_unit))
+            {
+                Some(timestamp) => timestamp,
+                None => continue, // Skip malformed candles
+            };
+            let close_price = match lookup(candle, &spec.close).and_then(decimal) {
+                Some(price) => price,
+                None => continue,
+            };
+
+            data_points.push(PriceHistoryPoint {
+                timestamp,
+                price: close_price,
+                open: decimal_at(candle, &spec.open),
+                high: decimal_at(candle, &spec.high),
+                low: decimal_at(candle, &spec.low),
+                volume: decimal_at(candle, &spec.volume),
+            });
+        }
+
+        // Sort by timestamp (newest first)
+        data_points.sort_by_key(|point| std::cmp::Reverse(point.timestamp));
+
+        if let Some(limit) = limit {
+            data_points.truncate(limit);
+        }
+
+        Ok(PriceHistory {
+            exchange: self.exchange,
+            pair: pair.clone(),
+            interval: interval.into(),
+            data: data_points,
+        })
+    }
+
+    async fn get_recent_trades(
+        &self,
+        pair: &TradingPair,
+        limit: Option<usize>,
+    ) -> Result<Vec<Trade>> {
+        let spec = self
+            .spec
+            .trades
+            .as_ref()
+            .ok_or_else(|| self.not_supported("trades"))?;
+        let symbol = self.format_symbol(pair);
+
+        debug!(
+            "Fetching recent trades from {} for {}",
+            self.spec.name, symbol
+        );
+
+        let vars = [
+            ("symbol", Some(symbol)),
+            (
+                "limit",
+                request_limit(limit, spec.default_limit, spec.max_limit),
+            ),
+        ];
+        let response = self.fetch(&spec.path, &spec.query, &vars).await?;
+
+        let mut trades = Vec::new();
+
+        for raw in items(&response, &spec.items, &self.spec.name)? {
+            let field = |path: &str, what: &str| {
+                lookup(raw, path).ok_or_else(|| {
+                    Error::ParseError(format!(
+                        "No trade {} at '{}' in {} response",
+                        what, path, self.spec.name
+                    ))
+                })
+            };
+
+            let trade_id = text(field(&spec.id, "id")?).unwrap_or_default();
+            let price = decimal(field(&spec.price, "price")?)
+                .ok_or_else(|| Error::ParseError("Failed to parse trade price".to_string()))?;
+            let size = decimal(field(&spec.size, "size")?)
+                .ok_or_else(|| Error::ParseError("Failed to parse trade size".to_string()))?;
+            let time = field(&spec.time, "time")?;
+            let timestamp = parse_time(time, spec.time_unit)
+                .ok_or_else(|| Error::ParseError(format!("Invalid trade timestamp: {}", time)))?;
+            let side = if text(field(&spec.side, "side")?).as_deref() == Some(&spec.buy) {
+                TradeSide::Buy
+            } else {
+                TradeSide::Sell
+            };
+
+            trades.push(Trade {
+                exchange: self.exchange,
+                pair: pair.clone(),
+                trade_id,
+                price,
+                size,
+                side,
+                timestamp,
+            });
+        }
+
+        trades.sort_by_key(|trade| std::cmp::Reverse(trade.timestamp));
+
+        if let Some(limit) = limit {
+            trades.truncate(limit);
+        }
+
+        Ok(trades)
+    }
+
+    async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+        let spec = self
+            .spec
+            .markets
+            .as_ref()
+            .ok_or_else(|| self.not_supported("a market list"))?;
+
+        debug!("Fetching markets from {}", self.spec.name);
+
+        let response = self.fetch(&spec.path, &spec.query, &[]).await?;
+
+        let pair_of = |market: &Value| -> Option<TradingPair> {
+            let (base, quote) = match (&spec.pair, &spec.pair_separator) {
+                (Some(path), Some(separator)) => {
+                    let pair = lookup(market, path)?.as_str()?;
+                    let (base, quote) = pair.split_once(separator.as_str())?;
+                    (base.to_string(), quote.to_string())
+                }
+                _ => (
+                    text(lookup(market, spec.base.as_deref()?)?)?,
+                    text(lookup(market, spec.quote.as_deref()?)?)?,
+                ),
+            };
+
+            Some(TradingPair {
+                base: base.to_uppercase(),
+                quote: quote.to_uppercase(),
+            })
+        };
+
+        let mut markets = Vec::new();
+
+        for market in items(&response, &spec.items, &self.spec.name)? {
+            let (symbol, pair) =
+                match (lookup(market, &spec.symbol).and_then(text), pair_of(market)) {
+                    (Some(symbol), Some(pair)) => (symbol, pair),
+                    _ => continue, // Skip markets missing their identifiers
+                };
+            let status = match &spec.status {
+                None => MarketStatus::Trading,
+                Some(path) => {
+                    let value = lookup(market, path).and_then(text);
+                    if value.is_some_and(|v| spec.trading.contains(&v)) {
+                        MarketStatus::Trading
+                    } else {
+                        MarketStatus::Halted
+                    }
+                }
+            };
+
+            markets.push(MarketInfo {
+                exchange: self.exchange,
+                pair,
+                symbol,
+                status,
+                price_increment: decimal_at(market, &spec.price_increment).or_else(|| {
+                    decimal_at(market, &spec.price_decimals).and_then(decimals_increment)
+                }),
+                size_increment: decimal_at(market, &spec.size_increment).or_else(|| {
+                    decimal_at(market, &spec.size_decimals).and_then(decimals_increment)
+                }),
+                min_size: decimal_at(market, &spec.min_size),
+                max_size: decimal_at(market, &spec.max_size),
+                min_notional: decimal_at(market, &spec.min_notional),
+            });
+        }
+
+        Ok(markets)
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let spec = self
+            .spec
+            .time
+            .as_ref()
+            .ok_or_else(|| self.not_supported("its server time"))?;
+
+        let response = self.fetch(&spec.path, &spec.query, &[]).await?;
+        let time = lookup(&response, &spec.time).ok_or_else(|| {
+            Error::ParseError(format!(
+                "No time at '{}' in {} response",
+                spec.time, self.spec.name
+            ))
+        })?;
+
+        parse_time(time, spec.time_unit)
+            .ok_or_else(|| Error::ParseError(format!("Invalid server time: {}", time)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::replay_fixtures;
+
+    // Connector for a spec file, relative to the crate
+    fn connector(spec: &str) -> RestConnector {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(spec);
+        RestConnector::new(RestSpec::load(&path).unwrap())
+            .unwrap()
+            .with_http(replay_fixtures("rest"))
+    }
+
+    fn bitstamp() -> RestConnector {
+        connector("../exchange.example.toml")
+    }
+
+    fn broken() -> RestConnector {
+        connector("tests/specs/broken.json")
+    }
+
+    fn btc_usd() -> TradingPair {
+        TradingPair {
+            base: "BTC".to_string(),
+            quote: "USD".to_string(),
+        }
+    }
+
+    fn decimal(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    fn parse_error(result: Result<impl std::fmt::Debug>) -> String {
+        match result {
+            Err(Error::ParseError(message)) => message,
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn ticker_reads_the_spec_fields() {
+        let price = bitstamp().get_current_price(&btc_usd()).await.unwrap();
+
+        assert_eq!(price.exchange.to_string(), "bitstamp");
+        assert_eq!(price.price, decimal("61000.5"));
+        assert_eq!(price.bid, Some(decimal("61000")));
+        assert_eq!(price.ask, Some(decimal("61001")));
+        assert_eq!(price.open_24h, Some(decimal("60100")));
+        // Reported by the exchange, not derived from the open
+        assert_eq!(price.change_percent_24h, Some(decimal("1.50")));
+        // Derived from the open
+        assert_eq!(price.change_24h, Some(decimal("900.5")));
+        assert_eq!(price.base_volume_24h, Some(decimal("1234.5")));
+        assert_eq!(price.timestamp.timestamp(), 1760000000);
+    }
+
+    #[tokio::test]
+    async fn history_reads_candles_newest_first() {
+        let history = bitstamp()
+            .get_price_history(&btc_usd(), PriceInterval::OneDay, None, None, Some(3))
+            .await
+            .unwrap();
+
+        // The candle with an invalid time is skipped
+        let closes: Vec<(i64, Decimal)> = history
+            .data
+            .iter()
+            .map(|point| (point.timestamp.timestamp(), point.price))
+            .collect();
+        assert_eq!(
+            closes,
+            [
+                (1759968000, decimal("61000.5")),
+                (1759881600, decimal("60500")),
+                (1759795200, decimal("59500")),
+            ]
+        );
+        assert_eq!(history.data[2].volume, Some(decimal("100.1")));
+    }
+
+    #[tokio::test]
+    async fn trades_and_markets_map_exchange_values() {
+        let trades = bitstamp()
+            .get_recent_trades(&btc_usd(), None)
+            .await
+            .unwrap();
+        let summary: Vec<(&str, TradeSide)> = trades
+            .iter()
+            .map(|trade| (trade.trade_id.as_str(), trade.side))
+            .collect();
+        // Ids and sides may be numbers or strings
+        assert_eq!(summary, [("11", TradeSide::Buy), ("10", TradeSide::Sell)]);
+
+        let markets = bitstamp().list_markets().await.unwrap();
+        let statuses: Vec<(&str, &str, MarketStatus, Option<Decimal>)> = markets
+            .iter()
+            .map(|market| {
+                (
+                    market.symbol.as_str(),
+                    market.pair.base.as_str(),
+                    market.status,
+                    market.price_increment,
+                )
+            })
+            .collect();
+        // The market without a pair is skipped
+        assert_eq!(
+            statuses,
+            [
+                ("btcusd", "BTC", MarketStatus::Trading, Some(decimal("1"))),
+                ("ethusd", "ETH", MarketStatus::Halted, Some(decimal("0.1"))),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_fields_and_intervals_are_reported() {
+        let message = parse_error(broken().get_current_price(&btc_usd()).await);
+        assert_eq!(message, "No price at 'data.last' in broken-rest ticker");
+
+        let message = parse_error(
+            broken()
+                .get_price_history(&btc_usd(), PriceInterval::OneHour, None, None, None)
+                .await,
+        );
+        assert_eq!(message, "No array at 'result' in broken-rest response");
+
+        let message = parse_error(
+            broken()
+                .get_price_history(&btc_usd(), PriceInterval::OneDay, None, None, None)
+                .await,
+        );
+        assert_eq!(message, "broken-rest does not support the 1d interval");
+
+        let message = parse_error(broken().get_recent_trades(&btc_usd(), None).await);
+        assert_eq!(message, "No trade price at 'px' in broken-rest response");
+
+        assert!(matches!(
+            broken().list_markets().await,
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_specs_are_rejected() {
+        let spec = |symbol: &str, markets: &str| {
+            toml::from_str::<RestSpec>(&format!(
+                "name = \"x\"\nbase_url = \"http://x.test\"\nsymbol = \"{}\"\n\
+                 [ticker]\npath = \"/t\"\nprice = \"p\"\n{}",
+                symbol, markets
+            ))
+            .unwrap()
+        };
+
+        assert!(spec("{base}{quote}", "").validate().is_ok());
+        assert!(spec("{base}", "").validate().is_err());
+        assert!(spec(
+            "{base}{quote}",
+            "[markets]\npath = \"/m\"\nsymbol = \"s\"\nbase = \"b\""
+        )
+        .validate()
+        .is_err());
+    }
+}
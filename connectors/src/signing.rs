@@ -0,0 +1,104 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::{Error, Result};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+/// API credentials of an exchange account
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    pub api_key: String,
+    pub api_secret: String,
+    /// Passphrase chosen when creating the key (Coinbase only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+}
+
+// Keep secrets out of logs
+impl std::fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Credentials")
+            .field("api_key", &self.api_key)
+            .finish_non_exhaustive()
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Signature of a Binance SIGNED request: the hex HMAC-SHA256 of its query
+/// string, keyed with the API secret. The example of Binance's documentation:
+///
+/// ```text
+/// secret:    NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j
+/// query:     symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1
+///            &recvWindow=5000&timestamp=1499827319559 (as one line)
+/// signature: c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71
+/// ```
+pub fn binance_signature(secret: &str, query: &str) -> String {
+    hex::encode(hmac_sha256(secret.as_bytes(), query.as_bytes()))
+}
+
+/// Signature of a Coinbase Exchange request: the base64 HMAC-SHA256 of the
+/// timestamp, method, request path (with its query string) and body,
+/// keyed with the base64-decoded API secret
+pub fn coinbase_signature(
+    secret: &str,
+    timestamp: &str,
+    method: &str,
+    request_path: &str,
+    body: &str,
+) -> Result<String> {
+    let key = STANDARD
+        .decode(secret)
+        .map_err(|e| Error::ConfigError(format!("Coinbase API secret is not base64: {}", e)))?;
+    let message = format!("{}{}{}{}", timestamp, method, request_path, body);
+
+    Ok(STANDARD.encode(hmac_sha256(&key, message.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binance_signature_matches_documented_example() {
+        let signature = binance_signature(
+            "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j",
+            "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1\
+             &recvWindow=5000&timestamp=1499827319559",
+        );
+        assert_eq!(
+            signature,
+            "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+    }
+
+    #[test]
+    fn coinbase_signature_keys_with_the_decoded_secret() {
+        // base64 of "secret-key-for-tests"
+        let secret = "c2VjcmV0LWtleS1mb3ItdGVzdHM=";
+        let signature = coinbase_signature(secret, "1700000000", "GET", "/accounts", "").unwrap();
+        assert_eq!(signature, "TfKoO5U5WcsRuvXZt5/du9e/iG+zCNu/3Jam95qvCU8=");
+
+        assert!(matches!(
+            coinbase_signature("not base64!", "1700000000", "GET", "/accounts", ""),
+            Err(Error::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn debug_output_hides_secrets() {
+        let credentials = Credentials {
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+            passphrase: Some("passphrase".to_string()),
+        };
+        let debug = format!("{:?}", credentials);
+        assert!(debug.contains("key"));
+        assert!(!debug.contains("secret") && !debug.contains("passphrase"));
+    }
+}
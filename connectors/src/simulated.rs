@@ -0,0 +1,823 @@
+use crate::http::fnv1a;
+use crate::ExchangeConnector;
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use common::{
+    models::{
+        CurrentPrice, Exchange, MarketInfo, MarketStatus, PriceHistory, PriceHistoryPoint,
+        PriceInterval, Trade, TradeSide, TradingPair,
+    },
+    Error, Result,
+};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+const SECONDS_PER_YEAR: f64 = 365.0 * 86_400.0;
+
+// Step sizes of the nested walks a price path is built from: days, then
+// hours, minutes and seconds bridged between the values of the level above
+const STEPS: [i64; 4] = [86_400, 3_600, 60, 1];
+
+// Unix time of a Monday, where weekly bars start as on the exchanges
+const WEEK_OFFSET: i64 = 4 * 86_400;
+
+/// An asset priced by the simulation, in US dollars
+#[derive(Debug, Clone)]
+pub struct SimulatedAsset {
+    pub symbol: String,
+    /// Price at the start of the simulation
+    pub start_price: f64,
+    /// Annualized volatility; the default volatility if unset, while zero
+    /// keeps the price fixed
+    pub volatility: Option<f64>,
+}
+
+impl SimulatedAsset {
+    fn new(symbol: &str, start_price: f64, volatility: Option<f64>) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            start_price,
+            volatility,
+        }
+    }
+}
+
+/// Settings of the simulated markets
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// Seed of the random walks; the same seed always produces the same prices
+    pub seed: u64,
+    /// Time at which assets are at their start prices
+    pub start: DateTime<Utc>,
+    /// Annualized drift of prices
+    pub drift: f64,
+    /// Annualized volatility of assets without their own
+    pub volatility: f64,
+    /// Average number of price jumps per asset and day
+    pub jumps_per_day: f64,
+    /// Standard deviation of the log size of a jump
+    pub jump_size: f64,
+    /// Average number of outages per exchange and day
+    pub outages_per_day: f64,
+    /// Length of an outage, in seconds
+    pub outage_secs: i64,
+    /// Bid/ask spread in basis points; exchanges also differ by up to this much
+    pub spread_bps: f64,
+    /// Average daily volume of each market, in US dollars
+    pub daily_volume_usd: f64,
+    /// Assets with their start prices; other requested assets get a start
+    /// price derived from the seed
+    pub assets: Vec<SimulatedAsset>,
+    /// Quote currencies every asset is listed against
+    pub quotes: Vec<String>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 42,
+            start: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            drift: 0.0,
+            volatility: 0.6,
+            jumps_per_day: 0.1,
+            jump_size: 0.03,
+            outages_per_day: 0.0,
+            outage_secs: 120,
+            spread_bps: 2.0,
+            daily_volume_usd: 50_000_000.0,
+            assets: vec![
+                SimulatedAsset::new("BTC", 42_000.0, Some(0.5)),
+                SimulatedAsset::new("ETH", 2_300.0, Some(0.65)),
+                SimulatedAsset::new("XRP", 0.6, None),
+                SimulatedAsset::new("ADA", 0.6, None),
+                SimulatedAsset::new("SOL", 100.0, Some(0.8)),
+                SimulatedAsset::new("USD", 1.0, Some(0.0)),
+                SimulatedAsset::new("USDT", 1.0, Some(0.0)),
+                SimulatedAsset::new("USDC", 1.0, Some(0.0)),
+                SimulatedAsset::new("EUR", 1.1, Some(0.07)),
+                SimulatedAsset::new("GBP", 1.27, Some(0.08)),
+            ],
+            quotes: vec!["USD".to_string(), "USDT".to_string(), "EUR".to_string()],
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn from_env() -> Self {
+        let mut config = Self::default();
+        let env = |name: &str| std::env::var(name).ok().filter(|v| !v.is_empty());
+        let env_f64 = |name: &str| {
+            env(name)
+                .and_then(|v| v.parse::<f64>().ok())
+                .filter(|v| *v >= 0.0)
+        };
+
+        if let Some(seed) = env("SIMULATED_SEED").and_then(|v| v.parse().ok()) {
+            config.seed = seed;
+        }
+        if let Some(start) = env("SIMULATED_START").and_then(|v| v.parse().ok()) {
+            config.start = start;
+        }
+        if let Some(drift) = env("SIMULATED_DRIFT").and_then(|v| v.parse().ok()) {
+            config.drift = drift;
+        }
+        config.volatility = env_f64("SIMULATED_VOLATILITY").unwrap_or(config.volatility);
+        config.jumps_per_day = env_f64("SIMULATED_JUMPS_PER_DAY").unwrap_or(config.jumps_per_day);
+        config.jump_size = env_f64("SIMULATED_JUMP_SIZE").unwrap_or(config.jump_size);
+        config.outages_per_day =
+            env_f64("SIMULATED_OUTAGES_PER_DAY").unwrap_or(config.outages_per_day);
+        if let Some(secs) = env("SIMULATED_OUTAGE_SECS").and_then(|v| v.parse().ok()) {
+            config.outage_secs = secs;
+        }
+        config.spread_bps = env_f64("SIMULATED_SPREAD_BPS").unwrap_or(config.spread_bps);
+        config.daily_volume_usd =
+            env_f64("SIMULATED_DAILY_VOLUME_USD").unwrap_or(config.daily_volume_usd);
+
+        // Entries are SYMBOL:PRICE or SYMBOL:PRICE:VOLATILITY, replacing
+        // built-in assets of the same symbol
+        if let Some(assets) = env("SIMULATED_ASSETS") {
+            for entry in assets.split(',') {
+                let parts: Vec<&str> = entry.trim().split(':').collect();
+                let (Some(symbol), Some(Ok(price))) =
+                    (parts.first(), parts.get(1).map(|p| p.parse::<f64>()))
+                else {
+                    continue;
+                };
+                if price <= 0.0 {
+                    continue;
+                }
+                let volatility = parts.get(2).and_then(|v| v.parse().ok());
+                let symbol = symbol.to_uppercase();
+
+                config.assets.retain(|asset| asset.symbol != symbol);
+                config
+                    .assets
+                    .push(SimulatedAsset::new(&symbol, price, volatility));
+            }
+        }
+
+        if let Some(quotes) = env("SIMULATED_QUOTES") {
+            config.quotes = quotes
+                .split(',')
+                .map(|q| q.trim().to_uppercase())
+                .filter(|q| !q.is_empty())
+                .collect();
+        }
+
+        config
+    }
+}
+
+// SplitMix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// Random stream identified by the seed and a name
+fn stream(seed: u64, name: &str) -> u64 {
+    mix(seed ^ fnv1a(name))
+}
+
+// Uniform number in (0, 1) drawn from a stream at a position. Draws depend
+// only on their position, so any part of a path can be computed on its own.
+fn uniform(stream: u64, a: i64, b: i64) -> f64 {
+    let hash = mix(mix(stream ^ a as u64) ^ (b as u64).wrapping_mul(0x632be59bd9b4e019));
+    ((hash >> 11) as f64 + 0.5) / (1u64 << 53) as f64
+}
+
+// Standard normal number, by the Box-Muller transform
+fn normal(stream: u64, a: i64, b: i64) -> f64 {
+    let u1 = uniform(stream, a, 2 * b);
+    let u2 = uniform(stream, a, 2 * b + 1);
+    (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+}
+
+// Poisson number with the given mean, by inversion of one uniform draw
+fn poisson(mean: f64, u: f64) -> usize {
+    let mut probability = (-mean).exp();
+    let mut cumulative = probability;
+    let mut count = 0;
+    while u > cumulative && count < 100 {
+        count += 1;
+        probability *= mean / count as f64;
+        cumulative += probability;
+    }
+    count
+}
+
+// Log price path of an asset since the simulation start.
+//
+// The diffusion is a Brownian motion sampled at day boundaries and filled
+// in by Brownian bridges down to the second, which has the distribution of
+// a walk with one-second steps while only the pieces that are read get
+// computed. Jumps are drawn per day at random times within it.
+struct Path {
+    walk: u64,
+    jumps: u64,
+    log_start: f64,
+    // Diffusion per second, and its drift per second
+    sigma: f64,
+    mu: f64,
+    jumps_per_day: f64,
+    jump_size: f64,
+    // Diffusion and cumulative jumps at the start of days 0, 1, ... and of
+    // days 0, -1, ...
+    forward: Vec<(f64, f64)>,
+    backward: Vec<(f64, f64)>,
+    // Diffusion within a bridged step, by level and index of the step
+    segments: HashMap<(usize, i64), Vec<f64>>,
+}
+
+impl Path {
+    fn new(config: &SimulationConfig, symbol: &str, start_price: f64, volatility: f64) -> Self {
+        let sigma = volatility / SECONDS_PER_YEAR.sqrt();
+        let fixed = volatility == 0.0;
+
+        Self {
+            walk: stream(config.seed, &format!("walk/{}", symbol)),
+            jumps: stream(config.seed, &format!("jumps/{}", symbol)),
+            log_start: start_price.ln(),
+            sigma,
+            mu: if fixed {
+                0.0
+            } else {
+                config.drift / SECONDS_PER_YEAR - sigma * sigma / 2.0
+            },
+            jumps_per_day: if fixed { 0.0 } else { config.jumps_per_day },
+            jump_size: config.jump_size,
+            forward: vec![(0.0, 0.0)],
+            backward: vec![(0.0, 0.0)],
+            segments: HashMap::new(),
+        }
+    }
+
+    // Jumps within a day, as offsets into the day and log sizes
+    fn day_jumps(&self, day: i64) -> Vec<(i64, f64)> {
+        if self.jumps_per_day <= 0.0 {
+            return Vec::new();
+        }
+        let count = poisson(self.jumps_per_day, uniform(self.jumps, day, -1));
+        (0..count as i64)
+            .map(|i| {
+                let offset = (uniform(self.jumps, day, i) * STEPS[0] as f64) as i64;
+                (offset, self.jump_size * normal(mix(self.jumps), day, i))
+            })
+            .collect()
+    }
+
+    // Diffusion increment and total jump of a day
+    fn day_change(&self, day: i64) -> (f64, f64) {
+        let step = STEPS[0] as f64;
+        let diffusion = self.mu * step + self.sigma * step.sqrt() * normal(self.walk, 0, day);
+        let jumps = self.day_jumps(day).iter().map(|(_, size)| size).sum();
+        (diffusion, jumps)
+    }
+
+    // Diffusion and cumulative jumps at the start of a day
+    fn day_start(&mut self, day: i64) -> (f64, f64) {
+        if day >= 0 {
+            while self.forward.len() as i64 <= day {
+                let previous = self.forward.len() as i64 - 1;
+                let (value, jumps) = self.forward[previous as usize];
+                let (d_value, d_jumps) = self.day_change(previous);
+                self.forward.push((value + d_value, jumps + d_jumps));
+            }
+            self.forward[day as usize]
+        } else {
+            while (self.backward.len() as i64) <= -day {
+                let next = -(self.backward.len() as i64 - 1);
+                let (value, jumps) = self.backward[-next as usize];
+                let (d_value, d_jumps) = self.day_change(next - 1);
+                self.backward.push((value - d_value, jumps - d_jumps));
+            }
+            self.backward[-day as usize]
+        }
+    }
+
+    // Diffusion at the start of a step of the given level
+    fn diffusion(&mut self, level: usize, index: i64) -> f64 {
+        if level == 0 {
+            return self.day_start(index).0;
+        }
+
+        let substeps = STEPS[level - 1] / STEPS[level];
+        let parent = index.div_euclid(substeps);
+        let position = index.rem_euclid(substeps) as usize;
+        if position == 0 || (self.sigma == 0.0 && self.mu == 0.0) {
+            return self.diffusion(level - 1, parent);
+        }
+
+        if let Some(segment) = self.segments.get(&(level, parent)) {
+            return segment[position];
+        }
+
+        // Bridge a fresh walk between the values at both ends of the parent step
+        let from = self.diffusion(level - 1, parent);
+        let to = self.diffusion(level - 1, parent + 1);
+        let scale = self.sigma * (STEPS[level] as f64).sqrt();
+        let stream = self.walk ^ mix(level as u64);
+
+        let mut walk = Vec::with_capacity(substeps as usize + 1);
+        walk.push(0.0);
+        for i in 1..=substeps {
+            walk.push(walk[i as usize - 1] + scale * normal(stream, parent, i));
+        }
+
+        let end = walk[substeps as usize];
+        let segment: Vec<f64> = walk
+            .iter()
+            .enumerate()
+            .map(|(i, w)| {
+                let fraction = i as f64 / substeps as f64;
+                from + fraction * (to - from) + w - fraction * end
+            })
+            .collect();
+
+        let value = segment[position];
+        self.segments.insert((level, parent), segment);
+        value
+    }
+
+    // Log price at a number of seconds since the simulation start
+    fn log_price(&mut self, seconds: i64) -> f64 {
+        let day = seconds.div_euclid(STEPS[0]);
+        let offset = seconds.rem_euclid(STEPS[0]);
+
+        let diffusion = self.diffusion(STEPS.len() - 1, seconds);
+        let (_, jumps) = self.day_start(day);
+        let day_jumps: f64 = self
+            .day_jumps(day)
+            .iter()
+            .filter(|(at, _)| *at <= offset)
+            .map(|(_, size)| size)
+            .sum();
+
+        self.log_start + diffusion + jumps + day_jumps
+    }
+}
+
+// Price paths of the two assets of a pair, for the duration of one request
+struct PairPaths {
+    start: i64,
+    base: Path,
+    quote: Path,
+    // Relative offset of the exchange's prices from other exchanges
+    basis: f64,
+}
+
+impl PairPaths {
+    // Price at a Unix time, in seconds
+    fn price(&mut self, time: i64) -> f64 {
+        let seconds = time - self.start;
+        (self.base.log_price(seconds) - self.quote.log_price(seconds)).exp() * (1.0 + self.basis)
+    }
+}
+
+// Open, high, low and close of the path between two Unix times, in seconds
+fn ohlc(paths: &mut PairPaths, from: i64, to: i64, step: i64) -> (f64, f64, f64, f64) {
+    let open = paths.price(from);
+    let close = paths.price(to);
+    let (mut high, mut low) = (open.max(close), open.min(close));
+
+    let mut time = (from.div_euclid(step) + 1) * step;
+    while time < to {
+        let price = paths.price(time);
+        high = high.max(price);
+        low = low.min(price);
+        time += step;
+    }
+
+    (open, high, low, close)
+}
+
+// Largest step giving at least 15 samples of a bar
+fn sample_step(bar_secs: i64) -> i64 {
+    STEPS
+        .into_iter()
+        .find(|step| bar_secs / step >= 15)
+        .unwrap_or(1)
+}
+
+fn interval_secs(interval: PriceInterval) -> i64 {
+    match interval {
+        PriceInterval::OneMinute => 60,
+        PriceInterval::FiveMinutes => 300,
+        PriceInterval::FifteenMinutes => 900,
+        PriceInterval::OneHour => 3_600,
+        PriceInterval::FourHours => 14_400,
+        PriceInterval::OneDay => 86_400,
+        PriceInterval::OneWeek => 604_800,
+    }
+}
+
+// Decimal places of prices and sizes of a market, from its start price
+fn precision(start_price: f64) -> (u32, u32) {
+    let magnitude = start_price.log10().floor() as i64;
+    (
+        (6 - magnitude).clamp(0, 12) as u32,
+        (2 + magnitude).clamp(0, 8) as u32,
+    )
+}
+
+fn to_decimal(value: f64, decimals: u32) -> Decimal {
+    Decimal::from_f64(value)
+        .unwrap_or_default()
+        .round_dp(decimals)
+}
+
+/// Exchange connector serving simulated markets instead of a real exchange.
+///
+/// Every asset follows a seeded geometric random walk with jumps, so prices,
+/// history and trades are consistent with each other and reproducible.
+/// Simulated exchanges share the walks and differ only by a small constant
+/// offset, and can be made to suffer outages, during which every request
+/// fails as a real exchange's would.
+pub struct SimulatedConnector {
+    exchange: Exchange,
+    config: SimulationConfig,
+}
+
+impl SimulatedConnector {
+    /// Create a connector simulating the markets of an exchange
+    pub fn new(exchange: Exchange, config: SimulationConfig) -> Self {
+        Self { exchange, config }
+    }
+
+    // Start price and volatility of an asset, derived from the seed for
+    // assets not in the configuration
+    fn asset(&self, symbol: &str) -> (f64, f64) {
+        match self.config.assets.iter().find(|a| a.symbol == symbol) {
+            Some(asset) => (
+                asset.start_price,
+                asset.volatility.unwrap_or(self.config.volatility),
+            ),
+            None => {
+                let u = uniform(stream(self.config.seed, &format!("asset/{}", symbol)), 0, 0);
+                (10f64.powf(u * 5.0 - 2.0), self.config.volatility)
+            }
+        }
+    }
+
+    fn paths(&self, pair: &TradingPair) -> PairPaths {
+        let (base_price, base_volatility) = self.asset(&pair.base);
+        let (quote_price, quote_volatility) = self.asset(&pair.quote);
+        let basis = stream(
+            self.config.seed,
+            &format!("basis/{}/{}/{}", self.exchange, pair.base, pair.quote),
+        );
+
+        PairPaths {
+            start: self.config.start.timestamp(),
+            base: Path::new(&self.config, &pair.base, base_price, base_volatility),
+            quote: Path::new(&self.config, &pair.quote, quote_price, quote_volatility),
+            basis: (uniform(basis, 0, 0) * 2.0 - 1.0) * self.config.spread_bps / 10_000.0,
+        }
+    }
+
+    fn start_price(&self, pair: &TradingPair) -> f64 {
+        self.asset(&pair.base).0 / self.asset(&pair.quote).0
+    }
+
+    fn symbol(&self, pair: &TradingPair) -> String {
+        match self.exchange {
+            Exchange::Coinbase => format!("{}-{}", pair.base, pair.quote),
+            Exchange::Binance | Exchange::Custom(_) => format!("{}{}", pair.base, pair.quote),
+        }
+    }
+
+    // Fail while the exchange is in a simulated outage
+    fn check_outage(&self, now: i64) -> Result<()> {
+        if self.config.outages_per_day <= 0.0 || self.config.outage_secs <= 0 {
+            return Ok(());
+        }
+
+        // Outages start at random times within hours
+        let outages = stream(self.config.seed, &format!("outages/{}", self.exchange));
+        let probability = (self.config.outages_per_day / 24.0).min(1.0);
+        let hour = now.div_euclid(STEPS[1]);
+        let lookback = self.config.outage_secs / STEPS[1] + 1;
+
+        for h in hour - lookback..=hour {
+            if uniform(outages, h, 0) >= probability {
+                continue;
+            }
+            let start = h * STEPS[1] + (uniform(outages, h, 1) * STEPS[1] as f64) as i64;
+            let end = start + self.config.outage_secs;
+            if (start..end).contains(&now) {
+                return Err(Error::ExchangeStatus {
+                    api: format!("Simulated {}", self.exchange),
+                    status: 503,
+                    body: format!("outage for {} more seconds", end - now),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Volume of a market in an hour, in base currency
+    fn hour_volume(&self, pair: &TradingPair, volumes: u64, hour: i64) -> f64 {
+        let (base_price, _) = self.asset(&pair.base);
+        let average = self.config.daily_volume_usd / 24.0 / base_price;
+        // Log-normal with a mean of one
+        average * (0.5 * normal(volumes, hour, 0) - 0.125).exp()
+    }
+
+    // Volume of a market between two Unix times, in base currency. Hourly
+    // volumes are spread over their minutes by random weights, and evenly
+    // within minutes, so the volume of any bar is the sum of its parts.
+    fn volume(&self, pair: &TradingPair, from: i64, to: i64) -> f64 {
+        let volumes = stream(
+            self.config.seed,
+            &format!("volume/{}/{}/{}", self.exchange, pair.base, pair.quote),
+        );
+        let mut total = 0.0;
+
+        let mut hour = from.div_euclid(STEPS[1]);
+        while hour * STEPS[1] < to {
+            let hour_start = hour * STEPS[1];
+            let hour_volume = self.hour_volume(pair, volumes, hour);
+
+            if from <= hour_start && hour_start + STEPS[1] <= to {
+                total += hour_volume;
+            } else {
+                let weights: Vec<f64> = (0..60).map(|m| uniform(volumes, hour, m + 2)).collect();
+                let weight_sum: f64 = weights.iter().sum();
+
+                for (minute, weight) in weights.iter().enumerate() {
+                    let minute_start = hour_start + minute as i64 * STEPS[2];
+                    let overlap = (minute_start + STEPS[2]).min(to) - minute_start.max(from);
+                    if overlap > 0 {
+                        total +=
+                            hour_volume * weight / weight_sum * overlap as f64 / STEPS[2] as f64;
+                    }
+                }
+            }
+
+            hour += 1;
+        }
+
+        total
+    }
+}
+
+#[async_trait]
+impl ExchangeConnector for SimulatedConnector {
+    async fn get_current_price(&self, pair: &TradingPair) -> Result<CurrentPrice> {
+        let now = Utc::now().timestamp();
+        self.check_outage(now)?;
+
+        let (price_dp, size_dp) = precision(self.start_price(pair));
+        let mut paths = self.paths(pair);
+        let (open, high, low, price) = ohlc(&mut paths, now - STEPS[0], now, STEPS[2]);
+        let half_spread = self.config.spread_bps / 20_000.0;
+        let base_volume = self.volume(pair, now - STEPS[0], now);
+
+        let price_decimal = to_decimal(price, price_dp);
+        let open_decimal = to_decimal(open, price_dp);
+        let change = price_decimal - open_decimal;
+
+        Ok(CurrentPrice {
+            exchange: self.exchange,
+            pair: pair.clone(),
+            price: price_decimal,
+            bid: Some(to_decimal(price * (1.0 - half_spread), price_dp)),
+            ask: Some(to_decimal(price * (1.0 + half_spread), price_dp)),
+            open_24h: Some(open_decimal),
+            high_24h: Some(to_decimal(high, price_dp)),
+            low_24h: Some(to_decimal(low, price_dp)),
+            change_24h: Some(change),
+            change_percent_24h: change
+                .checked_div(open_decimal)
+                .map(|c| (c * Decimal::ONE_HUNDRED).round_dp(3)),
+            base_volume_24h: Some(to_decimal(base_volume, size_dp)),
+            volume_24h: Some(to_decimal(base_volume * price, price_dp)),
+            timestamp: Utc.timestamp_opt(now, 0).unwrap(),
+            flags: Vec::new(),
+        })
+    }
+
+    async fn get_price_history(
+        &self,
+        pair: &TradingPair,
+        interval: PriceInterval,
+        start_time: Option<DateTime<Utc>>,
+        end_time: Option<DateTime<Utc>>,
+        limit: Option<usize>,
+    ) -> Result<PriceHistory> {
+        let now = Utc::now().timestamp();
+        self.check_outage(now)?;
+
+        let secs = interval_secs(interval);
+        let offset = if interval == PriceInterval::OneWeek {
+            WEEK_OFFSET
+        } else {
+            0
+        };
+        let bar_start = |time: i64| (time - offset).div_euclid(secs) * secs + offset;
+
+        // Bars opening between the start and end, the latest ones unless a
+        // start is given
+        let limit = limit.unwrap_or(300).min(1000) as i64;
+        let mut last = bar_start(end_time.map_or(now, |end| end.timestamp().min(now)));
+        let first = match start_time {
+            Some(start) => bar_start(start.timestamp() + secs - 1),
+            None => last - (limit - 1) * secs,
+        };
+        last = last.min(first + (limit - 1) * secs);
+
+        let (price_dp, size_dp) = precision(self.start_price(pair));
+        let mut paths = self.paths(pair);
+        let step = sample_step(secs);
+
+        let mut data = Vec::new();
+        let mut open_time = last;
+        while open_time >= first {
+            let close_time = (open_time + secs).min(now);
+            let (open, high, low, close) = ohlc(&mut paths, open_time, close_time, step);
+
+            data.push(PriceHistoryPoint {
+                timestamp: Utc.timestamp_opt(open_time, 0).unwrap(),
+                price: to_decimal(close, price_dp),
+                open: Some(to_decimal(open, price_dp)),
+                high: Some(to_decimal(high, price_dp)),
+                low: Some(to_decimal(low, price_dp)),
+                volume: Some(to_decimal(
+                    self.volume(pair, open_time, close_time),
+                    size_dp,
+                )),
+            });
+
+            open_time -= secs;
+        }
+
+        Ok(PriceHistory {
+            exchange: self.exchange,
+            pair: pair.clone(),
+            interval: interval.into(),
+            data,
+        })
+    }
+
+    async fn get_recent_trades(
+        &self,
+        pair: &TradingPair,
+        limit: Option<usize>,
+    ) -> Result<Vec<Trade>> {
+        let now = Utc::now().timestamp();
+        self.check_outage(now)?;
+
+        let limit = limit.unwrap_or(100).min(1000);
+        let (price_dp, size_dp) = precision(self.start_price(pair));
+        let half_spread = self.config.spread_bps / 20_000.0;
+        let trades_stream = stream(
+            self.config.seed,
+            &format!("trades/{}/{}/{}", self.exchange, pair.base, pair.quote),
+        );
+        let mut paths = self.paths(pair);
+
+        // About one trade every two seconds, newest first
+        let mut trades = Vec::with_capacity(limit);
+        let mut time = now;
+        while trades.len() < limit && time > now - 20 * limit as i64 {
+            if uniform(trades_stream, time, 0) < 0.5 {
+                let side = if uniform(trades_stream, time, 1) < 0.5 {
+                    TradeSide::Buy
+                } else {
+                    TradeSide::Sell
+                };
+                let mid = paths.price(time);
+                let price = match side {
+                    TradeSide::Buy => mid * (1.0 + half_spread),
+                    TradeSide::Sell => mid * (1.0 - half_spread),
+                };
+                let size =
+                    self.volume(pair, time, time + 1) * 4.0 * uniform(trades_stream, time, 2);
+                let size = to_decimal(size, size_dp);
+
+                if !size.is_zero() {
+                    trades.push(Trade {
+                        exchange: self.exchange,
+                        pair: pair.clone(),
+                        trade_id: time.to_string(),
+                        price: to_decimal(price, price_dp),
+                        size,
+                        side,
+                        timestamp: Utc.timestamp_opt(time, 0).unwrap(),
+                    });
+                }
+            }
+            time -= 1;
+        }
+
+        Ok(trades)
+    }
+
+    async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
+        self.check_outage(Utc::now().timestamp())?;
+
+        let mut markets = Vec::new();
+
+        for asset in &self.config.assets {
+            for quote in &self.config.quotes {
+                if asset.symbol == *quote {
+                    continue;
+                }
+
+                let pair = TradingPair {
+                    base: asset.symbol.clone(),
+                    quote: quote.clone(),
+                };
+                let (price_dp, size_dp) = precision(self.start_price(&pair));
+                let size_increment = Decimal::new(1, size_dp);
+
+                markets.push(MarketInfo {
+                    exchange: self.exchange,
+                    symbol: self.symbol(&pair),
+                    pair,
+                    status: MarketStatus::Trading,
+                    price_increment: Some(Decimal::new(1, price_dp)),
+                    size_increment: Some(size_increment),
+                    min_size: Some(size_increment),
+                    max_size: None,
+                    min_notional: Some(Decimal::ONE),
+                });
+            }
+        }
+
+        Ok(markets)
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        self.check_outage(Utc::now().timestamp())?;
+        Ok(Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn btc_usd() -> TradingPair {
+        TradingPair {
+            base: "BTC".to_string(),
+            quote: "USD".to_string(),
+        }
+    }
+
+    async fn history(config: SimulationConfig, interval: PriceInterval) -> PriceHistory {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2024, 3, 3, 0, 0, 0).unwrap();
+        SimulatedConnector::new(Exchange::Binance, config)
+            .get_price_history(&btc_usd(), interval, Some(start), Some(end), None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn same_seed_and_start_give_identical_history() {
+        let config = SimulationConfig {
+            seed: 7,
+            start: Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap(),
+            ..SimulationConfig::default()
+        };
+
+        let first = history(config.clone(), PriceInterval::OneHour).await;
+        let second = history(config.clone(), PriceInterval::OneHour).await;
+        assert_eq!(first.data.len(), 49);
+        assert_eq!(
+            serde_json::to_value(&first.data).unwrap(),
+            serde_json::to_value(&second.data).unwrap()
+        );
+
+        let reseeded = history(
+            SimulationConfig { seed: 8, ..config },
+            PriceInterval::OneHour,
+        )
+        .await;
+        assert_ne!(
+            serde_json::to_value(&first.data).unwrap(),
+            serde_json::to_value(&reseeded.data).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn consecutive_bars_join() {
+        for interval in [PriceInterval::FiveMinutes, PriceInterval::OneHour] {
+            let history = history(SimulationConfig::default(), interval).await;
+            assert!(history.data.len() > 1);
+
+            // Bars are newest first, so each bar opens at its successor's close
+            for pair in history.data.windows(2) {
+                let (newer, older) = (&pair[0], &pair[1]);
+                assert_eq!(newer.timestamp - older.timestamp, interval.duration());
+                assert_eq!(newer.open, Some(older.price));
+                assert!(newer.low <= newer.open && newer.open <= newer.high);
+            }
+        }
+    }
+}
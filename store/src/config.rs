@@ -9,6 +9,11 @@ pub struct StoreConfig {
     pub org: String,
     /// InfluxDB bucket to use for storing data
     pub bucket: String,
+    /// Whether individual trades are persisted to the `price_trades` measurement
+    pub store_trades: bool,
+    /// How old a stored current price may be and still be served instead of
+    /// a new one from the exchange, in seconds
+    pub price_max_age_secs: u64,
 }
 
 impl StoreConfig {
@@ -22,12 +27,21 @@ impl StoreConfig {
             .map_err(|_| "INFLUXDB_ORG environment variable not set")?;
         let bucket = std::env::var("INFLUXDB_BUCKET")
             .map_err(|_| "INFLUXDB_BUCKET environment variable not set")?;
+        let store_trades = std::env::var("INFLUXDB_STORE_TRADES")
+            .map(|v| v == "true" || v == "1")
+            .unwrap_or(false);
+        let price_max_age_secs = std::env::var("INFLUXDB_PRICE_MAX_AGE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60);
 
         Ok(Self {
             url,
             token,
             org,
             bucket,
+            store_trades,
+            price_max_age_secs,
         })
     }
 } 
\ No newline at end of file
@@ -1,6 +1,7 @@
 mod config;
 mod error;
 mod price_store;
+mod record;
 
 pub use config::StoreConfig;
 pub use error::StoreError;
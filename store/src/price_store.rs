@@ -1,12 +1,23 @@
-use crate::{StoreConfig, StoreError};
-use chrono::{DateTime, Utc};
+use crate::{record, StoreConfig, StoreError};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use common::models::{
-    CurrentPrice, Exchange, PriceHistory, PriceHistoryPoint, PriceInterval, TradingPair,
+    AlertRule, ClockSkew, Coin, CoinMetadata, CurrentPrice, EncryptedAccount, Exchange,
+    FundingRateHistory, FxRates, MarkPrice, OpenInterest, PerpetualHistory, Portfolio,
+    PriceHistory, PriceHistoryPoint, PriceInterval, Trade, TradingPair,
 };
 use futures::stream;
-use influxdb2::{Client, models::Query};
-use std::sync::Arc;
-use tracing::{debug, error, info};
+use influxdb2::{models::Query, Client};
+use std::collections::{BTreeMap, HashMap};
+use tracing::debug;
+
+// Escape a value for use inside a Flux string literal, where `"` ends the
+// string, `\` starts an escape and `${` starts an interpolation
+fn flux_string(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('$', "\\$")
+}
 
 pub struct PriceStore {
     client: Client,
@@ -36,15 +47,35 @@ impl PriceStore {
             price.pair.base, price.pair.quote, price.price
         );
 
-        // Create a data point for InfluxDB
-        let point = influxdb2::models::DataPoint::builder("price_current")
+        // Create a data point for InfluxDB. Decimal values are written as strings
+        // to `_decimal` fields so they are stored exactly as the exchange
+        // reported them.
+        let mut builder = influxdb2::models::DataPoint::builder("price_current")
             .tag("exchange", price.exchange.to_string())
             .tag("base", price.pair.base.clone())
             .tag("quote", price.pair.quote.clone())
-            .field("price", price.price)
-            .field("volume_24h", price.volume_24h.unwrap_or(0.0))
-            .timestamp(price.timestamp.timestamp_nanos())
-            .build()?;
+            .field(record::decimal_field("price"), price.price.to_string())
+            .timestamp(price.timestamp.timestamp_nanos_opt().unwrap_or_default());
+
+        // Only write the statistics the exchange actually reported
+        let optional_fields = [
+            ("bid", price.bid),
+            ("ask", price.ask),
+            ("open_24h", price.open_24h),
+            ("high_24h", price.high_24h),
+            ("low_24h", price.low_24h),
+            ("change_24h", price.change_24h),
+            ("change_percent_24h", price.change_percent_24h),
+            ("base_volume_24h", price.base_volume_24h),
+            ("volume_24h", price.volume_24h),
+        ];
+        for (name, value) in optional_fields {
+            if let Some(value) = value {
+                builder = builder.field(record::decimal_field(name), value.to_string());
+            }
+        }
+
+        let point = builder.build()?;
 
         self.client
             .write(&self.config.bucket, stream::iter(vec![point]))
@@ -64,16 +95,65 @@ impl PriceStore {
         let mut points = Vec::with_capacity(history.data.len());
 
         for point in &history.data {
-            let data_point = influxdb2::models::DataPoint::builder("price_history")
+            let mut builder = influxdb2::models::DataPoint::builder("price_history")
                 .tag("exchange", history.exchange.to_string())
                 .tag("base", history.pair.base.clone())
                 .tag("quote", history.pair.quote.clone())
                 .tag("interval", history.interval.to_string())
-                .field("price", point.price)
-                .field("volume", point.volume.unwrap_or(0.0))
-                .timestamp(point.timestamp.timestamp_nanos())
+                .field(record::decimal_field("price"), point.price.to_string())
+                .field(
+                    record::decimal_field("volume"),
+                    point.volume.unwrap_or_default().to_string(),
+                )
+                .timestamp(point.timestamp.timestamp_nanos_opt().unwrap_or_default());
+
+            for (name, value) in [
+                ("open", point.open),
+                ("high", point.high),
+                ("low", point.low),
+            ] {
+                if let Some(value) = value {
+                    builder = builder.field(record::decimal_field(name), value.to_string());
+                }
+            }
+
+            points.push(builder.build()?);
+        }
+
+        self.client
+            .write(&self.config.bucket, stream::iter(points))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Store trades in the `price_trades` measurement.
+    ///
+    /// This is a no-op unless `store_trades` is enabled in the configuration, since
+    /// trade volume can be far larger than that of the other measurements. The
+    /// trade id is a tag, so trades with the same timestamp are kept as separate
+    /// points instead of overwriting each other.
+    pub async fn store_trades(&self, trades: &[Trade]) -> Result<(), StoreError> {
+        if !self.config.store_trades || trades.is_empty() {
+            return Ok(());
+        }
+
+        debug!("Storing {} trades", trades.len());
+
+        let mut points = Vec::with_capacity(trades.len());
+
+        for trade in trades {
+            let data_point = influxdb2::models::DataPoint::builder("price_trades")
+                .tag("exchange", trade.exchange.to_string())
+                .tag("base", trade.pair.base.clone())
+                .tag("quote", trade.pair.quote.clone())
+                .tag("side", trade.side.to_string())
+                .tag("trade_id", trade.trade_id.clone())
+                .field(record::decimal_field("price"), trade.price.to_string())
+                .field(record::decimal_field("size"), trade.size.to_string())
+                .timestamp(trade.timestamp.timestamp_nanos_opt().unwrap_or_default())
                 .build()?;
-            
+
             points.push(data_point);
         }
 
@@ -84,6 +164,169 @@ impl PriceStore {
         Ok(())
     }
 
+    /// Persist the mark and index price of a perpetual contract to the
+    /// `perp_mark_price` measurement
+    pub async fn store_mark_price(&self, mark: &MarkPrice) -> Result<(), StoreError> {
+        debug!(
+            "Storing mark price: {} {} at {}",
+            mark.pair.base, mark.pair.quote, mark.mark_price
+        );
+
+        let mut builder = influxdb2::models::DataPoint::builder("perp_mark_price")
+            .tag("exchange", mark.exchange.to_string())
+            .tag("base", mark.pair.base.clone())
+            .tag("quote", mark.pair.quote.clone())
+            .field(
+                record::decimal_field("mark_price"),
+                mark.mark_price.to_string(),
+            )
+            .field(
+                record::decimal_field("index_price"),
+                mark.index_price.to_string(),
+            )
+            .timestamp(mark.timestamp.timestamp_nanos_opt().unwrap_or_default());
+
+        let optional_fields = [
+            ("estimated_settle_price", mark.estimated_settle_price),
+            ("last_funding_rate", mark.last_funding_rate),
+            ("interest_rate", mark.interest_rate),
+        ];
+        for (name, value) in optional_fields {
+            if let Some(value) = value {
+                builder = builder.field(record::decimal_field(name), value.to_string());
+            }
+        }
+
+        self.client
+            .write(&self.config.bucket, stream::iter(vec![builder.build()?]))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Persist the open interest of a perpetual contract to the
+    /// `perp_open_interest` measurement
+    pub async fn store_open_interest(&self, interest: &OpenInterest) -> Result<(), StoreError> {
+        debug!(
+            "Storing open interest: {} {} at {}",
+            interest.pair.base, interest.pair.quote, interest.open_interest
+        );
+
+        let mut builder = influxdb2::models::DataPoint::builder("perp_open_interest")
+            .tag("exchange", interest.exchange.to_string())
+            .tag("base", interest.pair.base.clone())
+            .tag("quote", interest.pair.quote.clone())
+            .field(
+                record::decimal_field("open_interest"),
+                interest.open_interest.to_string(),
+            )
+            .timestamp(interest.timestamp.timestamp_nanos_opt().unwrap_or_default());
+
+        if let Some(value) = interest.open_interest_value {
+            builder = builder.field(
+                record::decimal_field("open_interest_value"),
+                value.to_string(),
+            );
+        }
+
+        self.client
+            .write(&self.config.bucket, stream::iter(vec![builder.build()?]))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Persist funding payments of a perpetual contract to the
+    /// `perp_funding_rate` measurement, stamped with the funding time
+    pub async fn store_funding_rates(
+        &self,
+        history: &FundingRateHistory,
+    ) -> Result<(), StoreError> {
+        debug!(
+            "Storing funding rates: {} {} with {} payments",
+            history.pair.base,
+            history.pair.quote,
+            history.data.len()
+        );
+
+        let mut points = Vec::with_capacity(history.data.len());
+
+        for rate in &history.data {
+            let mut builder = influxdb2::models::DataPoint::builder("perp_funding_rate")
+                .tag("exchange", history.exchange.to_string())
+                .tag("base", history.pair.base.clone())
+                .tag("quote", history.pair.quote.clone())
+                .field(
+                    record::decimal_field("funding_rate"),
+                    rate.funding_rate.to_string(),
+                )
+                .timestamp(rate.funding_time.timestamp_nanos_opt().unwrap_or_default());
+
+            if let Some(mark_price) = rate.mark_price {
+                builder =
+                    builder.field(record::decimal_field("mark_price"), mark_price.to_string());
+            }
+
+            points.push(builder.build()?);
+        }
+
+        self.client
+            .write(&self.config.bucket, stream::iter(points))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Persist price history of a perpetual contract to the `perp_history`
+    /// measurement
+    pub async fn store_perpetual_history(
+        &self,
+        history: &PerpetualHistory,
+    ) -> Result<(), StoreError> {
+        debug!(
+            "Storing perpetual history: {} {} with {} points",
+            history.pair.base,
+            history.pair.quote,
+            history.data.len()
+        );
+
+        let mut points = Vec::with_capacity(history.data.len());
+
+        for point in &history.data {
+            let mut builder = influxdb2::models::DataPoint::builder("perp_history")
+                .tag("exchange", history.exchange.to_string())
+                .tag("base", history.pair.base.clone())
+                .tag("quote", history.pair.quote.clone())
+                .tag("interval", history.interval.to_string())
+                .field(record::decimal_field("price"), point.price.to_string())
+                .field(
+                    record::decimal_field("volume"),
+                    point.volume.unwrap_or_default().to_string(),
+                )
+                .timestamp(point.timestamp.timestamp_nanos_opt().unwrap_or_default());
+
+            for (name, value) in [
+                ("open", point.open),
+                ("high", point.high),
+                ("low", point.low),
+            ] {
+                if let Some(value) = value {
+                    builder = builder.field(record::decimal_field(name), value.to_string());
+                }
+            }
+
+            points.push(builder.build()?);
+        }
+
+        self.client
+            .write(&self.config.bucket, stream::iter(points))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Latest stored current price of a pair on each exchange, if no older
+    /// than the configured maximum age
     pub async fn get_current_price(
         &self,
         pair: &TradingPair,
@@ -91,76 +334,73 @@ impl PriceStore {
     ) -> Result<Vec<CurrentPrice>, StoreError> {
         let mut query_str = format!(
             r#"from(bucket: "{}")
-               |> range(start: -1h)
+               |> range(start: -{}s)
                |> filter(fn: (r) => r._measurement == "price_current")
                |> filter(fn: (r) => r.base == "{}" and r.quote == "{}")
                |> last()
                |> pivot(rowKey:["_time"], columnKey: ["_field"], valueColumn: "_value")"#,
-            self.config.bucket, pair.base, pair.quote
+            self.config.bucket,
+            self.config.price_max_age_secs,
+            flux_string(&pair.base),
+            flux_string(&pair.quote)
         );
 
         if let Some(ex) = exchange {
             query_str.push_str(&format!(
                 r#" |> filter(fn: (r) => r.exchange == "{}")"#,
-                ex
+                flux_string(&ex.to_string())
             ));
         }
 
         debug!("Executing InfluxDB query: {}", query_str);
 
-        // For now, we'll always return a simulated result for development purposes
-        // until we can properly parse the query results
-        let mut results = Vec::new();
-        
-        // Mock data for testing
-        if let Some(Exchange::Coinbase) = exchange {
-            results.push(CurrentPrice {
-                exchange: Exchange::Coinbase,
-                pair: pair.clone(),
-                price: 50000.0,  // Simulated price
-                volume_24h: Some(1234.56),
-                timestamp: Utc::now(),
-            });
-        } else if let Some(Exchange::Binance) = exchange {
-            results.push(CurrentPrice {
-                exchange: Exchange::Binance,
-                pair: pair.clone(),
-                price: 50100.0,  // Simulated price
-                volume_24h: Some(2345.67),
-                timestamp: Utc::now(),
-            });
-        } else {
-            // If no exchange specified, return data for both
-            results.push(CurrentPrice {
-                exchange: Exchange::Coinbase,
-                pair: pair.clone(),
-                price: 50000.0,
-                volume_24h: Some(1234.56),
-                timestamp: Utc::now(),
-            });
+        let records = self.client.query_raw(Some(Query::new(query_str))).await?;
+
+        let mut results = Vec::with_capacity(records.len());
+
+        for record in &records {
+            let (Some(exchange), Some(price), Some(timestamp)) = (
+                record::string(record, "exchange").and_then(|e| e.parse::<Exchange>().ok()),
+                record::decimal(record, "price"),
+                record::time(record, "_time"),
+            ) else {
+                continue; // Skip incomplete points
+            };
+
             results.push(CurrentPrice {
-                exchange: Exchange::Binance,
+                exchange,
                 pair: pair.clone(),
-                price: 50100.0,
-                volume_24h: Some(2345.67),
-                timestamp: Utc::now(),
+                price,
+                bid: record::decimal(record, "bid"),
+                ask: record::decimal(record, "ask"),
+                open_24h: record::decimal(record, "open_24h"),
+                high_24h: record::decimal(record, "high_24h"),
+                low_24h: record::decimal(record, "low_24h"),
+                change_24h: record::decimal(record, "change_24h"),
+                change_percent_24h: record::decimal(record, "change_percent_24h"),
+                base_volume_24h: record::decimal(record, "base_volume_24h"),
+                volume_24h: record::decimal(record, "volume_24h"),
+                timestamp,
+                flags: Vec::new(),
             });
         }
-        
+
         Ok(results)
     }
 
+    /// Stored price history of a pair, newest first. Without an exchange in
+    /// the query, the history of the exchange with the newest point is returned.
     pub async fn get_price_history(&self, query: &PriceQuery) -> Result<PriceHistory, StoreError> {
         let start_time = query
             .start_time
             .map(|t| t.to_rfc3339())
             .unwrap_or_else(|| "-7d".to_string());
-            
+
         let end_time = query
             .end_time
             .map(|t| t.to_rfc3339())
             .unwrap_or_else(|| "now()".to_string());
-            
+
         let mut flux_query_str = format!(
             r#"from(bucket: "{}")
                |> range(start: {}, stop: {})
@@ -168,14 +408,18 @@ impl PriceStore {
                |> filter(fn: (r) => r.base == "{}" and r.quote == "{}")
                |> filter(fn: (r) => r.interval == "{}")
                |> pivot(rowKey:["_time"], columnKey: ["_field"], valueColumn: "_value")"#,
-            self.config.bucket, start_time, end_time, query.pair.base, 
-            query.pair.quote, query.interval
+            self.config.bucket,
+            start_time,
+            end_time,
+            flux_string(&query.pair.base),
+            flux_string(&query.pair.quote),
+            query.interval
         );
 
         if let Some(ex) = query.exchange {
             flux_query_str.push_str(&format!(
                 r#" |> filter(fn: (r) => r.exchange == "{}")"#,
-                ex
+                flux_string(&ex.to_string())
             ));
         }
 
@@ -189,45 +433,483 @@ impl PriceStore {
 
         debug!("Executing InfluxDB query: {}", flux_query_str);
 
-        // Generate simulated data for testing
-        let mut data_points = Vec::new();
-        let now = Utc::now();
-        
-        // Create a few data points with test data
-        // Time interval between points will depend on the requested interval
-        let time_step_seconds = match query.interval {
-            PriceInterval::OneMinute => 60,
-            PriceInterval::FiveMinutes => 300,
-            PriceInterval::FifteenMinutes => 900,
-            PriceInterval::OneHour => 3600,
-            PriceInterval::FourHours => 14400,
-            PriceInterval::OneDay => 86400,
-            PriceInterval::OneWeek => 604800,
-        };
-        
-        // Create 10 simulated data points
-        let limit = query.limit.unwrap_or(10);
-        for i in 0..std::cmp::min(limit, 10) {
-            let timestamp = now - chrono::Duration::seconds(time_step_seconds * i as i64);
-            let base_price = 50000.0;
-            
-            // Add some variability to the price
-            let price = base_price * (1.0 + (i as f64 * 0.001) - 0.005);
-            
-            data_points.push(PriceHistoryPoint {
-                timestamp,
-                price,
-                volume: Some(1000.0 + i as f64 * 100.0),
-            });
+        let records = self
+            .client
+            .query_raw(Some(Query::new(flux_query_str)))
+            .await?;
+
+        // Each exchange's points come back as a separate table
+        let mut by_exchange: HashMap<Exchange, Vec<PriceHistoryPoint>> = HashMap::new();
+
+        for record in &records {
+            let (Some(exchange), Some(price), Some(timestamp)) = (
+                record::string(record, "exchange").and_then(|e| e.parse::<Exchange>().ok()),
+                record::decimal(record, "price"),
+                record::time(record, "_time"),
+            ) else {
+                continue; // Skip incomplete points
+            };
+
+            by_exchange
+                .entry(exchange)
+                .or_default()
+                .push(PriceHistoryPoint {
+                    timestamp,
+                    price,
+                    open: record::decimal(record, "open"),
+                    high: record::decimal(record, "high"),
+                    low: record::decimal(record, "low"),
+                    volume: record::decimal(record, "volume"),
+                });
         }
-        
-        let exchange = query.exchange.unwrap_or(Exchange::Coinbase);
-        
+
+        let newest = |data: &Vec<PriceHistoryPoint>| data.first().map(|p| p.timestamp);
+        let exchange = query.exchange.or_else(|| {
+            by_exchange
+                .iter()
+                .max_by_key(|(_, data)| newest(data))
+                .map(|(exchange, _)| *exchange)
+        });
+
+        let Some(exchange) = exchange else {
+            return Err(StoreError::QueryError(format!(
+                "No stored {} history for {}/{}",
+                query.interval, query.pair.base, query.pair.quote
+            )));
+        };
+
         Ok(PriceHistory {
             exchange,
             pair: query.pair.clone(),
-            interval: query.interval,
-            data: data_points,
+            interval: query.interval.into(),
+            data: by_exchange.remove(&exchange).unwrap_or_default(),
         })
     }
-} 
\ No newline at end of file
+
+    /// Persist coin catalog entries to the `coin_catalog` measurement
+    pub async fn store_coins(&self, coins: &[Coin]) -> Result<(), StoreError> {
+        debug!("Storing {} catalog coins", coins.len());
+
+        let now = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let mut points = Vec::with_capacity(coins.len());
+
+        for coin in coins {
+            let exchange_symbols = serde_json::to_string(&coin.exchange_symbols)
+                .map_err(|e| StoreError::ConversionError(e.to_string()))?;
+
+            let data_point = influxdb2::models::DataPoint::builder("coin_catalog")
+                .tag("id", coin.id.clone())
+                .field("name", coin.name.clone())
+                .field("symbol", coin.symbol.clone())
+                .field("exchange_symbols", exchange_symbols)
+                .timestamp(now)
+                .build()?;
+
+            points.push(data_point);
+        }
+
+        self.client
+            .write(&self.config.bucket, stream::iter(points))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Load the most recently persisted coin catalog. Coins left out of it
+    /// by a later `store_coins` are not returned.
+    pub async fn get_coins(&self) -> Result<Vec<Coin>, StoreError> {
+        let query_str = format!(
+            r#"from(bucket: "{}")
+               |> range(start: 0)
+               |> filter(fn: (r) => r._measurement == "coin_catalog")
+               |> last()
+               |> pivot(rowKey:["_time"], columnKey: ["_field"], valueColumn: "_value")"#,
+            self.config.bucket
+        );
+
+        debug!("Executing InfluxDB query: {}", query_str);
+
+        let records = self.client.query_raw(Some(Query::new(query_str))).await?;
+
+        // Every coin of a catalog is written with the same timestamp
+        let latest = records
+            .iter()
+            .filter_map(|r| record::time(r, "_time"))
+            .max();
+        let mut coins = Vec::with_capacity(records.len());
+
+        for record in &records {
+            let (Some(id), Some(name), Some(symbol), true) = (
+                record::string(record, "id"),
+                record::string(record, "name"),
+                record::string(record, "symbol"),
+                record::time(record, "_time") == latest,
+            ) else {
+                continue; // Skip incomplete entries
+            };
+
+            let exchange_symbols = match record::string(record, "exchange_symbols") {
+                Some(json) => serde_json::from_str(&json)
+                    .map_err(|e| StoreError::ConversionError(e.to_string()))?,
+                None => Default::default(),
+            };
+
+            coins.push(Coin {
+                id,
+                name,
+                symbol,
+                exchange_symbols,
+            });
+        }
+
+        Ok(coins)
+    }
+
+    /// Persist metadata of a coin to the `coin_metadata` measurement
+    pub async fn store_coin_metadata(
+        &self,
+        coin_id: &str,
+        metadata: &CoinMetadata,
+    ) -> Result<(), StoreError> {
+        debug!("Storing metadata for coin {}", coin_id);
+
+        let json = serde_json::to_string(metadata)
+            .map_err(|e| StoreError::ConversionError(e.to_string()))?;
+
+        let point = influxdb2::models::DataPoint::builder("coin_metadata")
+            .tag("id", coin_id)
+            .field("metadata", json)
+            .timestamp(Utc::now().timestamp_nanos_opt().unwrap_or_default())
+            .build()?;
+
+        self.client
+            .write(&self.config.bucket, stream::iter(vec![point]))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Load the latest persisted metadata of every coin, keyed by coin ID
+    pub async fn get_coin_metadata(&self) -> Result<HashMap<String, CoinMetadata>, StoreError> {
+        let query_str = format!(
+            r#"from(bucket: "{}")
+               |> range(start: 0)
+               |> filter(fn: (r) => r._measurement == "coin_metadata")
+               |> last()"#,
+            self.config.bucket
+        );
+
+        debug!("Executing InfluxDB query: {}", query_str);
+
+        let records = self.client.query_raw(Some(Query::new(query_str))).await?;
+
+        let mut metadata = HashMap::with_capacity(records.len());
+
+        for record in &records {
+            let (Some(id), Some(json)) = (
+                record::string(record, "id"),
+                record::string(record, "_value"),
+            ) else {
+                continue;
+            };
+
+            let value = serde_json::from_str(&json)
+                .map_err(|e| StoreError::ConversionError(e.to_string()))?;
+            metadata.insert(id, value);
+        }
+
+        Ok(metadata)
+    }
+
+    /// Persist an alert rule to the `alert_rules` measurement
+    pub async fn store_alert_rule(&self, rule: &AlertRule) -> Result<(), StoreError> {
+        debug!("Storing alert rule {}", rule.id);
+
+        let json =
+            serde_json::to_string(rule).map_err(|e| StoreError::ConversionError(e.to_string()))?;
+
+        let point = influxdb2::models::DataPoint::builder("alert_rules")
+            .tag("id", rule.id.clone())
+            .field("rule", json)
+            .timestamp(Utc::now().timestamp_nanos_opt().unwrap_or_default())
+            .build()?;
+
+        self.client
+            .write(&self.config.bucket, stream::iter(vec![point]))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete every stored version of an alert rule
+    pub async fn delete_alert_rule(&self, id: &str) -> Result<(), StoreError> {
+        debug!("Deleting alert rule {}", id);
+        self.delete_by_id("alert_rules", id).await
+    }
+
+    /// Load the latest version of every persisted alert rule
+    pub async fn get_alert_rules(&self) -> Result<Vec<AlertRule>, StoreError> {
+        let query_str = format!(
+            r#"from(bucket: "{}")
+               |> range(start: 0)
+               |> filter(fn: (r) => r._measurement == "alert_rules")
+               |> last()"#,
+            self.config.bucket
+        );
+
+        debug!("Executing InfluxDB query: {}", query_str);
+
+        let records = self.client.query_raw(Some(Query::new(query_str))).await?;
+
+        let mut rules = Vec::with_capacity(records.len());
+
+        for record in &records {
+            let Some(json) = record::string(record, "_value") else {
+                continue;
+            };
+
+            let rule = serde_json::from_str(&json)
+                .map_err(|e| StoreError::ConversionError(e.to_string()))?;
+            rules.push(rule);
+        }
+
+        Ok(rules)
+    }
+
+    /// Persist a portfolio to the `portfolios` measurement
+    pub async fn store_portfolio(&self, portfolio: &Portfolio) -> Result<(), StoreError> {
+        debug!("Storing portfolio {}", portfolio.id);
+
+        let json = serde_json::to_string(portfolio)
+            .map_err(|e| StoreError::ConversionError(e.to_string()))?;
+
+        let point = influxdb2::models::DataPoint::builder("portfolios")
+            .tag("id", portfolio.id.clone())
+            .field("portfolio", json)
+            .timestamp(Utc::now().timestamp_nanos_opt().unwrap_or_default())
+            .build()?;
+
+        self.client
+            .write(&self.config.bucket, stream::iter(vec![point]))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete every stored version of a portfolio
+    pub async fn delete_portfolio(&self, id: &str) -> Result<(), StoreError> {
+        debug!("Deleting portfolio {}", id);
+        self.delete_by_id("portfolios", id).await
+    }
+
+    /// Load the latest version of every persisted portfolio
+    pub async fn get_portfolios(&self) -> Result<Vec<Portfolio>, StoreError> {
+        let query_str = format!(
+            r#"from(bucket: "{}")
+               |> range(start: 0)
+               |> filter(fn: (r) => r._measurement == "portfolios")
+               |> last()"#,
+            self.config.bucket
+        );
+
+        debug!("Executing InfluxDB query: {}", query_str);
+
+        let records = self.client.query_raw(Some(Query::new(query_str))).await?;
+
+        let mut portfolios = Vec::with_capacity(records.len());
+
+        for record in &records {
+            let Some(json) = record::string(record, "_value") else {
+                continue;
+            };
+
+            let portfolio = serde_json::from_str(&json)
+                .map_err(|e| StoreError::ConversionError(e.to_string()))?;
+            portfolios.push(portfolio);
+        }
+
+        Ok(portfolios)
+    }
+
+    /// Persist an exchange account, credentials encrypted, to the
+    /// `exchange_accounts` measurement
+    pub async fn store_account(&self, stored: &EncryptedAccount) -> Result<(), StoreError> {
+        debug!("Storing exchange account {}", stored.account.id);
+
+        let json = serde_json::to_string(stored)
+            .map_err(|e| StoreError::ConversionError(e.to_string()))?;
+
+        let point = influxdb2::models::DataPoint::builder("exchange_accounts")
+            .tag("id", stored.account.id.clone())
+            .field("account", json)
+            .timestamp(Utc::now().timestamp_nanos_opt().unwrap_or_default())
+            .build()?;
+
+        self.client
+            .write(&self.config.bucket, stream::iter(vec![point]))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Delete every stored version of an exchange account
+    pub async fn delete_account(&self, id: &str) -> Result<(), StoreError> {
+        debug!("Deleting exchange account {}", id);
+        self.delete_by_id("exchange_accounts", id).await
+    }
+
+    /// Load the latest version of every persisted exchange account
+    pub async fn get_accounts(&self) -> Result<Vec<EncryptedAccount>, StoreError> {
+        let query_str = format!(
+            r#"from(bucket: "{}")
+               |> range(start: 0)
+               |> filter(fn: (r) => r._measurement == "exchange_accounts")
+               |> last()"#,
+            self.config.bucket
+        );
+
+        debug!("Executing InfluxDB query: {}", query_str);
+
+        let records = self.client.query_raw(Some(Query::new(query_str))).await?;
+
+        let mut accounts = Vec::with_capacity(records.len());
+
+        for record in &records {
+            let Some(json) = record::string(record, "_value") else {
+                continue;
+            };
+
+            let stored = serde_json::from_str(&json)
+                .map_err(|e| StoreError::ConversionError(e.to_string()))?;
+            accounts.push(stored);
+        }
+
+        Ok(accounts)
+    }
+
+    /// Persist daily fiat exchange rates to the `fx_rates` measurement, one
+    /// point per currency stamped with the start of the day
+    pub async fn store_fx_rates(&self, days: &[FxRates]) -> Result<(), StoreError> {
+        debug!("Storing {} days of FX rates", days.len());
+
+        let mut points = Vec::new();
+
+        for day in days {
+            let timestamp = day
+                .date
+                .and_time(NaiveTime::MIN)
+                .and_utc()
+                .timestamp_nanos_opt()
+                .unwrap_or_default();
+
+            for (currency, rate) in &day.rates {
+                let data_point = influxdb2::models::DataPoint::builder("fx_rates")
+                    .tag("source", day.source.clone())
+                    .tag("base", day.base.clone())
+                    .tag("currency", currency.clone())
+                    .field(record::decimal_field("rate"), rate.to_string())
+                    .timestamp(timestamp)
+                    .build()?;
+
+                points.push(data_point);
+            }
+        }
+
+        self.client
+            .write(&self.config.bucket, stream::iter(points))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Load the persisted daily fiat exchange rates since `start`, oldest first
+    pub async fn get_fx_rates(&self, start: NaiveDate) -> Result<Vec<FxRates>, StoreError> {
+        let query_str = format!(
+            r#"from(bucket: "{}")
+               |> range(start: {})
+               |> filter(fn: (r) => r._measurement == "fx_rates")
+               |> pivot(rowKey:["_time"], columnKey: ["_field"], valueColumn: "_value")"#,
+            self.config.bucket,
+            start.and_time(NaiveTime::MIN).and_utc().to_rfc3339()
+        );
+
+        debug!("Executing InfluxDB query: {}", query_str);
+
+        let records = self.client.query_raw(Some(Query::new(query_str))).await?;
+
+        let mut days: BTreeMap<(NaiveDate, String, String), FxRates> = BTreeMap::new();
+
+        for record in &records {
+            let (Some(source), Some(base), Some(currency), Some(rate), Some(time)) = (
+                record::string(record, "source"),
+                record::string(record, "base"),
+                record::string(record, "currency"),
+                record::decimal(record, "rate"),
+                record::time(record, "_time"),
+            ) else {
+                continue;
+            };
+
+            let date = time.date_naive();
+            days.entry((date, source.clone(), base.clone()))
+                .or_insert_with(|| FxRates {
+                    source,
+                    base,
+                    date,
+                    rates: BTreeMap::new(),
+                })
+                .rates
+                .insert(currency, rate);
+        }
+
+        Ok(days.into_values().collect())
+    }
+
+    /// Persist a clock skew sample of an exchange to the `exchange_clock`
+    /// measurement, stamped with the local time it was taken
+    pub async fn store_clock_skew(&self, skew: &ClockSkew) -> Result<(), StoreError> {
+        let data_point = influxdb2::models::DataPoint::builder("exchange_clock")
+            .tag("exchange", skew.exchange.to_string())
+            .field("offset_ms", skew.offset_ms)
+            .field("round_trip_ms", skew.round_trip_ms)
+            .timestamp(skew.sampled_at.timestamp_nanos_opt().unwrap_or_default())
+            .build()?;
+
+        self.client
+            .write(&self.config.bucket, stream::iter(vec![data_point]))
+            .await?;
+
+        Ok(())
+    }
+
+    // Delete all points of a measurement tagged with the given ID
+    async fn delete_by_id(&self, measurement: &str, id: &str) -> Result<(), StoreError> {
+        let start = DateTime::<Utc>::UNIX_EPOCH.naive_utc();
+        let stop = (Utc::now() + chrono::Duration::minutes(1)).naive_utc();
+        let predicate = format!(
+            r#"_measurement="{}" AND id="{}""#,
+            measurement,
+            id.replace('"', "\\\"")
+        );
+
+        self.client
+            .delete(&self.config.bucket, start, stop, Some(predicate))
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flux_string_escapes_quotes_backslashes_and_interpolation() {
+        assert_eq!(flux_string("BTC"), "BTC");
+        assert_eq!(
+            flux_string(r#"BTC" or r.base != "\${x}"#),
+            r#"BTC\" or r.base != \"\\\${x}"#
+        );
+    }
+}
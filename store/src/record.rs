@@ -0,0 +1,45 @@
+use chrono::{DateTime, Utc};
+use influxdb2::api::query::FluxRecord;
+use influxdb2_structmap::value::Value;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+
+// Helpers for reading typed values out of raw Flux query records
+
+pub(crate) fn string(record: &FluxRecord, key: &str) -> Option<String> {
+    match record.values.get(key) {
+        Some(Value::String(value)) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+pub(crate) fn time(record: &FluxRecord, key: &str) -> Option<DateTime<Utc>> {
+    match record.values.get(key) {
+        Some(Value::TimeRFC(value)) => Some(value.with_timezone(&Utc)),
+        _ => None,
+    }
+}
+
+/// Name of the string field an exact decimal value is written to.
+///
+/// Earlier versions wrote these values as floats under the plain name, and
+/// InfluxDB rejects writes that change a field's type, so the exact values get
+/// a field of their own.
+pub(crate) fn decimal_field(name: &str) -> String {
+    format!("{}_decimal", name)
+}
+
+// Exact decimal fields are preferred; floats written by earlier versions
+// under the plain name are read too
+pub(crate) fn decimal(record: &FluxRecord, key: &str) -> Option<Decimal> {
+    let value = record
+        .values
+        .get(&decimal_field(key))
+        .or_else(|| record.values.get(key));
+    match value {
+        Some(Value::String(value)) => value.parse().ok(),
+        Some(Value::Double(value)) => Decimal::from_f64(value.into_inner()),
+        Some(Value::Long(value)) => Some(Decimal::from(*value)),
+        _ => None,
+    }
+}